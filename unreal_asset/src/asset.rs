@@ -453,11 +453,11 @@ pub struct Asset<C: Read + Seek> {
     /// Preload dependency offset
     preload_dependency_offset: i32,
     /// Amount of names referenced from exports
-    names_referenced_from_export_data_count: i32,
+    pub(crate) names_referenced_from_export_data_count: i32,
     /// TOC payload offset
-    payload_toc_offset: i64,
+    pub(crate) payload_toc_offset: i64,
     /// Data resource offset
-    data_resource_offset: i32,
+    pub(crate) data_resource_offset: i32,
 
     /// Overriden name map hashes
     #[container_ignore]
@@ -486,6 +486,20 @@ impl<'a, C: Read + Seek> Asset<C> {
         engine_version: EngineVersion,
         mappings: Option<Usmap>,
     ) -> Result<Self, Error> {
+        let mut asset = Self::new_empty(asset_data, bulk_data, engine_version, mappings);
+        asset.parse_data()?;
+        Ok(asset)
+    }
+
+    /// Create an empty asset without parsing the header
+    ///
+    /// The binary data is only used for reading exports
+    pub(crate) fn new_empty(
+        asset_data: C,
+        bulk_data: Option<C>,
+        engine_version: EngineVersion,
+        mappings: Option<Usmap>,
+    ) -> Self {
         let use_event_driven_loader = bulk_data.is_some();
 
         let chain = Chain::new(asset_data, bulk_data);
@@ -547,8 +561,7 @@ impl<'a, C: Read + Seek> Asset<C> {
         };
         asset.set_engine_version(engine_version);
         asset.asset_data.mappings = mappings;
-        asset.parse_data()?;
-        Ok(asset)
+        asset
     }
 
     /// Set asset engine version
@@ -53,6 +53,7 @@ pub use base::enums;
 pub use base::error;
 pub use base::flags;
 pub use base::import;
+pub use base::io_store;
 pub use base::object_version;
 pub use base::reader;
 pub use base::types;
@@ -89,7 +90,10 @@ pub mod asset_archive_writer;
 pub mod asset_data;
 pub mod fengineversion;
 pub mod package_file_summary;
+pub mod usmap_builder;
+pub mod zen;
 
 pub use asset::Asset;
+pub use zen::ZenAsset;
 
 const UE4_ASSET_MAGIC: u32 = u32::from_be_bytes([0xc1, 0x83, 0x2a, 0x9e]);
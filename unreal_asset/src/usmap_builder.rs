@@ -0,0 +1,427 @@
+//! Usmap mappings generation from cooked assets
+//!
+//! Mappings dumped at runtime only contain types that were loaded when the dump was made,
+//! so blueprint-defined classes, structs and enums are often missing from them.
+//! Cooked assets that define these types still store their property definitions,
+//! [`UsmapBuilder`] collects them into a [`Usmap`].
+
+#[cfg(feature = "pak")]
+use std::io::Cursor;
+use std::io::{Read, Seek};
+
+#[cfg(feature = "pak")]
+use unreal_asset_base::engine_version::EngineVersion;
+use unreal_asset_base::{
+    containers::IndexedMap,
+    custom_version::CustomVersion,
+    enums::EArrayDim,
+    error::Error,
+    object_version::{ObjectVersion, ObjectVersionUE5},
+    reader::ArchiveTrait,
+    types::{fname::ToSerializedName, PackageIndex, PackageIndexTrait},
+    unversioned::{
+        properties::{
+            array_property::UsmapArrayPropertyData, enum_property::UsmapEnumPropertyData,
+            map_property::UsmapMapPropertyData, set_property::UsmapSetPropertyData,
+            shallow_property::UsmapShallowPropertyData, struct_property::UsmapStructPropertyData,
+            EPropertyType, UsmapProperty, UsmapPropertyData,
+        },
+        EUsmapCompressionMethod, EUsmapVersion, Usmap, UsmapExtensionVersion, UsmapSchema,
+    },
+};
+use unreal_asset_exports::{
+    properties::{
+        fproperty::{FProperty, FPropertyTrait},
+        uproperty::{UProperty, UPropertyTrait},
+    },
+    struct_export::StructExport,
+    Export, ExportBaseTrait,
+};
+#[cfg(feature = "pak")]
+use unreal_pak::{PakError, PakReader};
+
+use crate::asset::Asset;
+#[cfg(feature = "pak")]
+use crate::zen::container::get_package_name;
+
+/// Builds a [`Usmap`] from the class, struct and enum exports of cooked assets
+///
+/// Properties are read from `FProperty` definitions, or from `UProperty` exports
+/// for assets from before 4.25.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::io::Cursor;
+///
+/// use unreal_asset::{engine_version::EngineVersion, usmap_builder::UsmapBuilder, Asset};
+///
+/// let asset = Asset::new(
+///     File::open("BP_Example.uasset").unwrap(),
+///     Some(File::open("BP_Example.uexp").unwrap()),
+///     EngineVersion::VER_UE4_27,
+///     None,
+/// )
+/// .unwrap();
+///
+/// let mut builder = UsmapBuilder::new();
+/// builder.add_asset(&asset, Some("/Game/BP_Example")).unwrap();
+///
+/// let mut output = Cursor::new(Vec::new());
+/// builder.build().write(&mut output).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct UsmapBuilder {
+    /// Enums
+    enum_map: IndexedMap<String, Vec<String>>,
+    /// Schemas
+    schemas: IndexedMap<String, UsmapSchema>,
+    /// UE4 object version of the first added asset
+    object_version: ObjectVersion,
+    /// UE5 object version of the first added asset
+    object_version_ue5: ObjectVersionUE5,
+    /// Custom versions of the first added asset
+    custom_versions: Vec<CustomVersion>,
+}
+
+impl UsmapBuilder {
+    /// Create a new, empty `UsmapBuilder`
+    pub fn new() -> Self {
+        UsmapBuilder {
+            enum_map: IndexedMap::new(),
+            schemas: IndexedMap::new(),
+            object_version: ObjectVersion::UNKNOWN,
+            object_version_ue5: ObjectVersionUE5::UNKNOWN,
+            custom_versions: Vec::new(),
+        }
+    }
+
+    /// Add all class, struct and enum types defined by an asset
+    ///
+    /// `package_name` is stored as the module path of the schemas, e.g. `/Game/BP_Example`.
+    /// Types that were already added are replaced.
+    pub fn add_asset<C: Read + Seek>(
+        &mut self,
+        asset: &Asset<C>,
+        package_name: Option<&str>,
+    ) -> Result<(), Error> {
+        if self.object_version == ObjectVersion::UNKNOWN {
+            self.object_version = asset.get_object_version();
+            self.object_version_ue5 = asset.get_object_version_ue5();
+            self.custom_versions = asset.asset_data.summary.custom_versions.clone();
+        }
+
+        for export in &asset.asset_data.exports {
+            let struct_export = match export {
+                Export::ClassExport(class_export) => &class_export.struct_export,
+                Export::StructExport(struct_export) => struct_export,
+                Export::UserDefinedStructExport(user_defined_struct) => {
+                    &user_defined_struct.struct_export
+                }
+                Export::EnumExport(enum_export) => {
+                    let names = enum_export
+                        .value
+                        .names
+                        .iter()
+                        .map(|(name, _)| name.to_string_with_number())
+                        .collect();
+                    self.enum_map.insert(
+                        enum_export
+                            .get_base_export()
+                            .object_name
+                            .to_string_with_number(),
+                        names,
+                    );
+                    continue;
+                }
+                _ => continue,
+            };
+
+            let schema = Self::build_schema(asset, struct_export, package_name)?;
+            self.schemas.insert(schema.name.clone(), schema);
+        }
+
+        Ok(())
+    }
+
+    /// Add the types defined by all assets in a pak file
+    ///
+    /// Assets that fail to parse are skipped and returned together with their entry name.
+    /// Requires the `pak` feature.
+    #[cfg(feature = "pak")]
+    pub fn add_pak<R: Read + Seek>(
+        &mut self,
+        pak: &mut PakReader<R>,
+        engine_version: EngineVersion,
+    ) -> Result<Vec<(String, Error)>, PakError> {
+        let names = pak
+            .get_entry_names()
+            .into_iter()
+            .filter(|e| e.ends_with(".uasset") || e.ends_with(".umap"))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut failed = Vec::new();
+        for name in names {
+            let asset_data = pak.read_entry(&name)?;
+
+            let bulk_name = format!("{}.uexp", &name[..name.rfind('.').unwrap_or(name.len())]);
+            let bulk_data = match pak.contains_entry(&bulk_name) {
+                true => Some(Cursor::new(pak.read_entry(&bulk_name)?)),
+                false => None,
+            };
+
+            let package_name = get_package_name(&format!("{}{}", pak.mount_point, name));
+            let result = Asset::new(Cursor::new(asset_data), bulk_data, engine_version, None)
+                .and_then(|asset| self.add_asset(&asset, package_name.as_deref()));
+            if let Err(err) = result {
+                failed.push((name, err));
+            }
+        }
+
+        Ok(failed)
+    }
+
+    /// Build the [`Usmap`]
+    ///
+    /// The name map is built when writing the usmap,
+    /// module paths are only included if every schema has one.
+    pub fn build(self) -> Usmap {
+        let extension_version = match self.schemas.values().all(|e| e.module_path.is_some()) {
+            true => UsmapExtensionVersion::PATHS,
+            false => UsmapExtensionVersion::NONE,
+        };
+
+        Usmap {
+            version: EUsmapVersion::PackageVersioning,
+            name_map: Vec::new(),
+            enum_map: self.enum_map,
+            schemas: self.schemas,
+            extension_version,
+            object_version: self.object_version,
+            object_version_ue5: self.object_version_ue5,
+            custom_versions: self.custom_versions,
+            compression_method: EUsmapCompressionMethod::None,
+            net_cl: 0,
+        }
+    }
+
+    /// Build a schema from a struct export
+    fn build_schema<C: Read + Seek>(
+        asset: &Asset<C>,
+        struct_export: &StructExport<PackageIndex>,
+        package_name: Option<&str>,
+    ) -> Result<UsmapSchema, Error> {
+        // (name, array size, property data)
+        let mut properties = Vec::new();
+
+        for property in &struct_export.loaded_properties {
+            let generic_property = property.get_generic_property();
+            let name = generic_property.name.to_string_with_number();
+            let array_size = Self::array_size(&name, generic_property.array_dim)?;
+            properties.push((name, array_size, Self::fproperty_data(asset, property)?));
+        }
+
+        // before FProperties were introduced properties were stored as exports
+        for child in &struct_export.children {
+            let Some(Export::PropertyExport(property_export)) = asset.get_export(*child) else {
+                continue;
+            };
+            let name = property_export
+                .get_base_export()
+                .object_name
+                .to_string_with_number();
+            let array_size = Self::array_size(
+                &name,
+                property_export.property.get_generic_property().array_dim,
+            )?;
+            properties.push((name, array_size, Self::uproperty_data(asset, *child)?));
+        }
+
+        let mut schema_properties = IndexedMap::new();
+        let mut schema_index = 0;
+        for (name, array_size, property_data) in properties {
+            let array_size = array_size.max(1);
+            for array_index in 0..array_size as u16 {
+                let property = UsmapProperty {
+                    name: name.clone(),
+                    schema_index: schema_index + array_index,
+                    array_size,
+                    array_index,
+                    property_data: property_data.clone(),
+                };
+                schema_properties.insert(
+                    (property.name.clone(), property.schema_index as u32),
+                    property,
+                );
+            }
+            schema_index += array_size as u16;
+        }
+
+        let super_type = match struct_export.super_struct.index {
+            0 => String::new(),
+            _ => Self::get_object_name(asset, struct_export.super_struct)?,
+        };
+
+        Ok(UsmapSchema {
+            name: struct_export
+                .get_base_export()
+                .object_name
+                .to_string_with_number(),
+            super_type,
+            prop_count: schema_index,
+            module_path: package_name.map(|e| e.to_string()),
+            properties: schema_properties,
+        })
+    }
+
+    /// Convert an `FProperty` to `UsmapPropertyData`
+    fn fproperty_data<C: Read + Seek>(
+        asset: &Asset<C>,
+        property: &FProperty,
+    ) -> Result<UsmapPropertyData, Error> {
+        Ok(match property {
+            FProperty::FEnumProperty(property) => UsmapEnumPropertyData {
+                inner_property: Box::new(Self::fproperty_data(asset, &property.underlying_prop)?),
+                name: Self::get_object_name(asset, property.enum_value)?,
+            }
+            .into(),
+            FProperty::FArrayProperty(property) => UsmapArrayPropertyData {
+                inner_type: Box::new(Self::fproperty_data(asset, &property.inner)?),
+            }
+            .into(),
+            FProperty::FSetProperty(property) => UsmapSetPropertyData {
+                inner_type: Box::new(Self::fproperty_data(asset, &property.element_prop)?),
+            }
+            .into(),
+            FProperty::FMapProperty(property) => UsmapMapPropertyData {
+                inner_type: Box::new(Self::fproperty_data(asset, &property.key_prop)?),
+                value_type: Box::new(Self::fproperty_data(asset, &property.value_prop)?),
+            }
+            .into(),
+            FProperty::FByteProperty(property) => {
+                Self::byte_property_data(asset, property.enum_value)?
+            }
+            FProperty::FStructProperty(property) => UsmapStructPropertyData {
+                struct_type: Self::get_object_name(asset, property.struct_value)?,
+            }
+            .into(),
+            _ => Self::shallow_property_data(&property.to_serialized_name())?,
+        })
+    }
+
+    /// Convert a `UProperty` export to `UsmapPropertyData`
+    fn uproperty_data<C: Read + Seek>(
+        asset: &Asset<C>,
+        index: PackageIndex,
+    ) -> Result<UsmapPropertyData, Error> {
+        let Some(Export::PropertyExport(property_export)) = asset.get_export(index) else {
+            return Err(Error::invalid_package_index(format!(
+                "{index} is not a property export"
+            )));
+        };
+
+        Ok(match &property_export.property {
+            UProperty::UEnumProperty(property) => UsmapEnumPropertyData {
+                inner_property: Box::new(Self::uproperty_data(asset, property.underlying_prop)?),
+                name: Self::get_object_name(asset, property.value)?,
+            }
+            .into(),
+            UProperty::UArrayProperty(property) => UsmapArrayPropertyData {
+                inner_type: Box::new(Self::uproperty_data(asset, property.inner)?),
+            }
+            .into(),
+            UProperty::USetProperty(property) => UsmapSetPropertyData {
+                inner_type: Box::new(Self::uproperty_data(asset, property.element_prop)?),
+            }
+            .into(),
+            UProperty::UMapProperty(property) => UsmapMapPropertyData {
+                inner_type: Box::new(Self::uproperty_data(asset, property.key_prop)?),
+                value_type: Box::new(Self::uproperty_data(asset, property.value_prop)?),
+            }
+            .into(),
+            UProperty::UByteProperty(property) => {
+                Self::byte_property_data(asset, property.enum_value)?
+            }
+            UProperty::UStructProperty(property) => UsmapStructPropertyData {
+                struct_type: Self::get_object_name(asset, property.struct_value)?,
+            }
+            .into(),
+            // the property type is only known from the class of the export
+            _ => Self::shallow_property_data(&Self::get_object_name(
+                asset,
+                property_export.get_base_export().class_index,
+            )?)?,
+        })
+    }
+
+    /// Byte properties with an enum are stored as enum properties in usmaps
+    fn byte_property_data<C: Read + Seek>(
+        asset: &Asset<C>,
+        enum_value: PackageIndex,
+    ) -> Result<UsmapPropertyData, Error> {
+        let byte_property = Self::shallow_property_data("ByteProperty")?;
+        Ok(match enum_value.index {
+            0 => byte_property,
+            _ => UsmapEnumPropertyData {
+                inner_property: Box::new(byte_property),
+                name: Self::get_object_name(asset, enum_value)?,
+            }
+            .into(),
+        })
+    }
+
+    /// Create shallow `UsmapPropertyData` from a property type name
+    fn shallow_property_data(property_type: &str) -> Result<UsmapPropertyData, Error> {
+        let property_type = match property_type {
+            "ClassProperty" => EPropertyType::ObjectProperty,
+            "SoftClassProperty" => EPropertyType::SoftObjectProperty,
+            "MulticastInlineDelegateProperty" | "MulticastSparseDelegateProperty" => {
+                EPropertyType::MulticastDelegateProperty
+            }
+            _ => match property_type.parse() {
+                Ok(EPropertyType::Unknown) | Err(_) => {
+                    return Err(Error::unimplemented(format!(
+                        "Unknown property type {property_type}"
+                    )))
+                }
+                Ok(property_type) => property_type,
+            },
+        };
+        Ok(UsmapShallowPropertyData { property_type }.into())
+    }
+
+    /// Get the usmap array size of a property, usmaps store it as a byte
+    fn array_size(name: &str, array_dim: EArrayDim) -> Result<u8, Error> {
+        let array_dim = i32::from(array_dim);
+        u8::try_from(array_dim).map_err(|_| {
+            Error::invalid_file(format!(
+                "Property {name} has an array size of {array_dim}, more than a usmap can store"
+            ))
+        })
+    }
+
+    /// Get the object name of an import or export
+    fn get_object_name<C: Read + Seek>(
+        asset: &Asset<C>,
+        index: PackageIndex,
+    ) -> Result<String, Error> {
+        let name = match index.is_import() {
+            true => asset.get_import(index).map(|e| e.object_name),
+            false => asset
+                .get_export(index)
+                .map(|e| e.get_base_export().object_name.clone()),
+        };
+
+        name.map(|e| e.to_string_with_number()).ok_or_else(|| {
+            Error::invalid_package_index(format!("Failed to find object with index {index}"))
+        })
+    }
+}
+
+impl Default for UsmapBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,484 @@
+//! Zen package [`ZenAsset`] type
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::io::{Read, Seek, SeekFrom};
+
+use byteorder::{ReadBytesExt, LE};
+
+use unreal_asset_base::{
+    containers::{Chain, IndexedMap, NameMap, SharedResource},
+    custom_version::{CustomVersion, CustomVersionTrait},
+    engine_version::EngineVersion,
+    enums::{EExportCommandType, EZenPackageVersion},
+    error::{Error, ZenError},
+    object_version::{ObjectVersion, ObjectVersionUE5},
+    passthrough_archive_reader,
+    reader::{ArchiveReader, ArchiveTrait, ArchiveType, RawReader},
+    types::{FName, PackageIndex, PackageIndexTrait, PackageObjectIndex},
+    unversioned::Usmap,
+};
+use unreal_asset_exports::{Export, ExportBaseTrait};
+
+use crate::asset_data::{AssetData, AssetTrait, ExportReaderTrait};
+
+use super::export_map::{ExportBundleEntry, ZenExportMapEntry};
+use super::script_objects::ScriptObjects;
+use super::summary::{
+    get_zen_version, BulkDataMapEntry, ZenPackageSummary, ZenPackageVersioningInfo,
+};
+
+/// Unreal Engine 5 Zen package
+///
+/// Zen packages are the cooked package format used inside IoStore containers,
+/// the package header replaces the uasset header and export data follows it directly.
+pub struct ZenAsset<C: Read + Seek> {
+    /// Raw reader
+    pub raw_reader: RawReader<PackageObjectIndex, C>,
+    /// Asset data
+    pub asset_data: AssetData<PackageObjectIndex>,
+    /// Zen package version
+    pub zen_version: EZenPackageVersion,
+    /// Package summary
+    pub summary: ZenPackageSummary,
+    /// Versioning info
+    pub versioning_info: Option<ZenPackageVersioningInfo>,
+    /// Name map hash version
+    pub name_map_hash_version: u64,
+    /// Bulk data map
+    pub bulk_data_map: Vec<BulkDataMapEntry>,
+    /// Imported public export hashes
+    pub imported_public_export_hashes: Vec<u64>,
+    /// Import map
+    pub import_map: Vec<PackageObjectIndex>,
+    /// Export map
+    pub export_map: Vec<ZenExportMapEntry>,
+    /// Export bundle entries
+    pub export_bundle_entries: Vec<ExportBundleEntry>,
+    /// Imported package names, only present since [`EZenPackageVersion::ImportedPackageNames`]
+    pub imported_package_names: Vec<FName>,
+
+    /// Name map
+    name_map: SharedResource<NameMap>,
+    /// Resolved script import names
+    script_import_names: HashMap<PackageObjectIndex, FName>,
+}
+
+impl<'a, C: Read + Seek> ZenAsset<C> {
+    /// Create a Zen asset from a binary file
+    ///
+    /// # Arguments
+    ///
+    /// * `asset_data` - Zen package data, as stored in an `ExportBundleData` chunk
+    /// * `engine_version` - engine version the package was cooked with
+    /// * `mappings` - mappings used for reading unversioned properties
+    /// * `script_objects` - global script objects used for resolving script imports
+    pub fn new(
+        asset_data: C,
+        engine_version: EngineVersion,
+        mappings: Option<Usmap>,
+        script_objects: Option<&ScriptObjects>,
+    ) -> Result<Self, Error> {
+        let zen_version = get_zen_version(engine_version).ok_or(ZenError::NoObjectVersion)?;
+
+        let name_map = NameMap::new();
+        let mut raw_reader = RawReader::new(
+            Chain::new(asset_data, None),
+            ObjectVersion::UNKNOWN,
+            ObjectVersionUE5::UNKNOWN,
+            true,
+            name_map.clone(),
+        );
+
+        let summary = ZenPackageSummary::read(&mut raw_reader, zen_version)?;
+
+        let mut asset = ZenAsset {
+            raw_reader,
+            asset_data: AssetData {
+                use_event_driven_loader: true,
+                ..Default::default()
+            },
+            zen_version,
+            summary,
+            versioning_info: None,
+            name_map_hash_version: 0,
+            bulk_data_map: Vec::new(),
+            imported_public_export_hashes: Vec::new(),
+            import_map: Vec::new(),
+            export_map: Vec::new(),
+            export_bundle_entries: Vec::new(),
+            imported_package_names: Vec::new(),
+            name_map,
+            script_import_names: HashMap::new(),
+        };
+        asset.set_engine_version(engine_version);
+        asset.asset_data.mappings = mappings;
+        asset.parse_data(script_objects)?;
+        Ok(asset)
+    }
+
+    /// Set asset engine version
+    fn set_engine_version(&mut self, engine_version: EngineVersion) {
+        self.asset_data.set_engine_version(engine_version);
+        self.raw_reader.object_version = self.asset_data.object_version;
+        self.raw_reader.object_version_ue5 = self.asset_data.object_version_ue5;
+    }
+
+    /// Get name map
+    pub fn get_name_map(&self) -> SharedResource<NameMap> {
+        self.name_map.clone()
+    }
+
+    /// Get package name
+    pub fn get_package_name(&self) -> FName {
+        self.summary.name.to_fname(self.name_map.clone())
+    }
+
+    /// Get an export
+    pub fn get_export(&'a self, index: PackageIndex) -> Option<&'a Export<PackageObjectIndex>> {
+        self.asset_data.get_export(index)
+    }
+
+    /// Get a mutable export reference
+    pub fn get_export_mut(
+        &'a mut self,
+        index: PackageIndex,
+    ) -> Option<&'a mut Export<PackageObjectIndex>> {
+        self.asset_data.get_export_mut(index)
+    }
+
+    /// Get the number of `entry_size` sized entries between two header offsets
+    fn get_entry_count(
+        &mut self,
+        name: &str,
+        start_offset: i32,
+        end_offset: i32,
+        entry_size: i32,
+    ) -> Result<i32, Error> {
+        if start_offset < 0
+            || end_offset < start_offset
+            || end_offset as u64 > self.data_length()?
+        {
+            return Err(Error::invalid_file(format!(
+                "Invalid {name} offsets, start: {start_offset}, end: {end_offset}"
+            )));
+        }
+        Ok((end_offset - start_offset) / entry_size)
+    }
+
+    /// Parse asset data
+    fn parse_data(&mut self, script_objects: Option<&ScriptObjects>) -> Result<(), Error> {
+        if self.summary.has_versioning_info {
+            let versioning_info = ZenPackageVersioningInfo::read(self)?;
+
+            self.asset_data.object_version = versioning_info.object_version;
+            self.asset_data.object_version_ue5 = versioning_info.object_version_ue5;
+            self.asset_data.summary.file_licensee_version = versioning_info.licensee_version;
+            self.asset_data.summary.custom_versions = versioning_info.custom_versions.clone();
+            self.raw_reader.object_version = versioning_info.object_version;
+            self.raw_reader.object_version_ue5 = versioning_info.object_version_ue5;
+
+            self.versioning_info = Some(versioning_info);
+        }
+        self.asset_data.summary.unversioned = !self.summary.has_versioning_info;
+        self.asset_data.summary.package_flags = self.summary.package_flags;
+
+        let (names, hash_version) = self.read_name_batch(false)?;
+        self.name_map_hash_version = hash_version;
+        for name in names {
+            self.add_name_reference(name, true);
+        }
+
+        if self.zen_version >= EZenPackageVersion::DataResourceTable {
+            let bulk_data_map_size = self.read_i64::<LE>()?;
+            let remaining = self.data_length()? - self.position();
+            if bulk_data_map_size < 0 || bulk_data_map_size as u64 > remaining {
+                return Err(Error::invalid_file(format!(
+                    "Invalid bulk data map size {bulk_data_map_size}"
+                )));
+            }
+            let count = bulk_data_map_size / BulkDataMapEntry::SERIALIZED_SIZE;
+            self.bulk_data_map = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                let entry = BulkDataMapEntry::read(self)?;
+                self.bulk_data_map.push(entry);
+            }
+        }
+
+        self.seek(SeekFrom::Start(
+            self.summary.imported_public_export_hashes_offset as u64,
+        ))?;
+        let count = self.get_entry_count(
+            "imported public export hashes",
+            self.summary.imported_public_export_hashes_offset,
+            self.summary.import_map_offset,
+            std::mem::size_of::<u64>() as i32,
+        )?;
+        for _ in 0..count {
+            let hash = self.read_u64::<LE>()?;
+            self.imported_public_export_hashes.push(hash);
+        }
+
+        self.seek(SeekFrom::Start(self.summary.import_map_offset as u64))?;
+        let count = self.get_entry_count(
+            "import map",
+            self.summary.import_map_offset,
+            self.summary.export_map_offset,
+            std::mem::size_of::<u64>() as i32,
+        )?;
+        for _ in 0..count {
+            let import = PackageObjectIndex::new(self.read_u64::<LE>()?);
+            self.import_map.push(import);
+        }
+
+        self.seek(SeekFrom::Start(self.summary.export_map_offset as u64))?;
+        let count = self.get_entry_count(
+            "export map",
+            self.summary.export_map_offset,
+            self.summary.export_bundle_entries_offset,
+            ZenExportMapEntry::SERIALIZED_SIZE,
+        )?;
+        for _ in 0..count {
+            let entry = ZenExportMapEntry::read(self)?;
+            self.export_map.push(entry);
+        }
+
+        self.seek(SeekFrom::Start(
+            self.summary.export_bundle_entries_offset as u64,
+        ))?;
+        let count = self.get_entry_count(
+            "export bundle entries",
+            self.summary.export_bundle_entries_offset,
+            self.summary.get_export_bundle_entries_end(self.zen_version),
+            ExportBundleEntry::SERIALIZED_SIZE,
+        )?;
+        for _ in 0..count {
+            let entry = ExportBundleEntry::read(self)?;
+            self.export_bundle_entries.push(entry);
+        }
+
+        if self.zen_version >= EZenPackageVersion::ImportedPackageNames {
+            self.seek(SeekFrom::Start(
+                self.summary.imported_package_names_offset as u64,
+            ))?;
+            let (names, _) = self.read_name_batch(false)?;
+            for name in names {
+                let number = self.read_i32::<LE>()?;
+                self.imported_package_names
+                    .push(FName::new_dummy(name, number));
+            }
+        }
+
+        self.asset_data.summary.import_count = self.import_map.len() as i32;
+        self.asset_data.summary.export_count = self.export_map.len() as i32;
+
+        if let Some(script_objects) = script_objects {
+            let indices = self
+                .import_map
+                .iter()
+                .copied()
+                .chain(self.export_map.iter().flat_map(|e| {
+                    [
+                        e.class_index,
+                        e.super_index,
+                        e.template_index,
+                        e.outer_index,
+                    ]
+                }));
+            for index in indices {
+                if !index.is_script_import() {
+                    continue;
+                }
+                if let Some(entry) = script_objects.get(index) {
+                    self.script_import_names
+                        .insert(index, FName::new_dummy(entry.object_name.clone(), 0));
+                }
+            }
+        }
+
+        // export data is laid out in export bundle order directly after the header
+        let mut serial_offsets = vec![0u64; self.export_map.len()];
+        let mut next_offset = self.summary.header_size as u64;
+        for entry in &self.export_bundle_entries {
+            if entry.command_type != EExportCommandType::Serialize {
+                continue;
+            }
+            let export = self
+                .export_map
+                .get(entry.local_export_index as usize)
+                .ok_or_else(|| {
+                    Error::invalid_file(format!(
+                        "Invalid export bundle export index {}",
+                        entry.local_export_index
+                    ))
+                })?;
+            serial_offsets[entry.local_export_index as usize] = next_offset;
+            next_offset += export.cooked_serial_size;
+        }
+
+        self.asset_data.exports.reserve(self.export_map.len());
+        for (i, serial_offset) in serial_offsets.into_iter().enumerate() {
+            let base_export = self.export_map[i].to_base_export(serial_offset as i64);
+            let next_starting = serial_offset + self.export_map[i].cooked_serial_size;
+
+            let export = self.read_export(base_export, next_starting)?;
+            self.asset_data.exports.push(export);
+        }
+
+        Ok(())
+    }
+}
+
+impl<C: Read + Seek> AssetTrait<PackageObjectIndex> for ZenAsset<C> {
+    fn get_asset_data(&self) -> &AssetData<PackageObjectIndex> {
+        &self.asset_data
+    }
+
+    fn get_asset_data_mut(&mut self) -> &mut AssetData<PackageObjectIndex> {
+        &mut self.asset_data
+    }
+
+    fn get_name_map(&self) -> SharedResource<NameMap> {
+        self.name_map.clone()
+    }
+
+    fn search_name_reference(&self, name: &str) -> Option<i32> {
+        self.name_map.get_ref().search_name_reference(name)
+    }
+
+    fn add_name_reference(&mut self, name: String, force_add_duplicates: bool) -> i32 {
+        self.name_map
+            .get_mut()
+            .add_name_reference(name, force_add_duplicates)
+    }
+
+    fn get_name_reference<T>(&self, index: i32, func: impl FnOnce(&str) -> T) -> T {
+        func(self.name_map.get_ref().get_name_reference(index))
+    }
+
+    fn add_fname(&mut self, slice: &str) -> FName {
+        self.name_map.get_mut().add_fname(slice)
+    }
+}
+
+impl<C: Read + Seek> ArchiveTrait<PackageObjectIndex> for ZenAsset<C> {
+    fn get_archive_type(&self) -> ArchiveType {
+        ArchiveType::Zen
+    }
+
+    fn get_custom_version<T>(&self) -> CustomVersion
+    where
+        T: CustomVersionTrait + Into<i32>,
+    {
+        self.asset_data.get_custom_version::<T>()
+    }
+
+    fn has_unversioned_properties(&self) -> bool {
+        self.asset_data.has_unversioned_properties()
+    }
+
+    fn use_event_driven_loader(&self) -> bool {
+        self.asset_data.use_event_driven_loader
+    }
+
+    fn position(&mut self) -> u64 {
+        self.raw_reader.position()
+    }
+
+    fn get_name_map(&self) -> SharedResource<NameMap> {
+        self.name_map.clone()
+    }
+
+    fn get_array_struct_type_override(&self) -> &IndexedMap<String, String> {
+        &self.asset_data.array_struct_type_override
+    }
+
+    fn get_map_key_override(&self) -> &IndexedMap<String, String> {
+        &self.asset_data.map_key_override
+    }
+
+    fn get_map_value_override(&self) -> &IndexedMap<String, String> {
+        &self.asset_data.map_value_override
+    }
+
+    fn get_engine_version(&self) -> EngineVersion {
+        self.asset_data.get_engine_version()
+    }
+
+    fn get_object_version(&self) -> ObjectVersion {
+        self.asset_data.object_version
+    }
+
+    fn get_object_version_ue5(&self) -> ObjectVersionUE5 {
+        self.asset_data.object_version_ue5
+    }
+
+    fn get_mappings(&self) -> Option<&Usmap> {
+        self.asset_data.mappings.as_ref()
+    }
+
+    fn get_parent_class_export_name(&self) -> Option<FName> {
+        None
+    }
+
+    fn get_object_name(&self, index: PackageObjectIndex) -> Option<FName> {
+        match index.is_export() {
+            true => self
+                .export_map
+                .get(index.get_index() as usize)
+                .map(|e| e.object_name.clone()),
+            false => self.script_import_names.get(&index).cloned(),
+        }
+    }
+
+    fn get_object_name_packageindex(&self, index: PackageIndex) -> Option<FName> {
+        match index.is_import() {
+            true => self
+                .import_map
+                .get((-index.index - 1) as usize)
+                .and_then(|e| self.get_object_name(*e)),
+            false => self
+                .asset_data
+                .get_export(index)
+                .map(|e| e.get_base_export().object_name.clone()),
+        }
+    }
+}
+
+impl<C: Read + Seek> ArchiveReader<PackageObjectIndex> for ZenAsset<C> {
+    passthrough_archive_reader!(raw_reader);
+}
+
+impl<C: Read + Seek> Read for ZenAsset<C> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.raw_reader.read(buf)
+    }
+}
+
+impl<C: Read + Seek> Seek for ZenAsset<C> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.raw_reader.seek(pos)
+    }
+}
+
+// custom debug implementation to not print the whole data buffer
+impl<C: Read + Seek> Debug for ZenAsset<C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_struct("ZenAsset")
+            .field("asset_data", &self.asset_data)
+            .field("zen_version", &self.zen_version)
+            .field("summary", &self.summary)
+            .field("versioning_info", &self.versioning_info)
+            .field("name_map_hash_version", &self.name_map_hash_version)
+            .field("bulk_data_map", &self.bulk_data_map)
+            .field(
+                "imported_public_export_hashes",
+                &self.imported_public_export_hashes,
+            )
+            .field("import_map", &self.import_map)
+            .field("export_map", &self.export_map)
+            .field("export_bundle_entries", &self.export_bundle_entries)
+            .field("imported_package_names", &self.imported_package_names)
+            .finish()
+    }
+}
@@ -0,0 +1,253 @@
+//! Zen package IoStore container writer
+//!
+//! Games that only load IoStore content need mods to be shipped as a
+//! `.utoc`/`.ucas`/`.pak` triple, packages are stored in the IoStore container
+//! and all other files go into the companion `.pak`.
+
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, Write};
+
+use unreal_asset_base::{
+    compression::CompressionMethod,
+    engine_version::EngineVersion,
+    enums::{EIoChunkType, EIoContainerHeaderVersion, EIoStoreTocVersion},
+    error::{Error, ZenError},
+    io_store::{
+        container_header::{get_container_id, get_package_id},
+        IoChunkId, IoContainerHeader, IoStoreWriter,
+    },
+    unversioned::Usmap,
+};
+#[cfg(feature = "pak")]
+use unreal_pak::{PakError, PakWriter};
+
+use crate::asset::Asset;
+
+use super::writer::ZenPackage;
+
+/// Get the package name of a package from its path, e.g. `../../../Game/Content/Maps/Map.umap`
+/// becomes `/Game/Maps/Map`
+///
+/// Returns `None` if the path is not inside of a `Content` directory.
+pub fn get_package_name(path: &str) -> Option<String> {
+    let path = path
+        .rsplit_once('.')
+        .filter(|(_, extension)| !extension.contains('/'))
+        .map(|(path, _)| path)
+        .unwrap_or(path);
+
+    let components = path
+        .split('/')
+        .filter(|e| !e.is_empty() && *e != "." && *e != "..")
+        .collect::<Vec<_>>();
+    let content_index = components.iter().position(|e| *e == "Content")?;
+
+    let root = match content_index {
+        0 => return None,
+        1 if components[0] == "Engine" => "Engine",
+        1 => "Game",
+        // plugin content is mounted under the plugin's name
+        _ => components[content_index - 1],
+    };
+
+    Some(format!(
+        "/{}/{}",
+        root,
+        components[content_index + 1..].join("/")
+    ))
+}
+
+/// Get the container header version used by an engine version
+fn get_container_header_version(engine_version: EngineVersion) -> EIoContainerHeaderVersion {
+    match engine_version {
+        EngineVersion::VER_UE5_0 => EIoContainerHeaderVersion::Initial,
+        EngineVersion::VER_UE5_1 => EIoContainerHeaderVersion::LocalizedPackages,
+        EngineVersion::VER_UE5_2 => EIoContainerHeaderVersion::OptionalSegmentPackages,
+        _ => EIoContainerHeaderVersion::NoExportInfo,
+    }
+}
+
+/// An IoStore container writer for Zen packages
+///
+/// Packages and their bulk data are written to the `.ucas` partition as they are added,
+/// all other files are kept in memory until they are written to the companion `.pak`
+/// with `ZenContainerWriter::write_companion_pak`, which requires the `pak` feature.
+#[derive(Debug)]
+pub struct ZenContainerWriter<W: Write> {
+    /// Engine version of the packages in this container
+    pub engine_version: EngineVersion,
+    /// Mappings used for reading unversioned legacy packages
+    pub mappings: Option<Usmap>,
+    mount_point: String,
+    container_header: IoContainerHeader,
+    files: BTreeMap<String, Vec<u8>>,
+    writer: IoStoreWriter<W>,
+}
+
+impl<W: Write> ZenContainerWriter<W> {
+    /// Creates a new `ZenContainerWriter` that writes the `.ucas` partition to the provided writer.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - `.ucas` partition writer
+    /// * `container_name` - name of the container, this is the file name without an extension
+    /// * `mount_point` - mount point of the container, typically `../../../`
+    /// * `engine_version` - engine version of the packages, has to be at least UE5.0
+    pub fn new(
+        writer: W,
+        container_name: &str,
+        mount_point: String,
+        engine_version: EngineVersion,
+    ) -> Result<Self, Error> {
+        if engine_version < EngineVersion::VER_UE5_0 {
+            return Err(ZenError::NoObjectVersion.into());
+        }
+
+        let container_id = get_container_id(container_name);
+        Ok(ZenContainerWriter {
+            engine_version,
+            mappings: None,
+            container_header: IoContainerHeader::new(
+                get_container_header_version(engine_version),
+                container_id,
+            ),
+            files: BTreeMap::new(),
+            writer: IoStoreWriter::new(
+                writer,
+                EIoStoreTocVersion::PerfectHashWithOverflow,
+                container_id,
+                mount_point.clone(),
+            ),
+            mount_point,
+        })
+    }
+
+    /// Set the compression method used for chunks written from now on
+    pub fn set_compression(&mut self, compression: CompressionMethod) {
+        self.writer.set_compression(compression);
+    }
+
+    /// Writes a Zen package
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path of the `.uasset`/`.umap` file relative to the mount point
+    /// * `package` - Zen package
+    pub fn write_package(&mut self, path: &str, package: &ZenPackage) -> Result<(), Error> {
+        let chunk_id = IoChunkId::new(package.package_id, 0, EIoChunkType::ExportBundleData);
+        self.writer
+            .write_chunk(chunk_id, Some(path), &package.data)?;
+
+        self.container_header.package_ids.push(package.package_id);
+        self.container_header
+            .store_entries
+            .push(package.store_entry.clone());
+        Ok(())
+    }
+
+    /// Converts an asset to a Zen package and writes it
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path of the `.uasset`/`.umap` file relative to the mount point
+    /// * `asset` - asset
+    pub fn write_asset<C: Read + Seek>(
+        &mut self,
+        path: &str,
+        asset: &Asset<C>,
+    ) -> Result<(), Error> {
+        let package = asset.to_zen(&self.get_package_name(path)?)?;
+        self.write_package(path, &package)
+    }
+
+    /// Converts a legacy `.uasset`/`.uexp` pair to a Zen package and writes it
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path of the `.uasset`/`.umap` file relative to the mount point
+    /// * `asset_data` - `.uasset` data
+    /// * `bulk_data` - `.uexp` data
+    pub fn write_legacy_package(
+        &mut self,
+        path: &str,
+        asset_data: Vec<u8>,
+        bulk_data: Option<Vec<u8>>,
+    ) -> Result<(), Error> {
+        let package = ZenPackage::from_legacy(
+            &self.get_package_name(path)?,
+            asset_data,
+            bulk_data,
+            self.engine_version,
+            self.mappings.clone(),
+        )?;
+        self.write_package(path, &package)
+    }
+
+    /// Writes a file which is not a package
+    ///
+    /// `.ubulk`, `.uptnl` and `.m.ubulk` files are written to the container as bulk data
+    /// of their package, all other files are kept for the companion `.pak`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path of the file relative to the mount point
+    /// * `data` - file data
+    pub fn write_file(&mut self, path: &str, data: Vec<u8>) -> Result<(), Error> {
+        let chunk_type = match path {
+            e if e.ends_with(".m.ubulk") => Some(EIoChunkType::MemoryMappedBulkData),
+            e if e.ends_with(".ubulk") => Some(EIoChunkType::BulkData),
+            e if e.ends_with(".uptnl") => Some(EIoChunkType::OptionalBulkData),
+            _ => None,
+        };
+
+        match chunk_type {
+            Some(chunk_type) => {
+                let package_name = self.get_package_name(path.trim_end_matches(".m.ubulk"))?;
+                let chunk_id = IoChunkId::new(get_package_id(&package_name), 0, chunk_type);
+                self.writer.write_chunk(chunk_id, Some(path), &data)
+            }
+            None => {
+                self.files.insert(path.to_string(), data);
+                Ok(())
+            }
+        }
+    }
+
+    /// Writes all files which are not stored in the container to the companion `.pak`
+    ///
+    /// The pak writer still has to be finished after this.
+    #[cfg(feature = "pak")]
+    pub fn write_companion_pak<P: Write + Seek>(
+        &self,
+        pak_writer: &mut PakWriter<P>,
+    ) -> Result<(), PakError> {
+        pak_writer.mount_point = self.mount_point.clone();
+        for (path, data) in &self.files {
+            pak_writer.write_entry(path, data, true)?;
+        }
+        Ok(())
+    }
+
+    /// Finish writing the container by writing the container header and the table of contents
+    ///
+    /// Returns the `.ucas` partition writer.
+    pub fn finish_write<T: Write>(mut self, toc_writer: &mut T) -> Result<W, Error> {
+        let mut container_header = Vec::new();
+        self.container_header.write(&mut container_header)?;
+
+        let chunk_id = IoChunkId::new(
+            self.container_header.container_id,
+            0,
+            EIoChunkType::ContainerHeader,
+        );
+        self.writer.write_chunk(chunk_id, None, &container_header)?;
+
+        self.writer.finish_write(toc_writer)
+    }
+
+    /// Get the package name for a path relative to the mount point
+    fn get_package_name(&self, path: &str) -> Result<String, Error> {
+        get_package_name(&format!("{}/{}", self.mount_point, path))
+            .ok_or_else(|| Error::no_data(format!("Couldn't get the package name of {path}")))
+    }
+}
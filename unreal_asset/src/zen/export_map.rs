@@ -0,0 +1,150 @@
+//! Zen package export map
+
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
+
+use unreal_asset_base::{
+    enums::EExportCommandType,
+    error::Error,
+    flags::EObjectFlags,
+    reader::{ArchiveReader, ArchiveWriter},
+    types::{FName, PackageObjectIndex},
+};
+use unreal_asset_exports::{base_export::EExportFilterFlags, BaseExport};
+
+use super::summary::MappedName;
+
+/// Export bundle entry
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ExportBundleEntry {
+    /// Export index
+    pub local_export_index: u32,
+    /// Command type
+    pub command_type: EExportCommandType,
+}
+
+impl ExportBundleEntry {
+    /// Serialized size of an `ExportBundleEntry`
+    pub const SERIALIZED_SIZE: i32 = 8;
+
+    /// Read an `ExportBundleEntry` from an archive
+    pub fn read<R: ArchiveReader<PackageObjectIndex>>(archive: &mut R) -> Result<Self, Error> {
+        let local_export_index = archive.read_u32::<LE>()?;
+        let command_type = EExportCommandType::try_from(archive.read_u32::<LE>()?)?;
+
+        Ok(ExportBundleEntry {
+            local_export_index,
+            command_type,
+        })
+    }
+
+    /// Write an `ExportBundleEntry` to an archive
+    pub fn write<W: ArchiveWriter<PackageObjectIndex>>(
+        &self,
+        archive: &mut W,
+    ) -> Result<(), Error> {
+        archive.write_u32::<LE>(self.local_export_index)?;
+        archive.write_u32::<LE>(self.command_type.into())?;
+        Ok(())
+    }
+}
+
+/// Zen package export map entry
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ZenExportMapEntry {
+    /// Serialized offset in the package this was converted from
+    pub cooked_serial_offset: u64,
+    /// Serialized size
+    pub cooked_serial_size: u64,
+    /// Object name
+    pub object_name: FName,
+    /// Outer index
+    pub outer_index: PackageObjectIndex,
+    /// Class index
+    pub class_index: PackageObjectIndex,
+    /// Super index
+    pub super_index: PackageObjectIndex,
+    /// Template index
+    pub template_index: PackageObjectIndex,
+    /// Public export hash
+    pub public_export_hash: u64,
+    /// Object flags
+    pub object_flags: EObjectFlags,
+    /// Filter flags
+    pub filter_flags: EExportFilterFlags,
+}
+
+impl ZenExportMapEntry {
+    /// Serialized size of a `ZenExportMapEntry`
+    pub const SERIALIZED_SIZE: i32 = 72;
+
+    /// Read a `ZenExportMapEntry` from an archive
+    pub fn read<R: ArchiveReader<PackageObjectIndex>>(archive: &mut R) -> Result<Self, Error> {
+        let cooked_serial_offset = archive.read_u64::<LE>()?;
+        let cooked_serial_size = archive.read_u64::<LE>()?;
+        let object_name = MappedName::read(archive)?.to_fname(archive.get_name_map());
+        let outer_index = PackageObjectIndex::new(archive.read_u64::<LE>()?);
+        let class_index = PackageObjectIndex::new(archive.read_u64::<LE>()?);
+        let super_index = PackageObjectIndex::new(archive.read_u64::<LE>()?);
+        let template_index = PackageObjectIndex::new(archive.read_u64::<LE>()?);
+        let public_export_hash = archive.read_u64::<LE>()?;
+        let object_flags = EObjectFlags::from_bits(archive.read_u32::<LE>()?)
+            .ok_or_else(|| Error::invalid_file("Invalid object flags".to_string()))?;
+        let filter_flags = EExportFilterFlags::try_from(archive.read_u8()?)?;
+        let mut _padding = [0u8; 3];
+        archive.read_exact(&mut _padding)?;
+
+        Ok(ZenExportMapEntry {
+            cooked_serial_offset,
+            cooked_serial_size,
+            object_name,
+            outer_index,
+            class_index,
+            super_index,
+            template_index,
+            public_export_hash,
+            object_flags,
+            filter_flags,
+        })
+    }
+
+    /// Write a `ZenExportMapEntry` to an archive
+    pub fn write<W: ArchiveWriter<PackageObjectIndex>>(
+        &self,
+        archive: &mut W,
+    ) -> Result<(), Error> {
+        archive.write_u64::<LE>(self.cooked_serial_offset)?;
+        archive.write_u64::<LE>(self.cooked_serial_size)?;
+        MappedName::from_fname(&self.object_name)?.write(archive)?;
+        archive.write_u64::<LE>(self.outer_index.value)?;
+        archive.write_u64::<LE>(self.class_index.value)?;
+        archive.write_u64::<LE>(self.super_index.value)?;
+        archive.write_u64::<LE>(self.template_index.value)?;
+        archive.write_u64::<LE>(self.public_export_hash)?;
+        archive.write_u32::<LE>(self.object_flags.bits())?;
+        archive.write_u8(self.filter_flags.into())?;
+        archive.write_all(&[0u8; 3])?;
+        Ok(())
+    }
+
+    /// Convert `ZenExportMapEntry` to [`BaseExport`]
+    ///
+    /// # Arguments
+    ///
+    /// * `serial_offset` - offset of the export's data in the Zen package
+    pub fn to_base_export(&self, serial_offset: i64) -> BaseExport<PackageObjectIndex> {
+        BaseExport {
+            class_index: self.class_index,
+            super_index: self.super_index,
+            template_index: self.template_index,
+            outer_index: self.outer_index,
+            object_name: self.object_name.clone(),
+            object_flags: self.object_flags,
+            serial_size: self.cooked_serial_size as i64,
+            serial_offset,
+            not_for_client: self.filter_flags == EExportFilterFlags::NotForClient,
+            not_for_server: self.filter_flags == EExportFilterFlags::NotForServer,
+            public_export_hash: self.public_export_hash,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,539 @@
+//! Zen package to legacy package conversion
+//!
+//! Zen packages don't store their imports by name, script imports are global
+//! [`PackageObjectIndex`] values from the `ScriptObjects` chunk and package imports
+//! reference public exports of other packages by hash. A [`GlobalImportDatabase`]
+//! provides the information required to turn those back into legacy [`Import`] entries.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+use unreal_asset_base::{
+    containers::{NameMap, SharedResource},
+    error::Error,
+    flags::EObjectFlags,
+    io_store::{container_header::get_package_id, IoContainerHeader},
+    object_version::ObjectVersionUE5,
+    reader::ArchiveTrait,
+    types::{EPackageObjectIndexType, FName, GenerationInfo, PackageIndex, PackageObjectIndex},
+    Import,
+};
+use unreal_asset_exports::{BaseExport, ExportBaseTrait};
+
+use crate::asset::Asset;
+use crate::asset_data::ExportReaderTrait;
+
+use super::asset::ZenAsset;
+use super::script_objects::ScriptObjects;
+
+/// Package containing all script classes used for packages and classes
+const CORE_UOBJECT_PACKAGE: &str = "/Script/CoreUObject";
+
+/// Public export of a package that can be imported by other packages
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ImportedExport {
+    /// Object name
+    pub object_name: String,
+    /// Class package
+    pub class_package: String,
+    /// Class name
+    pub class_name: String,
+}
+
+/// Global import database used for resolving Zen package imports
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GlobalImportDatabase {
+    /// Global script objects
+    pub script_objects: ScriptObjects,
+    /// Package names and public exports by public export hash, by package id
+    packages: HashMap<u64, (String, HashMap<u64, ImportedExport>)>,
+    /// Ids of the packages imported by each package, by package id
+    imported_packages: HashMap<u64, Vec<u64>>,
+}
+
+impl GlobalImportDatabase {
+    /// Create a new `GlobalImportDatabase` instance
+    pub fn new(script_objects: ScriptObjects) -> Self {
+        GlobalImportDatabase {
+            script_objects,
+            packages: HashMap::new(),
+            imported_packages: HashMap::new(),
+        }
+    }
+
+    /// Add public exports of a package
+    pub fn add_package(
+        &mut self,
+        package_name: String,
+        exports: impl IntoIterator<Item = (u64, ImportedExport)>,
+    ) {
+        self.packages
+            .entry(get_package_id(&package_name))
+            .or_insert_with(|| (package_name, HashMap::new()))
+            .1
+            .extend(exports);
+    }
+
+    /// Add the imported packages of all packages in a container
+    ///
+    /// Packages before [`unreal_asset_base::enums::EZenPackageVersion::ImportedPackageNames`]
+    /// don't store the names of the packages they import, their package imports are resolved
+    /// through the imported package ids of their store entry in the container header instead.
+    pub fn add_container_header(&mut self, container_header: &IoContainerHeader) {
+        for (package_id, store_entry) in container_header
+            .package_ids
+            .iter()
+            .zip(&container_header.store_entries)
+        {
+            self.imported_packages
+                .insert(*package_id, store_entry.imported_packages.clone());
+        }
+    }
+
+    /// Add public exports of a parsed Zen package
+    pub fn add_zen_package<C: Read + Seek>(&mut self, asset: &ZenAsset<C>) -> Result<(), Error> {
+        let package_name = asset.get_package_name().get_owned_content();
+
+        let mut exports = Vec::new();
+        for export in &asset.export_map {
+            if export.public_export_hash == 0 {
+                continue;
+            }
+
+            let (class_package, class_name) =
+                self.get_class(asset, &package_name, export.class_index)?;
+            exports.push((
+                export.public_export_hash,
+                ImportedExport {
+                    object_name: export.object_name.get_owned_content(),
+                    class_package,
+                    class_name,
+                },
+            ));
+        }
+
+        self.add_package(package_name, exports);
+        Ok(())
+    }
+
+    /// Get a public export of a package
+    pub fn get_export(
+        &self,
+        package_name: &str,
+        public_export_hash: u64,
+    ) -> Option<&ImportedExport> {
+        self.packages
+            .get(&get_package_id(package_name))
+            .and_then(|(_, exports)| exports.get(&public_export_hash))
+    }
+
+    /// Get the package and name of a class referenced from a Zen package
+    fn get_class<C: Read + Seek>(
+        &self,
+        asset: &ZenAsset<C>,
+        package_name: &str,
+        index: PackageObjectIndex,
+    ) -> Result<(String, String), Error> {
+        match index.get_type() {
+            EPackageObjectIndexType::ScriptImport => self.get_script_class(index),
+            EPackageObjectIndexType::Export => asset
+                .export_map
+                .get(index.get_index() as usize)
+                .map(|e| (package_name.to_string(), e.object_name.get_owned_content()))
+                .ok_or_else(|| Error::invalid_package_index(format!("Invalid export {index}"))),
+            EPackageObjectIndexType::PackageImport => {
+                let (package_name, export) = self.get_package_import(asset, index)?;
+                Ok((package_name, export.object_name.clone()))
+            }
+            EPackageObjectIndexType::Null => {
+                Ok((CORE_UOBJECT_PACKAGE.to_string(), "Class".to_string()))
+            }
+        }
+    }
+
+    /// Get the package and name of a script class
+    fn get_script_class(&self, index: PackageObjectIndex) -> Result<(String, String), Error> {
+        let path = self
+            .script_objects
+            .get_object_path(index)
+            .ok_or_else(|| Error::no_data(format!("Unknown script object {index}")))?;
+
+        path.split_once('.')
+            .map(|(package, name)| (package.to_string(), name.to_string()))
+            .ok_or_else(|| Error::no_data(format!("Script object {path} is not a class")))
+    }
+
+    /// Resolve a package import of a Zen package
+    fn get_package_import<C: Read + Seek>(
+        &self,
+        asset: &ZenAsset<C>,
+        index: PackageObjectIndex,
+    ) -> Result<(String, &ImportedExport), Error> {
+        let (package_index, hash_index) = index.get_package_import().ok_or_else(|| {
+            Error::invalid_package_index(format!("{index} is not a package import"))
+        })?;
+
+        let package_id = match asset.imported_package_names.is_empty() {
+            false => asset
+                .imported_package_names
+                .get(package_index as usize)
+                .map(|e| get_package_id(&e.get_owned_content())),
+            true => self
+                .imported_packages
+                .get(&get_package_id(
+                    &asset.get_package_name().get_owned_content(),
+                ))
+                .and_then(|e| e.get(package_index as usize))
+                .copied(),
+        }
+        .ok_or_else(|| Error::no_data(format!("No imported package for {index}")))?;
+        let hash = asset
+            .imported_public_export_hashes
+            .get(hash_index as usize)
+            .ok_or_else(|| Error::no_data(format!("No imported public export hash for {index}")))?;
+
+        let (package_name, export) = self
+            .packages
+            .get(&package_id)
+            .and_then(|(package_name, exports)| Some((package_name, exports.get(hash)?)))
+            .ok_or_else(|| {
+                Error::no_data(format!(
+                    "Unresolved package import {package_id:#x} {hash:#x}"
+                ))
+            })?;
+
+        Ok((package_name.clone(), export))
+    }
+}
+
+/// Zen import map to legacy import conversion state
+struct ImportConverter<'a, C: Read + Seek> {
+    /// Zen package
+    asset: &'a ZenAsset<C>,
+    /// Import database
+    database: &'a GlobalImportDatabase,
+    /// Legacy name map
+    name_map: SharedResource<NameMap>,
+    /// Legacy imports, the first entries map one to one to the Zen import map
+    imports: Vec<Option<Import>>,
+    /// Imports that are not part of the Zen import map
+    additional_imports: HashMap<PackageObjectIndex, PackageIndex>,
+    /// Package imports by package name
+    packages: HashMap<String, PackageIndex>,
+}
+
+impl<'a, C: Read + Seek> ImportConverter<'a, C> {
+    /// Create a new `ImportConverter` instance
+    fn new(
+        asset: &'a ZenAsset<C>,
+        database: &'a GlobalImportDatabase,
+        name_map: SharedResource<NameMap>,
+    ) -> Self {
+        ImportConverter {
+            asset,
+            database,
+            name_map,
+            imports: vec![None; asset.import_map.len()],
+            additional_imports: HashMap::new(),
+            packages: HashMap::new(),
+        }
+    }
+
+    /// Add an `FName`, splitting the instance number off the name like the engine does
+    fn add_fname(&mut self, name: &str) -> FName {
+        let number = name
+            .rsplit_once('_')
+            .filter(|(name, number)| {
+                !name.is_empty()
+                    && !number.is_empty()
+                    && number.bytes().all(|e| e.is_ascii_digit())
+                    && (!number.starts_with('0') || number.len() == 1)
+            })
+            .and_then(|(name, number)| Some((name, number.parse::<i32>().ok()?)));
+
+        match number {
+            Some((name, number)) => self
+                .name_map
+                .get_mut()
+                .add_fname_with_number(name, number + 1),
+            None => self.name_map.get_mut().add_fname(name),
+        }
+    }
+
+    /// Create a new import
+    fn create_import(
+        &mut self,
+        class_package: &str,
+        class_name: &str,
+        outer_index: PackageIndex,
+        object_name: &str,
+    ) -> Import {
+        Import::new(
+            self.add_fname(class_package),
+            self.add_fname(class_name),
+            outer_index,
+            self.add_fname(object_name),
+            false,
+        )
+    }
+
+    /// Push an import that is not part of the Zen import map
+    fn push_import(&mut self, import: Import) -> PackageIndex {
+        self.imports.push(Some(import));
+        PackageIndex::new(-(self.imports.len() as i32))
+    }
+
+    /// Get the legacy import index of a package by its name
+    fn get_package(&mut self, package_name: &str) -> PackageIndex {
+        if let Some(index) = self.packages.get(package_name) {
+            return *index;
+        }
+
+        let import = self.create_import(
+            CORE_UOBJECT_PACKAGE,
+            "Package",
+            PackageIndex::new(0),
+            package_name,
+        );
+        let index = self.push_import(import);
+        self.packages.insert(package_name.to_string(), index);
+        index
+    }
+
+    /// Get the legacy index of a [`PackageObjectIndex`]
+    fn get_index(&mut self, index: PackageObjectIndex) -> Result<PackageIndex, Error> {
+        match index.get_type() {
+            EPackageObjectIndexType::Null => Ok(PackageIndex::new(0)),
+            EPackageObjectIndexType::Export => Ok(PackageIndex::new(index.get_index() as i32 + 1)),
+            _ => self.get_import(index),
+        }
+    }
+
+    /// Get the legacy import index of a script or package import
+    fn get_import(&mut self, index: PackageObjectIndex) -> Result<PackageIndex, Error> {
+        if let Some(position) = self.asset.import_map.iter().position(|e| *e == index) {
+            if self.imports[position].is_none() {
+                let import = self.resolve_import(index)?;
+                self.imports[position] = Some(import);
+            }
+            return Ok(PackageIndex::new(-(position as i32) - 1));
+        }
+
+        if let Some(import) = self.additional_imports.get(&index) {
+            return Ok(*import);
+        }
+
+        let import = self.resolve_import(index)?;
+        let import = self.push_import(import);
+        self.additional_imports.insert(index, import);
+        Ok(import)
+    }
+
+    /// Resolve a script or package import
+    fn resolve_import(&mut self, index: PackageObjectIndex) -> Result<Import, Error> {
+        match index.get_type() {
+            EPackageObjectIndexType::ScriptImport => {
+                let entry = self
+                    .database
+                    .script_objects
+                    .get(index)
+                    .ok_or_else(|| Error::no_data(format!("Unknown script object {index}")))?
+                    .clone();
+
+                if entry.outer_index.is_null() {
+                    return Ok(self.create_import(
+                        CORE_UOBJECT_PACKAGE,
+                        "Package",
+                        PackageIndex::new(0),
+                        &entry.object_name,
+                    ));
+                }
+
+                let outer_index = self.get_import(entry.outer_index)?;
+                // script objects don't store their class, only class default objects reference one
+                let (class_package, class_name) = match entry.cdo_class_index.is_null() {
+                    true => (CORE_UOBJECT_PACKAGE.to_string(), "Class".to_string()),
+                    false => self.database.get_script_class(entry.cdo_class_index)?,
+                };
+
+                Ok(
+                    self.create_import(
+                        &class_package,
+                        &class_name,
+                        outer_index,
+                        &entry.object_name,
+                    ),
+                )
+            }
+            EPackageObjectIndexType::PackageImport => {
+                let (package_name, export) = self.database.get_package_import(self.asset, index)?;
+                let outer_index = self.get_package(&package_name);
+
+                Ok(self.create_import(
+                    &export.class_package,
+                    &export.class_name,
+                    outer_index,
+                    &export.object_name,
+                ))
+            }
+            _ => Err(Error::invalid_package_index(format!(
+                "{index} is not an import"
+            ))),
+        }
+    }
+
+    /// Convert the whole Zen import map
+    fn convert(mut self) -> Result<Vec<Import>, Error> {
+        for i in 0..self.asset.import_map.len() {
+            let index = self.asset.import_map[i];
+            if index.is_null() {
+                // unused import map slots still have to keep their position
+                let import = self.create_import(
+                    CORE_UOBJECT_PACKAGE,
+                    "Package",
+                    PackageIndex::new(0),
+                    "None",
+                );
+                self.imports[i] = Some(import);
+                continue;
+            }
+            self.get_import(index)?;
+        }
+
+        Ok(self.imports.into_iter().flatten().collect())
+    }
+}
+
+impl<C: Read + Seek> ZenAsset<C> {
+    /// Convert this Zen package to a legacy [`Asset`]
+    ///
+    /// Script imports are resolved through the script objects of the database,
+    /// package imports through the public exports registered in the database.
+    /// Packages before [`unreal_asset_base::enums::EZenPackageVersion::ImportedPackageNames`]
+    /// don't store their imported package names, the container header of their container
+    /// has to be added with [`GlobalImportDatabase::add_container_header`].
+    ///
+    /// Script objects don't store their class, so script imports other than packages and
+    /// class default objects are imported as `Class`.
+    pub fn to_legacy(
+        &mut self,
+        database: &GlobalImportDatabase,
+    ) -> Result<Asset<Cursor<Vec<u8>>>, Error> {
+        let serial_ranges = self
+            .asset_data
+            .exports
+            .iter()
+            .map(|e| {
+                let base_export = e.get_base_export();
+                (
+                    base_export.serial_offset as u64,
+                    base_export.serial_size as usize,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut export_data = Vec::new();
+        let mut serial_offsets = Vec::with_capacity(serial_ranges.len());
+        for (serial_offset, serial_size) in serial_ranges {
+            self.seek(SeekFrom::Start(serial_offset))?;
+
+            serial_offsets.push(export_data.len() as u64);
+            let mut data = vec![0u8; serial_size];
+            self.read_exact(&mut data)?;
+            export_data.extend(data);
+        }
+        let data_length = export_data.len() as u64;
+
+        let mut asset = Asset::new_empty(
+            Cursor::new(export_data),
+            None,
+            self.get_engine_version(),
+            self.asset_data.mappings.clone(),
+        );
+
+        asset.asset_data.object_version = self.asset_data.object_version;
+        asset.asset_data.object_version_ue5 = self.asset_data.object_version_ue5;
+        asset.asset_data.summary = self.asset_data.summary.clone();
+        asset.asset_data.use_event_driven_loader = true;
+        asset.raw_reader.object_version = self.asset_data.object_version;
+        asset.raw_reader.object_version_ue5 = self.asset_data.object_version_ue5;
+        asset.raw_reader.use_event_driven_loader = true;
+
+        asset.legacy_file_version =
+            match self.asset_data.object_version_ue5 > ObjectVersionUE5::UNKNOWN {
+                true => -8,
+                false => -7,
+            };
+        asset.folder_name = String::from("None");
+        asset.payload_toc_offset = -1;
+        asset.data_resource_offset = -1;
+
+        for name in self.get_name_map().get_ref().get_name_map_index_list() {
+            asset.add_name_reference(name.clone(), true);
+        }
+        let name_count = asset
+            .get_name_map()
+            .get_ref()
+            .get_name_map_index_list()
+            .len();
+        asset.names_referenced_from_export_data_count = name_count as i32;
+
+        let mut converter = ImportConverter::new(self, database, asset.get_name_map());
+        let mut base_exports = Vec::with_capacity(self.export_map.len());
+        for (i, entry) in self.export_map.iter().enumerate() {
+            let zen_export = self.asset_data.exports[i].get_base_export();
+
+            base_exports.push(BaseExport {
+                class_index: converter.get_index(entry.class_index)?,
+                super_index: converter.get_index(entry.super_index)?,
+                template_index: converter.get_index(entry.template_index)?,
+                outer_index: converter.get_index(entry.outer_index)?,
+                object_name: converter.name_map.get_mut().add_fname_with_number(
+                    &entry.object_name.get_owned_content(),
+                    entry.object_name.get_number(),
+                ),
+                object_flags: entry.object_flags,
+                serial_size: zen_export.serial_size,
+                serial_offset: serial_offsets[i] as i64,
+                not_for_client: zen_export.not_for_client,
+                not_for_server: zen_export.not_for_server,
+                is_asset: entry.outer_index.is_null()
+                    && !entry
+                        .object_flags
+                        .contains(EObjectFlags::RF_CLASS_DEFAULT_OBJECT),
+                generate_public_hash: entry.public_export_hash != 0,
+                public_export_hash: entry.public_export_hash,
+                ..Default::default()
+            });
+        }
+        asset.imports = converter.convert()?;
+
+        for (i, base_export) in base_exports.into_iter().enumerate() {
+            let next_starting = serial_offsets.get(i + 1).copied().unwrap_or(data_length);
+            let export = asset.read_export(base_export, next_starting)?;
+            asset.asset_data.exports.push(export);
+        }
+
+        asset.generations = vec![GenerationInfo::new(
+            asset.asset_data.exports.len() as i32,
+            asset
+                .get_name_map()
+                .get_ref()
+                .get_name_map_index_list()
+                .len() as i32,
+        )];
+
+        Ok(asset)
+    }
+
+    /// Convert this Zen package to a legacy package and write it as a `.uasset`/`.uexp` pair
+    pub fn write_legacy<W: Read + Seek + Write>(
+        &mut self,
+        database: &GlobalImportDatabase,
+        asset_cursor: &mut W,
+        uexp_cursor: &mut W,
+    ) -> Result<(), Error> {
+        self.to_legacy(database)?
+            .write_data(asset_cursor, Some(uexp_cursor))
+    }
+}
@@ -0,0 +1,21 @@
+//! Unreal Engine 5 Zen packages
+//!
+//! Zen packages are stored in IoStore containers and can be read from
+//! `ExportBundleData` chunks using [`crate::io_store::IoStoreReader`],
+//! [`ZenContainerWriter`] writes legacy packages to a new container.
+
+pub mod asset;
+pub mod container;
+pub mod export_map;
+pub mod legacy;
+pub mod script_objects;
+pub mod summary;
+pub mod writer;
+
+pub use asset::ZenAsset;
+pub use container::ZenContainerWriter;
+pub use export_map::{ExportBundleEntry, ZenExportMapEntry};
+pub use legacy::{GlobalImportDatabase, ImportedExport};
+pub use script_objects::{ScriptObjectEntry, ScriptObjects};
+pub use summary::{MappedName, ZenPackageSummary, ZenPackageVersioningInfo};
+pub use writer::ZenPackage;
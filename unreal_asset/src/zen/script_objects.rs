@@ -0,0 +1,120 @@
+//! Global script objects
+//!
+//! Zen packages reference engine classes and other script objects by a global
+//! [`PackageObjectIndex`], the names of these objects are stored in the
+//! `ScriptObjects` chunk of the `global.utoc` container.
+
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+
+use byteorder::{ReadBytesExt, LE};
+
+use unreal_asset_base::{
+    containers::{Chain, NameMap},
+    error::Error,
+    object_version::{ObjectVersion, ObjectVersionUE5},
+    reader::{ArchiveReader, RawReader},
+    types::PackageObjectIndex,
+};
+
+use super::summary::MappedName;
+
+/// Global script object entry
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ScriptObjectEntry {
+    /// Object name
+    pub object_name: String,
+    /// Global index of this object
+    pub global_index: PackageObjectIndex,
+    /// Outer index
+    pub outer_index: PackageObjectIndex,
+    /// Class default object class index
+    pub cdo_class_index: PackageObjectIndex,
+}
+
+/// Global script objects database
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScriptObjects {
+    /// Script object entries
+    pub entries: Vec<ScriptObjectEntry>,
+    /// Global index to entry index lookup
+    lookup: HashMap<PackageObjectIndex, usize>,
+}
+
+impl ScriptObjects {
+    /// Read `ScriptObjects` from the `ScriptObjects` chunk data
+    pub fn read<C: Read + Seek>(data: C) -> Result<Self, Error> {
+        let mut reader = RawReader::<PackageObjectIndex, C>::new(
+            Chain::new(data, None),
+            ObjectVersion::UNKNOWN,
+            ObjectVersionUE5::UNKNOWN,
+            false,
+            NameMap::new(),
+        );
+
+        let (names, _) = reader.read_name_batch(false)?;
+
+        let count = reader.read_i32::<LE>()?;
+        let mut entries = Vec::with_capacity(count.max(0) as usize);
+        for _ in 0..count {
+            let name = MappedName::read(&mut reader)?;
+            let global_index = PackageObjectIndex::new(reader.read_u64::<LE>()?);
+            let outer_index = PackageObjectIndex::new(reader.read_u64::<LE>()?);
+            let cdo_class_index = PackageObjectIndex::new(reader.read_u64::<LE>()?);
+
+            let object_name = names.get(name.index as usize).ok_or_else(|| {
+                Error::invalid_file(format!("Invalid script object name {}", name.index))
+            })?;
+            let object_name = match name.number {
+                0 => object_name.clone(),
+                number => format!("{}_{}", object_name, number - 1),
+            };
+
+            entries.push(ScriptObjectEntry {
+                object_name,
+                global_index,
+                outer_index,
+                cdo_class_index,
+            });
+        }
+
+        Ok(Self::from_entries(entries))
+    }
+
+    /// Create `ScriptObjects` from a list of entries
+    pub fn from_entries(entries: Vec<ScriptObjectEntry>) -> Self {
+        let lookup = entries
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (e.global_index, i))
+            .collect();
+
+        ScriptObjects { entries, lookup }
+    }
+
+    /// Get a script object by its global index
+    pub fn get(&self, index: PackageObjectIndex) -> Option<&ScriptObjectEntry> {
+        self.lookup.get(&index).map(|e| &self.entries[*e])
+    }
+
+    /// Get the full path of a script object, e.g. `/Script/Engine.StaticMesh`
+    pub fn get_object_path(&self, index: PackageObjectIndex) -> Option<String> {
+        let entry = self.get(index)?;
+        match entry.outer_index.is_null() {
+            true => Some(entry.object_name.clone()),
+            false => {
+                let outer = self.get(entry.outer_index)?;
+                let separator = match outer.outer_index.is_null() {
+                    true => '.',
+                    false => ':',
+                };
+                Some(format!(
+                    "{}{}{}",
+                    self.get_object_path(entry.outer_index)?,
+                    separator,
+                    entry.object_name
+                ))
+            }
+        }
+    }
+}
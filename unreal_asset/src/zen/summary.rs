@@ -0,0 +1,317 @@
+//! Zen package summary
+
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
+
+use unreal_asset_base::{
+    containers::{NameMap, SharedResource},
+    custom_version::CustomVersion,
+    engine_version::EngineVersion,
+    enums::{ECustomVersionSerializationFormat, EZenPackageVersion},
+    error::{Error, FNameError},
+    flags::EPackageFlags,
+    object_version::{ObjectVersion, ObjectVersionUE5},
+    reader::{ArchiveReader, ArchiveWriter},
+    types::{fname::EMappedNameType, FName, PackageObjectIndex},
+};
+
+/// Get the Zen package version used by an engine version
+///
+/// Returns `None` for engine versions which don't use the UE5 Zen package format
+pub fn get_zen_version(engine_version: EngineVersion) -> Option<EZenPackageVersion> {
+    match engine_version {
+        EngineVersion::UNKNOWN | EngineVersion::VER_UE4_27 => None,
+        EngineVersion::VER_UE5_0 | EngineVersion::VER_UE5_1 => Some(EZenPackageVersion::Initial),
+        EngineVersion::VER_UE5_2 => Some(EZenPackageVersion::DataResourceTable),
+        e if e < EngineVersion::VER_UE5_0 => None,
+        _ => Some(EZenPackageVersion::ImportedPackageNames),
+    }
+}
+
+/// Mapped name, a name map index together with the name map type
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MappedName {
+    /// Name map index
+    pub index: u32,
+    /// Instance number
+    pub number: u32,
+    /// Name map type
+    pub ty: EMappedNameType,
+}
+
+impl MappedName {
+    /// Amount of bits used for the index
+    const INDEX_BITS: u32 = 30;
+    /// Index mask
+    const INDEX_MASK: u32 = (1 << Self::INDEX_BITS) - 1;
+
+    /// Read a `MappedName` from an archive
+    pub fn read<R: ArchiveReader<PackageObjectIndex>>(archive: &mut R) -> Result<Self, Error> {
+        let index = archive.read_u32::<LE>()?;
+        let number = archive.read_u32::<LE>()?;
+
+        Ok(MappedName {
+            index: index & Self::INDEX_MASK,
+            number,
+            ty: EMappedNameType::try_from((index >> Self::INDEX_BITS) as u16)?,
+        })
+    }
+
+    /// Create a `MappedName` from a name map backed `FName`
+    pub fn from_fname(name: &FName) -> Result<Self, Error> {
+        match name {
+            FName::Backed {
+                index, number, ty, ..
+            } => Ok(MappedName {
+                index: *index as u32,
+                number: *number as u32,
+                ty: *ty,
+            }),
+            FName::Dummy { value, number } => {
+                Err(FNameError::dummy_serialize(value, *number).into())
+            }
+        }
+    }
+
+    /// Write a `MappedName` to an archive
+    pub fn write<W: ArchiveWriter<PackageObjectIndex>>(
+        &self,
+        archive: &mut W,
+    ) -> Result<(), Error> {
+        archive.write_u32::<LE>(self.get_raw_index())?;
+        archive.write_u32::<LE>(self.number)?;
+        Ok(())
+    }
+
+    /// Get the raw serialized index, including the name map type
+    pub fn get_raw_index(&self) -> u32 {
+        ((u16::from(self.ty) as u32) << Self::INDEX_BITS) | (self.index & Self::INDEX_MASK)
+    }
+
+    /// Convert this `MappedName` to an `FName` in the given name map
+    pub fn to_fname(&self, name_map: SharedResource<NameMap>) -> FName {
+        FName::new_with_type(self.index as i32, self.number as i32, self.ty, name_map)
+    }
+}
+
+/// Zen package versioning info, only present in uncooked or versioned Zen packages
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ZenPackageVersioningInfo {
+    /// Zen package version
+    pub zen_version: EZenPackageVersion,
+    /// Object version
+    pub object_version: ObjectVersion,
+    /// UE5 object version
+    pub object_version_ue5: ObjectVersionUE5,
+    /// Licensee version
+    pub licensee_version: i32,
+    /// Custom versions
+    pub custom_versions: Vec<CustomVersion>,
+}
+
+impl ZenPackageVersioningInfo {
+    /// Read `ZenPackageVersioningInfo` from an archive
+    pub fn read<R: ArchiveReader<PackageObjectIndex>>(archive: &mut R) -> Result<Self, Error> {
+        let zen_version = EZenPackageVersion::try_from(archive.read_u32::<LE>()?)?;
+        let object_version = ObjectVersion::try_from(archive.read_i32::<LE>()?)?;
+        let object_version_ue5 = ObjectVersionUE5::try_from(archive.read_i32::<LE>()?)?;
+        let licensee_version = archive.read_i32::<LE>()?;
+        let custom_versions = archive
+            .read_custom_version_container(ECustomVersionSerializationFormat::Optimized, None)?;
+
+        Ok(ZenPackageVersioningInfo {
+            zen_version,
+            object_version,
+            object_version_ue5,
+            licensee_version,
+            custom_versions,
+        })
+    }
+
+    /// Write `ZenPackageVersioningInfo` to an archive
+    pub fn write<W: ArchiveWriter<PackageObjectIndex>>(
+        &self,
+        archive: &mut W,
+    ) -> Result<(), Error> {
+        archive.write_u32::<LE>(self.zen_version.into())?;
+        archive.write_i32::<LE>(self.object_version.into())?;
+        archive.write_i32::<LE>(self.object_version_ue5.into())?;
+        archive.write_i32::<LE>(self.licensee_version)?;
+        archive.write_i32::<LE>(self.custom_versions.len() as i32)?;
+        for custom_version in &self.custom_versions {
+            custom_version.write(archive)?;
+        }
+        Ok(())
+    }
+}
+
+/// Zen package summary
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ZenPackageSummary {
+    /// Does the package have versioning info
+    pub has_versioning_info: bool,
+    /// Header size, export data starts after the header
+    pub header_size: u32,
+    /// Package name
+    pub name: MappedName,
+    /// Package flags
+    pub package_flags: EPackageFlags,
+    /// Header size of the package this was converted from
+    pub cooked_header_size: u32,
+    /// Imported public export hashes offset
+    pub imported_public_export_hashes_offset: i32,
+    /// Import map offset
+    pub import_map_offset: i32,
+    /// Export map offset
+    pub export_map_offset: i32,
+    /// Export bundle entries offset
+    pub export_bundle_entries_offset: i32,
+    /// Graph data offset, only used before [`EZenPackageVersion::ImportedPackageNames`]
+    pub graph_data_offset: i32,
+    /// Dependency bundle headers offset, only used since [`EZenPackageVersion::ImportedPackageNames`]
+    pub dependency_bundle_headers_offset: i32,
+    /// Dependency bundle entries offset, only used since [`EZenPackageVersion::ImportedPackageNames`]
+    pub dependency_bundle_entries_offset: i32,
+    /// Imported package names offset, only used since [`EZenPackageVersion::ImportedPackageNames`]
+    pub imported_package_names_offset: i32,
+}
+
+impl ZenPackageSummary {
+    /// Read a `ZenPackageSummary` from an archive
+    pub fn read<R: ArchiveReader<PackageObjectIndex>>(
+        archive: &mut R,
+        zen_version: EZenPackageVersion,
+    ) -> Result<Self, Error> {
+        let has_versioning_info = archive.read_u32::<LE>()? != 0;
+        let header_size = archive.read_u32::<LE>()?;
+        let name = MappedName::read(archive)?;
+        let package_flags = EPackageFlags::from_bits(archive.read_u32::<LE>()?)
+            .ok_or_else(|| Error::invalid_file("Invalid package flags".to_string()))?;
+        let cooked_header_size = archive.read_u32::<LE>()?;
+        let imported_public_export_hashes_offset = archive.read_i32::<LE>()?;
+        let import_map_offset = archive.read_i32::<LE>()?;
+        let export_map_offset = archive.read_i32::<LE>()?;
+        let export_bundle_entries_offset = archive.read_i32::<LE>()?;
+
+        let mut summary = ZenPackageSummary {
+            has_versioning_info,
+            header_size,
+            name,
+            package_flags,
+            cooked_header_size,
+            imported_public_export_hashes_offset,
+            import_map_offset,
+            export_map_offset,
+            export_bundle_entries_offset,
+            graph_data_offset: -1,
+            dependency_bundle_headers_offset: -1,
+            dependency_bundle_entries_offset: -1,
+            imported_package_names_offset: -1,
+        };
+
+        match zen_version >= EZenPackageVersion::ImportedPackageNames {
+            true => {
+                summary.dependency_bundle_headers_offset = archive.read_i32::<LE>()?;
+                summary.dependency_bundle_entries_offset = archive.read_i32::<LE>()?;
+                summary.imported_package_names_offset = archive.read_i32::<LE>()?;
+            }
+            false => {
+                summary.graph_data_offset = archive.read_i32::<LE>()?;
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Get the serialized size of a `ZenPackageSummary`
+    pub fn serialized_size(zen_version: EZenPackageVersion) -> u64 {
+        match zen_version >= EZenPackageVersion::ImportedPackageNames {
+            true => 52,
+            false => 44,
+        }
+    }
+
+    /// Write a `ZenPackageSummary` to an archive
+    pub fn write<W: ArchiveWriter<PackageObjectIndex>>(
+        &self,
+        archive: &mut W,
+        zen_version: EZenPackageVersion,
+    ) -> Result<(), Error> {
+        archive.write_u32::<LE>(self.has_versioning_info as u32)?;
+        archive.write_u32::<LE>(self.header_size)?;
+        self.name.write(archive)?;
+        archive.write_u32::<LE>(self.package_flags.bits())?;
+        archive.write_u32::<LE>(self.cooked_header_size)?;
+        archive.write_i32::<LE>(self.imported_public_export_hashes_offset)?;
+        archive.write_i32::<LE>(self.import_map_offset)?;
+        archive.write_i32::<LE>(self.export_map_offset)?;
+        archive.write_i32::<LE>(self.export_bundle_entries_offset)?;
+
+        match zen_version >= EZenPackageVersion::ImportedPackageNames {
+            true => {
+                archive.write_i32::<LE>(self.dependency_bundle_headers_offset)?;
+                archive.write_i32::<LE>(self.dependency_bundle_entries_offset)?;
+                archive.write_i32::<LE>(self.imported_package_names_offset)?;
+            }
+            false => {
+                archive.write_i32::<LE>(self.graph_data_offset)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the offset where export bundle entries end
+    pub fn get_export_bundle_entries_end(&self, zen_version: EZenPackageVersion) -> i32 {
+        match zen_version >= EZenPackageVersion::ImportedPackageNames {
+            true => self.dependency_bundle_headers_offset,
+            false => self.graph_data_offset,
+        }
+    }
+}
+
+/// Bulk data map entry
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BulkDataMapEntry {
+    /// Serialized offset
+    pub serial_offset: i64,
+    /// Duplicate serialized offset
+    pub duplicate_serial_offset: i64,
+    /// Serialized size
+    pub serial_size: i64,
+    /// Bulk data flags
+    pub flags: u32,
+}
+
+impl BulkDataMapEntry {
+    /// Serialized size of a `BulkDataMapEntry`
+    pub const SERIALIZED_SIZE: i64 = 32;
+
+    /// Read a `BulkDataMapEntry` from an archive
+    pub fn read<R: ArchiveReader<PackageObjectIndex>>(archive: &mut R) -> Result<Self, Error> {
+        let serial_offset = archive.read_i64::<LE>()?;
+        let duplicate_serial_offset = archive.read_i64::<LE>()?;
+        let serial_size = archive.read_i64::<LE>()?;
+        let flags = archive.read_u32::<LE>()?;
+        let _padding = archive.read_u32::<LE>()?;
+
+        Ok(BulkDataMapEntry {
+            serial_offset,
+            duplicate_serial_offset,
+            serial_size,
+            flags,
+        })
+    }
+
+    /// Write a `BulkDataMapEntry` to an archive
+    pub fn write<W: ArchiveWriter<PackageObjectIndex>>(
+        &self,
+        archive: &mut W,
+    ) -> Result<(), Error> {
+        archive.write_i64::<LE>(self.serial_offset)?;
+        archive.write_i64::<LE>(self.duplicate_serial_offset)?;
+        archive.write_i64::<LE>(self.serial_size)?;
+        archive.write_u32::<LE>(self.flags)?;
+        archive.write_u32::<LE>(0)?;
+        Ok(())
+    }
+}
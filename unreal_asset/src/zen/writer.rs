@@ -0,0 +1,533 @@
+//! Legacy package to Zen package conversion
+//!
+//! Zen packages reference script imports by a hash of their object path and
+//! public exports of other packages by a hash of their path inside of the package,
+//! so a legacy package can be converted without access to any other package.
+
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
+
+use unreal_asset_base::{
+    containers::NameMap,
+    crc,
+    engine_version::EngineVersion,
+    enums::{EExportCommandType, EZenPackageVersion},
+    error::{Error, ZenError},
+    flags::EObjectFlags,
+    io_store::{container_header::get_package_id, IoPackageStoreEntry},
+    object_version::{ObjectVersion, ObjectVersionUE5},
+    reader::{ArchiveTrait, ArchiveWriter, RawWriter},
+    types::{
+        fname::EMappedNameType, EPackageObjectIndexType, PackageIndex, PackageIndexTrait,
+        PackageObjectIndex,
+    },
+    unversioned::Usmap,
+};
+use unreal_asset_exports::{base_export::EExportFilterFlags, ExportBaseTrait};
+
+use crate::asset::Asset;
+
+use super::export_map::{ExportBundleEntry, ZenExportMapEntry};
+use super::summary::{
+    get_zen_version, BulkDataMapEntry, MappedName, ZenPackageSummary, ZenPackageVersioningInfo,
+};
+
+/// Prefix of script packages
+const SCRIPT_PACKAGE_PREFIX: &str = "/Script/";
+
+/// Data resource table version which added cooked indices
+const DATA_RESOURCE_VERSION_ADDED_COOKED_INDEX: u32 = 2;
+
+/// A Zen package converted from a legacy package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZenPackage {
+    /// Package name, e.g. `/Game/Maps/Map`
+    pub package_name: String,
+    /// Package id
+    pub package_id: u64,
+    /// Package store entry for the container header
+    pub store_entry: IoPackageStoreEntry,
+    /// Zen package data, stored in the `ExportBundleData` chunk
+    pub data: Vec<u8>,
+}
+
+impl ZenPackage {
+    /// Convert a legacy `.uasset`/`.uexp` pair to a Zen package
+    ///
+    /// # Arguments
+    ///
+    /// * `package_name` - name of the package, e.g. `/Game/Maps/Map`
+    /// * `asset_data` - `.uasset` data
+    /// * `bulk_data` - `.uexp` data
+    /// * `engine_version` - engine version of the package, has to be at least UE5.0
+    /// * `mappings` - mappings for unversioned packages
+    pub fn from_legacy(
+        package_name: &str,
+        asset_data: Vec<u8>,
+        bulk_data: Option<Vec<u8>>,
+        engine_version: EngineVersion,
+        mappings: Option<Usmap>,
+    ) -> Result<Self, Error> {
+        let zen_version = get_zen_version(engine_version).ok_or(ZenError::NoObjectVersion)?;
+
+        let asset = Asset::new(
+            Cursor::new(asset_data.as_slice()),
+            bulk_data.as_deref().map(Cursor::new),
+            engine_version,
+            mappings,
+        )?;
+
+        let cooked_header_size = asset_data.len() as u32;
+        let data_resources = match asset.data_resource_offset > 0 {
+            true => read_data_resources(&asset_data, asset.data_resource_offset as u64)?,
+            false => Vec::new(),
+        };
+
+        let data = [
+            asset_data.as_slice(),
+            bulk_data.as_deref().unwrap_or_default(),
+        ]
+        .concat();
+
+        ZenPackageBuilder::new(&asset, package_name, zen_version)?.build(
+            &data,
+            cooked_header_size,
+            data_resources,
+        )
+    }
+}
+
+impl<C: Read + Seek> Asset<C> {
+    /// Convert this asset to a Zen package
+    ///
+    /// # Arguments
+    ///
+    /// * `package_name` - name of the package, e.g. `/Game/Maps/Map`
+    pub fn to_zen(&self, package_name: &str) -> Result<ZenPackage, Error> {
+        let mut asset_cursor = Cursor::new(Vec::new());
+        let mut uexp_cursor = Cursor::new(Vec::new());
+        let use_event_driven_loader = self.asset_data.use_event_driven_loader;
+
+        self.write_data(
+            &mut asset_cursor,
+            use_event_driven_loader.then_some(&mut uexp_cursor),
+        )?;
+
+        ZenPackage::from_legacy(
+            package_name,
+            asset_cursor.into_inner(),
+            use_event_driven_loader.then(|| uexp_cursor.into_inner()),
+            self.get_engine_version(),
+            self.asset_data.mappings.clone(),
+        )
+    }
+}
+
+/// Read the data resource table of a legacy package as a Zen bulk data map
+fn read_data_resources(asset_data: &[u8], offset: u64) -> Result<Vec<BulkDataMapEntry>, Error> {
+    let mut reader = Cursor::new(asset_data);
+    reader.seek(SeekFrom::Start(offset))?;
+
+    let version = reader.read_u32::<LE>()?;
+    let count = reader.read_i32::<LE>()?;
+    let mut data_resources = Vec::with_capacity(count.max(0) as usize);
+    for _ in 0..count {
+        let _flags = reader.read_u32::<LE>()?;
+        if version >= DATA_RESOURCE_VERSION_ADDED_COOKED_INDEX {
+            let _cooked_index = reader.read_u8()?;
+        }
+        let serial_offset = reader.read_i64::<LE>()?;
+        let duplicate_serial_offset = reader.read_i64::<LE>()?;
+        let serial_size = reader.read_i64::<LE>()?;
+        let _raw_size = reader.read_i64::<LE>()?;
+        let _outer_index = reader.read_i32::<LE>()?;
+        let flags = reader.read_u32::<LE>()?;
+
+        data_resources.push(BulkDataMapEntry {
+            serial_offset,
+            duplicate_serial_offset,
+            serial_size,
+            flags,
+        });
+    }
+
+    Ok(data_resources)
+}
+
+/// Builds a Zen package header from a legacy package
+struct ZenPackageBuilder<'asset, C: Read + Seek> {
+    asset: &'asset Asset<C>,
+    package_name: String,
+    zen_version: EZenPackageVersion,
+    /// Name batch, the legacy name map with the package name appended if needed
+    names: Vec<String>,
+    package_name_index: u32,
+    imported_packages: Vec<String>,
+    imported_public_export_hashes: Vec<u64>,
+    import_map: Vec<PackageObjectIndex>,
+}
+
+impl<'asset, C: Read + Seek> ZenPackageBuilder<'asset, C> {
+    fn new(
+        asset: &'asset Asset<C>,
+        package_name: &str,
+        zen_version: EZenPackageVersion,
+    ) -> Result<Self, Error> {
+        let mut names = asset
+            .get_name_map()
+            .get_ref()
+            .get_name_map_index_list()
+            .to_vec();
+        let package_name_index = match names.iter().position(|e| e == package_name) {
+            Some(index) => index as u32,
+            None => {
+                names.push(package_name.to_string());
+                names.len() as u32 - 1
+            }
+        };
+
+        let mut builder = ZenPackageBuilder {
+            asset,
+            package_name: package_name.to_string(),
+            zen_version,
+            names,
+            package_name_index,
+            imported_packages: Vec::new(),
+            imported_public_export_hashes: Vec::new(),
+            import_map: Vec::with_capacity(asset.imports.len()),
+        };
+
+        for i in 0..asset.imports.len() {
+            let import = builder.convert_import(i)?;
+            builder.import_map.push(import);
+        }
+
+        Ok(builder)
+    }
+
+    /// Get the object path of an import, starting with its package name
+    fn get_import_path(&self, index: usize) -> Result<Vec<String>, Error> {
+        let mut path = Vec::new();
+        let mut index = PackageIndex::from_import(index as i32)?;
+        while index.is_import() {
+            let import = self.asset.get_import(index).ok_or_else(|| {
+                Error::invalid_package_index(format!("Invalid import index {}", index.index))
+            })?;
+            path.push(import.object_name.to_string_with_number());
+            index = import.outer_index;
+
+            if path.len() > self.asset.imports.len() {
+                return Err(Error::invalid_package_index(
+                    "Import outer chain contains a cycle".to_string(),
+                ));
+            }
+        }
+
+        if index.is_export() {
+            return Err(Error::invalid_package_index(format!(
+                "Import {} is outered to an export",
+                path[0]
+            )));
+        }
+
+        path.reverse();
+        Ok(path)
+    }
+
+    /// Convert a legacy import to a Zen import map entry
+    fn convert_import(&mut self, index: usize) -> Result<PackageObjectIndex, Error> {
+        let path = self.get_import_path(index)?;
+        let package_name = &path[0];
+
+        if package_name.starts_with(SCRIPT_PACKAGE_PREFIX) {
+            // script object paths are hashed with all separators replaced by `/`
+            let hash = crc::cityhash64_to_lower(&path.join("/"));
+            return Ok(PackageObjectIndex::from_type(
+                EPackageObjectIndexType::ScriptImport,
+                hash,
+            ));
+        }
+
+        // imports of packages themselves are not resolved
+        if path.len() == 1 {
+            return Ok(PackageObjectIndex::NULL);
+        }
+
+        let package_index = match self
+            .imported_packages
+            .iter()
+            .position(|e| e == package_name)
+        {
+            Some(e) => e,
+            None => {
+                self.imported_packages.push(package_name.clone());
+                self.imported_packages.len() - 1
+            }
+        };
+
+        let hash = get_public_export_hash(&path[1..]);
+        let hash_index = match self
+            .imported_public_export_hashes
+            .iter()
+            .position(|e| *e == hash)
+        {
+            Some(e) => e,
+            None => {
+                self.imported_public_export_hashes.push(hash);
+                self.imported_public_export_hashes.len() - 1
+            }
+        };
+
+        Ok(PackageObjectIndex::from_package_import(
+            package_index as u32,
+            hash_index as u32,
+        ))
+    }
+
+    /// Convert a legacy package index to a Zen package object index
+    fn convert_index(&self, index: PackageIndex) -> Result<PackageObjectIndex, Error> {
+        match index.index {
+            0 => Ok(PackageObjectIndex::NULL),
+            i if i < 0 => self
+                .import_map
+                .get((-i - 1) as usize)
+                .copied()
+                .ok_or_else(|| Error::invalid_package_index(format!("Invalid import index {i}"))),
+            i => Ok(PackageObjectIndex::from_export(i as u32 - 1)),
+        }
+    }
+
+    /// Get the public export hash of an export
+    fn get_export_hash(&self, index: usize) -> Result<u64, Error> {
+        let mut path = Vec::new();
+        let mut index = PackageIndex::from_export(index as i32)?;
+        while index.is_export() {
+            let export = self
+                .asset
+                .asset_data
+                .get_export(index)
+                .ok_or_else(|| {
+                    Error::invalid_package_index(format!("Invalid export index {}", index.index))
+                })?
+                .get_base_export();
+            path.push(export.object_name.to_string_with_number());
+            index = export.outer_index;
+
+            if path.len() > self.asset.asset_data.exports.len() {
+                return Err(Error::invalid_package_index(
+                    "Export outer chain contains a cycle".to_string(),
+                ));
+            }
+        }
+
+        path.reverse();
+        Ok(get_public_export_hash(&path))
+    }
+
+    /// Build the Zen package
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - concatenated `.uasset` and `.uexp` data
+    /// * `cooked_header_size` - size of the `.uasset` header
+    /// * `bulk_data_map` - bulk data map converted from the legacy data resource table
+    fn build(
+        self,
+        data: &[u8],
+        cooked_header_size: u32,
+        bulk_data_map: Vec<BulkDataMapEntry>,
+    ) -> Result<ZenPackage, Error> {
+        let asset = self.asset;
+        let zen_version = self.zen_version;
+
+        let mut export_map = Vec::with_capacity(asset.asset_data.exports.len());
+        for (i, export) in asset.asset_data.exports.iter().enumerate() {
+            let export = export.get_base_export();
+
+            let public_export_hash = match export.object_flags.contains(EObjectFlags::RF_PUBLIC)
+                || export.generate_public_hash
+            {
+                true => self.get_export_hash(i)?,
+                false => 0,
+            };
+
+            let filter_flags = match (export.not_for_client, export.not_for_server) {
+                (true, _) => EExportFilterFlags::NotForClient,
+                (_, true) => EExportFilterFlags::NotForServer,
+                _ => EExportFilterFlags::None,
+            };
+
+            export_map.push(ZenExportMapEntry {
+                cooked_serial_offset: export.serial_offset as u64,
+                cooked_serial_size: export.serial_size as u64,
+                object_name: export.object_name.clone(),
+                outer_index: self.convert_index(export.outer_index)?,
+                class_index: self.convert_index(export.class_index)?,
+                super_index: self.convert_index(export.super_index)?,
+                template_index: self.convert_index(export.template_index)?,
+                public_export_hash,
+                object_flags: export.object_flags,
+                filter_flags,
+            });
+        }
+
+        // every export is created first and serialized afterwards, all in a single bundle
+        let export_bundle_entries = [EExportCommandType::Create, EExportCommandType::Serialize]
+            .into_iter()
+            .flat_map(|command_type| {
+                (0..export_map.len() as u32).map(move |local_export_index| ExportBundleEntry {
+                    local_export_index,
+                    command_type,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = RawWriter::<PackageObjectIndex, _>::new(
+            &mut cursor,
+            ObjectVersion::UNKNOWN,
+            ObjectVersionUE5::UNKNOWN,
+            false,
+            NameMap::new(),
+        );
+
+        let summary_size = ZenPackageSummary::serialized_size(zen_version);
+        writer.write_all(&vec![0u8; summary_size as usize])?;
+
+        let has_versioning_info = !asset.asset_data.summary.unversioned;
+        if has_versioning_info {
+            ZenPackageVersioningInfo {
+                zen_version,
+                object_version: asset.asset_data.object_version,
+                object_version_ue5: asset.asset_data.object_version_ue5,
+                licensee_version: asset.asset_data.summary.file_licensee_version,
+                custom_versions: asset.asset_data.summary.custom_versions.clone(),
+            }
+            .write(&mut writer)?;
+        }
+
+        writer.write_name_batch(&self.names)?;
+
+        if zen_version >= EZenPackageVersion::DataResourceTable {
+            writer
+                .write_i64::<LE>(bulk_data_map.len() as i64 * BulkDataMapEntry::SERIALIZED_SIZE)?;
+            for entry in &bulk_data_map {
+                entry.write(&mut writer)?;
+            }
+        }
+
+        let imported_public_export_hashes_offset = writer.position() as i32;
+        for hash in &self.imported_public_export_hashes {
+            writer.write_u64::<LE>(*hash)?;
+        }
+
+        let import_map_offset = writer.position() as i32;
+        for import in &self.import_map {
+            writer.write_u64::<LE>(import.value)?;
+        }
+
+        let export_map_offset = writer.position() as i32;
+        for entry in &export_map {
+            entry.write(&mut writer)?;
+        }
+
+        let export_bundle_entries_offset = writer.position() as i32;
+        for entry in &export_bundle_entries {
+            entry.write(&mut writer)?;
+        }
+
+        let mut summary = ZenPackageSummary {
+            has_versioning_info,
+            header_size: 0,
+            name: MappedName {
+                index: self.package_name_index,
+                number: 0,
+                ty: EMappedNameType::Package,
+            },
+            package_flags: asset.asset_data.summary.package_flags,
+            cooked_header_size,
+            imported_public_export_hashes_offset,
+            import_map_offset,
+            export_map_offset,
+            export_bundle_entries_offset,
+            graph_data_offset: -1,
+            dependency_bundle_headers_offset: -1,
+            dependency_bundle_entries_offset: -1,
+            imported_package_names_offset: -1,
+        };
+
+        match zen_version >= EZenPackageVersion::ImportedPackageNames {
+            true => {
+                // no dependencies between exports, every header has zero entries
+                summary.dependency_bundle_headers_offset = writer.position() as i32;
+                for _ in 0..export_map.len() {
+                    writer.write_i32::<LE>(0)?;
+                    writer.write_all(&[0u8; 4 * 4])?;
+                }
+                summary.dependency_bundle_entries_offset = writer.position() as i32;
+
+                summary.imported_package_names_offset = writer.position() as i32;
+                writer.write_name_batch(&self.imported_packages)?;
+                for _ in &self.imported_packages {
+                    writer.write_i32::<LE>(0)?;
+                }
+            }
+            false => {
+                summary.graph_data_offset = writer.position() as i32;
+
+                // export bundle header
+                writer.write_u64::<LE>(0)?;
+                writer.write_u32::<LE>(0)?;
+                writer.write_u32::<LE>(export_bundle_entries.len() as u32)?;
+
+                // no internal arcs and no arcs for any imported package
+                writer.write_i32::<LE>(0)?;
+                for _ in &self.imported_packages {
+                    writer.write_i32::<LE>(0)?;
+                }
+            }
+        }
+
+        summary.header_size = writer.position() as u32;
+
+        // export data follows the header in export bundle order
+        for entry in &export_map {
+            let start = entry.cooked_serial_offset as usize;
+            let end = start + entry.cooked_serial_size as usize;
+            let export_data = data.get(start..end).ok_or_else(|| {
+                Error::invalid_file(format!(
+                    "Export {} is out of bounds",
+                    entry.object_name.get_owned_content()
+                ))
+            })?;
+            writer.write_all(export_data)?;
+        }
+
+        writer.seek(SeekFrom::Start(0))?;
+        summary.write(&mut writer, zen_version)?;
+
+        let store_entry = IoPackageStoreEntry {
+            export_count: export_map.len() as i32,
+            export_bundle_count: 1,
+            imported_packages: self
+                .imported_packages
+                .iter()
+                .map(|e| get_package_id(e))
+                .collect(),
+            shader_map_hashes: Vec::new(),
+        };
+
+        Ok(ZenPackage {
+            package_id: get_package_id(&self.package_name),
+            package_name: self.package_name,
+            store_entry,
+            data: cursor.into_inner(),
+        })
+    }
+}
+
+/// Get the public export hash of an object from its path inside of its package
+fn get_public_export_hash(path: &[String]) -> u64 {
+    crc::cityhash64_to_lower(&format!("/{}", path.join("/")))
+}
@@ -0,0 +1,250 @@
+use std::io::Cursor;
+
+use unreal_asset::{
+    containers::{Chain, NameMap},
+    custom_version::FAssetRegistryVersionType,
+    engine_version::{self, EngineVersion},
+    reader::RawReader,
+    registry::{
+        objects::{asset_data::AssetData, depends_node::DependsNode},
+        AssetRegistryState,
+    },
+    Error,
+};
+
+macro_rules! assets_folder {
+    () => {
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/assets/asset_registry/")
+    };
+}
+
+/// Asset registry in the name table format written by UE 4.26 and 4.27
+const ASSET_REGISTRY: &[u8] = include_bytes!(concat!(assets_folder!(), "AssetRegistry.bin"));
+
+fn asset_registry() -> Result<AssetRegistryState, Error> {
+    let (object_version, object_version_ue5) =
+        engine_version::get_object_versions(EngineVersion::VER_UE4_27);
+    let mut reader = RawReader::new(
+        Chain::new(Cursor::new(ASSET_REGISTRY), None),
+        object_version,
+        object_version_ue5,
+        false,
+        NameMap::new(),
+    );
+    AssetRegistryState::new(&mut reader)
+}
+
+/// Asset registry with the `ClassPaths` version written by UE 5.1,
+/// its names are supplied by [`UE5_NAMES`] instead of being read from a name batch
+const ASSET_REGISTRY_UE5: &[u8] =
+    include_bytes!(concat!(assets_folder!(), "AssetRegistry_UE5.bin"));
+
+/// Names of [`ASSET_REGISTRY_UE5`], in the order they are referenced by index
+const UE5_NAMES: [&str; 23] = [
+    "/Game/Blueprints/BP_Weapon.BP_Weapon",
+    "/Game/Blueprints",
+    "/Script/Engine",
+    "Blueprint",
+    "/Game/Blueprints/BP_Weapon",
+    "BP_Weapon",
+    "ParentClass",
+    "GeneratedClass",
+    "/Game/Blueprints/BP_Player.BP_Player",
+    "/Game/Blueprints/BP_Player",
+    "BP_Player",
+    "/Game/Maps/Level.Level",
+    "/Game/Maps",
+    "World",
+    "/Game/Maps/Level",
+    "Level",
+    "/Game/Textures/T_Icon_2.T_Icon",
+    "/Game/Textures",
+    "Texture2D",
+    "/Game/Textures/T_Icon",
+    "T_Icon",
+    "Dimensions",
+    "Format",
+];
+
+fn asset_registry_ue5() -> Result<AssetRegistryState, Error> {
+    let (object_version, object_version_ue5) =
+        engine_version::get_object_versions(EngineVersion::VER_UE5_1);
+    let names = UE5_NAMES.map(String::from);
+    let mut reader = RawReader::new(
+        Chain::new(Cursor::new(ASSET_REGISTRY_UE5), None),
+        object_version,
+        object_version_ue5,
+        false,
+        NameMap::from_name_batch(&names),
+    );
+    AssetRegistryState::new(&mut reader)
+}
+
+fn package_names<'a>(assets: impl IntoIterator<Item = &'a AssetData>) -> Vec<String> {
+    assets
+        .into_iter()
+        .map(|e| e.package_name.to_string_with_number())
+        .collect()
+}
+
+fn identifiers(nodes: Vec<&DependsNode>) -> Vec<String> {
+    nodes
+        .into_iter()
+        .map(|e| {
+            e.identifier
+                .package_name
+                .as_ref()
+                .unwrap()
+                .to_string_with_number()
+        })
+        .collect()
+}
+
+#[test]
+fn asset_registry_index_assets() -> Result<(), Error> {
+    let asset_registry = asset_registry()?;
+    assert_eq!(
+        asset_registry.get_version(),
+        FAssetRegistryVersionType::AddedDependencyFlags
+    );
+    let index = asset_registry.index();
+
+    let weapon = index
+        .get_asset_by_object_path("/Game/Blueprints/BP_Weapon.BP_Weapon")
+        .unwrap();
+    assert_eq!(weapon.asset_name, "BP_Weapon");
+    assert!(index
+        .get_asset_by_object_path("/Game/Blueprints/BP_Weapon")
+        .is_none());
+
+    // names ending in a number are stored with an instance number
+    let icon = index
+        .get_asset_by_object_path("/Game/Textures/T_Icon_2.T_Icon_2")
+        .unwrap();
+    assert_eq!(icon.asset_name.get_number(), 3);
+
+    assert_eq!(
+        package_names(index.get_assets_by_package_name("/Game/Maps/Level")),
+        vec!["/Game/Maps/Level"]
+    );
+    // registries before ClassPaths only store the class name
+    assert_eq!(
+        package_names(index.get_assets_by_class("Blueprint")),
+        vec!["/Game/Blueprints/BP_Weapon", "/Game/Blueprints/BP_Player"]
+    );
+    assert!(index
+        .get_assets_by_class("/Script/Engine.Blueprint")
+        .is_empty());
+
+    assert_eq!(index.get_assets_by_tag("ParentClass").len(), 2);
+    assert_eq!(
+        package_names(index.get_assets_by_tag_value("ParentClass", "Class'/Script/Engine.Actor'")),
+        vec!["/Game/Blueprints/BP_Weapon"]
+    );
+    assert_eq!(
+        package_names(index.get_assets_by_tag_value("Dimensions", "256x256")),
+        vec!["/Game/Textures/T_Icon_2"]
+    );
+    assert!(index.get_assets_by_tag("MissingTag").is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn asset_registry_ue5_index_assets() -> Result<(), Error> {
+    let asset_registry = asset_registry_ue5()?;
+    assert_eq!(
+        asset_registry.get_version(),
+        FAssetRegistryVersionType::ClassPaths
+    );
+    let index = asset_registry.index();
+
+    let weapon = index
+        .get_asset_by_object_path("/Game/Blueprints/BP_Weapon.BP_Weapon")
+        .unwrap();
+    assert!(weapon.asset_class.is_none());
+    let class = weapon.asset_path.as_ref().unwrap();
+    assert_eq!(class.package_name, "/Script/Engine");
+    assert_eq!(class.asset_name, "Blueprint");
+
+    // registries since ClassPaths store the class as a TopLevelAssetPath
+    assert_eq!(
+        package_names(index.get_assets_by_class("/Script/Engine.Blueprint")),
+        vec!["/Game/Blueprints/BP_Weapon", "/Game/Blueprints/BP_Player"]
+    );
+    assert_eq!(
+        package_names(index.get_assets_by_class("/Script/Engine.Texture2D")),
+        vec!["/Game/Textures/T_Icon_2"]
+    );
+    assert!(index.get_assets_by_class("Blueprint").is_empty());
+
+    assert_eq!(
+        identifiers(index.get_dependencies("/Game/Maps/Level")),
+        vec![
+            "/Script/Engine",
+            "/Game/Blueprints/BP_Player",
+            "/Game/Blueprints/BP_Weapon"
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn asset_registry_index_dependencies() -> Result<(), Error> {
+    let asset_registry = asset_registry()?;
+    let index = asset_registry.index();
+
+    let player = index
+        .get_depends_node("/Game/Blueprints/BP_Player")
+        .unwrap();
+    assert_eq!(player.hard_dependencies.len(), 2);
+    assert_eq!(player.soft_dependencies.len(), 1);
+
+    assert_eq!(
+        identifiers(index.get_dependencies("/Game/Blueprints/BP_Player")),
+        vec![
+            "/Script/Engine",
+            "/Game/Blueprints/BP_Weapon",
+            "/Game/Textures/T_Icon_2"
+        ]
+    );
+    assert_eq!(
+        identifiers(index.get_referencers("/Game/Blueprints/BP_Weapon")),
+        vec!["/Game/Blueprints/BP_Player", "/Game/Maps/Level"]
+    );
+    assert_eq!(
+        identifiers(index.get_referencers("/Game/Textures/T_Icon_2")),
+        vec!["/Game/Blueprints/BP_Weapon", "/Game/Blueprints/BP_Player"]
+    );
+    assert!(index.get_referencers("/Game/Maps/Level").is_empty());
+    assert!(index.get_dependencies("/Game/Missing").is_empty());
+
+    // the computed referencers match the serialized ones
+    for node in &asset_registry.depends_nodes {
+        let package_name = node
+            .identifier
+            .package_name
+            .as_ref()
+            .unwrap()
+            .to_string_with_number();
+        let referencers = index
+            .get_referencers(&package_name)
+            .into_iter()
+            .map(DependsNode::get_index)
+            .collect::<Vec<_>>();
+        let serialized = node
+            .referencers
+            .iter()
+            .map(DependsNode::get_index)
+            .collect::<Vec<_>>();
+        assert_eq!(referencers, serialized, "{package_name}");
+    }
+
+    assert_eq!(
+        package_names(index.get_referencing_assets("/Game/Blueprints/BP_Player")),
+        vec!["/Game/Maps/Level"]
+    );
+
+    Ok(())
+}
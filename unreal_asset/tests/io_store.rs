@@ -0,0 +1,356 @@
+use std::io::Cursor;
+
+use unreal_asset::{
+    compression::CompressionMethod,
+    enums::{EIoChunkType, EIoStoreTocVersion},
+    flags::EIoContainerFlags,
+    io_store::{
+        directory_index::{IoDirectoryIndexEntry, IoFileIndexEntry, INVALID_INDEX},
+        toc::{
+            IoOffsetAndLength, IoStoreTocCompressedBlockEntry, IoStoreTocEntryMeta,
+            IoStoreTocHeader,
+        },
+        IoChunkId, IoDirectoryIndex, IoStoreReader, IoStoreToc, IoStoreWriter,
+    },
+    Error, Guid,
+};
+
+const BLOCK_SIZE: u64 = 0x100;
+
+fn test_files() -> Vec<(IoChunkId, Vec<u8>)> {
+    vec![
+        (
+            IoChunkId::new(1, 0, EIoChunkType::ExportBundleData),
+            (0..300u32).map(|e| (e * 7) as u8).collect(),
+        ),
+        (
+            IoChunkId::new(2, 0, EIoChunkType::BulkData),
+            b"bulk".repeat(12),
+        ),
+    ]
+}
+
+/// Build an uncompressed container with a partition size of two blocks
+fn build_container() -> (Vec<u8>, Vec<Vec<u8>>) {
+    let partition_size = BLOCK_SIZE * 2;
+
+    let mut chunk_offset_lengths = Vec::new();
+    let mut compression_blocks = Vec::new();
+    let mut partitions = vec![Vec::new(), Vec::new()];
+    let mut offset = 0;
+    for (_, data) in test_files() {
+        chunk_offset_lengths.push(IoOffsetAndLength {
+            offset,
+            length: data.len() as u64,
+        });
+
+        for block in data.chunks(BLOCK_SIZE as usize) {
+            compression_blocks.push(IoStoreTocCompressedBlockEntry {
+                offset,
+                compressed_size: block.len() as u32,
+                uncompressed_size: block.len() as u32,
+                compression_method_index: 0,
+            });
+
+            let partition = &mut partitions[(offset / partition_size) as usize];
+            partition.resize((offset % partition_size) as usize, 0);
+            partition.extend_from_slice(block);
+            offset += BLOCK_SIZE;
+        }
+    }
+
+    let directory_index = IoDirectoryIndex {
+        mount_point: "../../../".to_string(),
+        directory_entries: vec![
+            IoDirectoryIndexEntry {
+                name: INVALID_INDEX,
+                first_child_entry: 1,
+                next_sibling_entry: INVALID_INDEX,
+                first_file_entry: INVALID_INDEX,
+            },
+            IoDirectoryIndexEntry {
+                name: 0,
+                first_child_entry: INVALID_INDEX,
+                next_sibling_entry: INVALID_INDEX,
+                first_file_entry: 0,
+            },
+        ],
+        file_entries: vec![
+            IoFileIndexEntry {
+                name: 1,
+                next_file_entry: 1,
+                user_data: 0,
+            },
+            IoFileIndexEntry {
+                name: 2,
+                next_file_entry: INVALID_INDEX,
+                user_data: 1,
+            },
+        ],
+        string_table: vec![
+            "Game".to_string(),
+            "Asset.uasset".to_string(),
+            "Asset.ubulk".to_string(),
+        ],
+    };
+    let mut directory_index_data = Cursor::new(Vec::new());
+    directory_index.write(&mut directory_index_data).unwrap();
+    let directory_index_data = directory_index_data.into_inner();
+
+    let toc = IoStoreToc {
+        header: IoStoreTocHeader {
+            version: EIoStoreTocVersion::ReplaceIoChunkHashWithIoHash,
+            entry_count: test_files().len() as u32,
+            compressed_block_entry_count: compression_blocks.len() as u32,
+            compression_method_name_count: 0,
+            compression_block_size: BLOCK_SIZE as u32,
+            directory_index_size: directory_index_data.len() as u32,
+            partition_count: partitions.len() as u32,
+            container_id: 0x1234,
+            encryption_key_guid: Guid::default(),
+            container_flags: EIoContainerFlags::INDEXED,
+            perfect_hash_seeds_count: 0,
+            partition_size,
+            chunks_without_perfect_hash_count: 0,
+        },
+        chunk_ids: test_files().into_iter().map(|(id, _)| id).collect(),
+        chunk_offset_lengths,
+        chunk_perfect_hash_seeds: Vec::new(),
+        chunk_indices_without_perfect_hash: Vec::new(),
+        compression_blocks,
+        compression_methods: Vec::new(),
+        signatures: None,
+        directory_index: directory_index_data,
+        chunk_metas: vec![IoStoreTocEntryMeta::default(); test_files().len()],
+    };
+
+    let mut toc_data = Cursor::new(Vec::new());
+    toc.write(&mut toc_data).unwrap();
+
+    (toc_data.into_inner(), partitions)
+}
+
+#[test]
+fn io_store_reader() -> Result<(), Error> {
+    let (toc, partitions) = build_container();
+
+    let mut reader = IoStoreReader::new(
+        &mut Cursor::new(toc),
+        partitions.into_iter().map(Cursor::new).collect(),
+    )?;
+
+    assert_eq!(reader.mount_point, "../../../");
+    assert_eq!(
+        reader.get_file_names(),
+        vec!["../../../Game/Asset.uasset", "../../../Game/Asset.ubulk"]
+    );
+    assert_eq!(reader.get_chunk_ids().len(), 2);
+
+    let files = test_files();
+    assert_eq!(reader.read_file("../../../Game/Asset.uasset")?, files[0].1);
+    assert_eq!(reader.read_chunk(&files[1].0)?, files[1].1);
+    assert_eq!(
+        reader.get_file_chunk_id("../../../Game/Asset.ubulk"),
+        Some(files[1].0)
+    );
+    assert!(reader.read_file("../../../Game/Missing.uasset").is_err());
+
+    let mut count = 0;
+    for ((_, data), (_, expected)) in reader.iter().zip(files.iter()) {
+        assert_eq!(&data?, expected);
+        count += 1;
+    }
+    assert_eq!(count, 2);
+
+    Ok(())
+}
+
+#[test]
+fn io_store_invalid_magic() {
+    let (mut toc, partitions) = build_container();
+    toc[0] = b'x';
+
+    assert!(IoStoreReader::new(
+        &mut Cursor::new(toc),
+        partitions.into_iter().map(Cursor::new).collect(),
+    )
+    .is_err());
+}
+
+#[test]
+fn io_store_invalid_partitions() {
+    for range in [52..56, 88..96] {
+        let (mut toc, partitions) = build_container();
+        toc[range].fill(0);
+
+        let err = IoStoreReader::new(
+            &mut Cursor::new(toc),
+            partitions.into_iter().map(Cursor::new).collect(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Invalid .utoc partitions"));
+    }
+}
+
+#[test]
+fn io_store_invalid_signature_size() {
+    let (mut toc, partitions) = build_container();
+    toc[80] |= EIoContainerFlags::SIGNED.bits();
+
+    // signatures follow the compression blocks, there are no compression methods
+    let entry_count = u32::from_le_bytes(toc[24..28].try_into().unwrap()) as usize;
+    let block_count = u32::from_le_bytes(toc[28..32].try_into().unwrap()) as usize;
+    let offset = 144 + entry_count * (12 + 10) + block_count * 12;
+    toc.splice(offset..offset, (-1i32).to_le_bytes());
+
+    let err = IoStoreReader::new(
+        &mut Cursor::new(toc),
+        partitions.into_iter().map(Cursor::new).collect(),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("signature size -1"));
+}
+
+/// Rewrite the .utoc of a container with `modify` applied and open it
+fn open_modified(modify: impl FnOnce(&mut IoStoreToc)) -> IoStoreReader<Cursor<Vec<u8>>> {
+    let (toc, partitions) = build_container();
+    let mut toc = IoStoreToc::read(&mut Cursor::new(toc)).unwrap();
+    modify(&mut toc);
+
+    let mut toc_data = Cursor::new(Vec::new());
+    toc.write(&mut toc_data).unwrap();
+    toc_data.set_position(0);
+    IoStoreReader::new(
+        &mut toc_data,
+        partitions.into_iter().map(Cursor::new).collect(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn io_store_directory_index_cycle() {
+    let directory_index = |directories: &[(u32, u32)], next_file_entry: u32| IoDirectoryIndex {
+        mount_point: "../../../".to_string(),
+        directory_entries: directories
+            .iter()
+            .map(
+                |&(first_child_entry, next_sibling_entry)| IoDirectoryIndexEntry {
+                    name: 0,
+                    first_child_entry,
+                    next_sibling_entry,
+                    first_file_entry: 0,
+                },
+            )
+            .collect(),
+        file_entries: vec![IoFileIndexEntry {
+            name: 0,
+            next_file_entry,
+            user_data: 0,
+        }],
+        string_table: vec!["Game".to_string()],
+    };
+
+    // a file linking to itself
+    let err = directory_index(&[(INVALID_INDEX, INVALID_INDEX)], 0)
+        .get_files()
+        .unwrap_err();
+    assert!(err.to_string().contains("linked more than once"));
+
+    // a directory that is its own sibling and a child of its child
+    for directories in [
+        &[(1, INVALID_INDEX), (INVALID_INDEX, 1)][..],
+        &[(1, INVALID_INDEX), (0, INVALID_INDEX)][..],
+    ] {
+        let mut directory_index = directory_index(directories, INVALID_INDEX);
+        for directory in &mut directory_index.directory_entries[1..] {
+            directory.first_file_entry = INVALID_INDEX;
+        }
+        let err = directory_index.get_files().unwrap_err();
+        assert!(err.to_string().contains("linked more than once"));
+    }
+}
+
+#[test]
+fn io_store_invalid_chunk() {
+    let files = test_files();
+
+    // a length past the end of the container
+    let mut reader = open_modified(|toc| toc.chunk_offset_lengths[1].length = 1 << 39);
+    let err = reader.read_chunk(&files[1].0).unwrap_err();
+    assert!(err.to_string().contains("out of bounds"));
+
+    // uncompressed blocks with a smaller compressed size
+    let mut reader = open_modified(|toc| toc.compression_blocks[0].compressed_size = 16);
+    let err = reader.read_chunk(&files[0].0).unwrap_err();
+    assert!(err.to_string().contains("Invalid compression block 0"));
+
+    // blocks larger than the compression block size
+    let mut reader = open_modified(|toc| {
+        let block = &mut toc.compression_blocks[0];
+        block.compressed_size = BLOCK_SIZE as u32 * 2;
+        block.uncompressed_size = BLOCK_SIZE as u32 * 2;
+    });
+    let err = reader.read_chunk(&files[0].0).unwrap_err();
+    assert!(err.to_string().contains("Invalid compression block 0"));
+}
+
+#[test]
+fn io_store_writer_compression_methods() -> Result<(), Error> {
+    let mut writer = IoStoreWriter::new(
+        Cursor::new(Vec::new()),
+        EIoStoreTocVersion::PerfectHashWithOverflow,
+        0x1234,
+        "../../../".to_string(),
+    );
+    writer.set_compression_block_size(BLOCK_SIZE as u32)?;
+
+    let chunks = [
+        (
+            CompressionMethod::Zlib,
+            "Game/Zlib.uasset",
+            b"zlib".repeat(100),
+        ),
+        (
+            CompressionMethod::None,
+            "Game/None.uasset",
+            b"none".repeat(100),
+        ),
+        (
+            CompressionMethod::Gzip,
+            "Game/Gzip.uasset",
+            b"gzip".repeat(100),
+        ),
+        (
+            CompressionMethod::Zlib,
+            "Game/Zlib2.uasset",
+            b"zlib2".repeat(100),
+        ),
+    ];
+    for (i, (compression, path, data)) in chunks.iter().enumerate() {
+        writer.set_compression(compression.clone());
+        writer.write_chunk(
+            IoChunkId::new(i as u64, 0, EIoChunkType::ExportBundleData),
+            Some(path),
+            data,
+        )?;
+    }
+    // offsets of the written chunks depend on the block size
+    assert!(writer.set_compression_block_size(0x10000).is_err());
+
+    let mut toc = Cursor::new(Vec::new());
+    let partition = writer.finish_write(&mut toc)?;
+
+    let mut reader = IoStoreReader::new(
+        &mut Cursor::new(toc.into_inner()),
+        vec![Cursor::new(partition.into_inner())],
+    )?;
+    assert_eq!(
+        reader.get_toc().compression_methods,
+        vec![CompressionMethod::Zlib, CompressionMethod::Gzip]
+    );
+    for (_, path, data) in chunks {
+        assert_eq!(reader.read_file(&format!("../../../{path}"))?, data);
+    }
+
+    Ok(())
+}
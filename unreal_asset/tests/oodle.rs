@@ -0,0 +1,54 @@
+use std::io::Cursor;
+
+use unreal_asset::{
+    compression::{self, CompressionMethod},
+    containers::IndexedMap,
+    object_version::{ObjectVersion, ObjectVersionUE5},
+    unversioned::{EUsmapCompressionMethod, EUsmapVersion, Usmap, UsmapExtensionVersion},
+    Error,
+};
+
+#[test]
+fn oodle_compression_method() {
+    let method = CompressionMethod::new("Oodle");
+    assert_eq!(method, CompressionMethod::Oodle);
+    assert_eq!(method.to_string(), "Oodle");
+}
+
+// no Oodle library is loaded in the tests
+#[test]
+fn oodle_not_loaded() {
+    let mut decompressed = [0u8; 4];
+    assert!(matches!(
+        compression::decompress(CompressionMethod::Oodle, &[0u8; 4], &mut decompressed),
+        Err(Error::OodleNotInitialized)
+    ));
+    assert!(matches!(
+        compression::compress(CompressionMethod::Oodle, b"data"),
+        Err(Error::OodleNotInitialized)
+    ));
+
+    let usmap = Usmap {
+        version: EUsmapVersion::Initial,
+        name_map: Vec::new(),
+        enum_map: IndexedMap::new(),
+        schemas: IndexedMap::new(),
+        extension_version: UsmapExtensionVersion::NONE,
+        object_version: ObjectVersion::UNKNOWN,
+        object_version_ue5: ObjectVersionUE5::UNKNOWN,
+        custom_versions: Vec::new(),
+        compression_method: EUsmapCompressionMethod::Oodle,
+        net_cl: 0,
+    };
+    assert!(matches!(
+        usmap.write(&mut Cursor::new(Vec::new())),
+        Err(Error::OodleNotInitialized)
+    ));
+}
+
+#[cfg(feature = "oodle")]
+#[test]
+fn oodle_load_missing_library() {
+    assert!(unsafe { compression::oodle::load("/nonexistent/liboo2corelinux64.so.9") }.is_err());
+    assert!(!compression::oodle::is_loaded());
+}
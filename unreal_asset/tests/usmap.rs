@@ -0,0 +1,379 @@
+use std::io::Cursor;
+
+use unreal_asset::containers::IndexedMap;
+use unreal_asset::custom_version::{CustomVersion, FCoreObjectVersion};
+use unreal_asset::object_version::{ObjectVersion, ObjectVersionUE5};
+use unreal_asset::unversioned::{
+    properties::{
+        array_property::UsmapArrayPropertyData, enum_property::UsmapEnumPropertyData,
+        map_property::UsmapMapPropertyData, set_property::UsmapSetPropertyData,
+        shallow_property::UsmapShallowPropertyData, struct_property::UsmapStructPropertyData,
+        EPropertyType, UsmapProperty, UsmapPropertyData,
+    },
+    EUsmapCompressionMethod, EUsmapVersion, Usmap, UsmapExtensionVersion, UsmapSchema,
+};
+
+fn shallow(property_type: EPropertyType) -> Box<UsmapPropertyData> {
+    Box::new(UsmapShallowPropertyData { property_type }.into())
+}
+
+fn schema(
+    name: &str,
+    super_type: &str,
+    module_path: &str,
+    properties: Vec<(&str, u8, UsmapPropertyData)>,
+) -> UsmapSchema {
+    let mut map = IndexedMap::new();
+    let mut schema_index = 0;
+    for (name, array_size, property_data) in properties {
+        // properties with an array size are expanded the same way they are when reading
+        for array_index in 0..array_size as u16 {
+            let property = UsmapProperty {
+                name: name.to_string(),
+                schema_index: schema_index + array_index,
+                array_size,
+                array_index,
+                property_data: property_data.clone(),
+            };
+            map.insert(
+                (property.name.clone(), property.schema_index as u32),
+                property,
+            );
+        }
+        schema_index += array_size as u16;
+    }
+
+    UsmapSchema {
+        name: name.to_string(),
+        super_type: super_type.to_string(),
+        prop_count: schema_index,
+        module_path: Some(module_path.to_string()),
+        properties: map,
+    }
+}
+
+fn test_usmap(compression_method: EUsmapCompressionMethod) -> Usmap {
+    let mut enum_map = IndexedMap::new();
+    enum_map.insert(
+        "EColor".to_string(),
+        vec![
+            "EColor::Red".to_string(),
+            "EColor::Green".to_string(),
+            "EColor::EColor_MAX".to_string(),
+        ],
+    );
+
+    let enum_property = UsmapEnumPropertyData {
+        inner_property: shallow(EPropertyType::ByteProperty),
+        name: "EColor".to_string(),
+    };
+
+    let mut schemas = IndexedMap::new();
+    for schema in [
+        schema(
+            "Vector",
+            "",
+            "/Script/CoreUObject",
+            vec![
+                ("X", 1, *shallow(EPropertyType::FloatProperty)),
+                ("Y", 1, *shallow(EPropertyType::FloatProperty)),
+                ("Z", 1, *shallow(EPropertyType::FloatProperty)),
+            ],
+        ),
+        schema(
+            "TestActor",
+            "Actor",
+            "/Script/Test",
+            vec![
+                ("Color", 1, enum_property.clone().into()),
+                (
+                    "Location",
+                    1,
+                    UsmapStructPropertyData {
+                        struct_type: "Vector".to_string(),
+                    }
+                    .into(),
+                ),
+                ("Corners", 4, *shallow(EPropertyType::IntProperty)),
+                (
+                    "Names",
+                    1,
+                    UsmapArrayPropertyData {
+                        inner_type: shallow(EPropertyType::NameProperty),
+                    }
+                    .into(),
+                ),
+                (
+                    "Tags",
+                    1,
+                    UsmapSetPropertyData {
+                        inner_type: shallow(EPropertyType::StrProperty),
+                    }
+                    .into(),
+                ),
+                (
+                    "Colors",
+                    1,
+                    UsmapMapPropertyData {
+                        inner_type: Box::new(enum_property.into()),
+                        value_type: shallow(EPropertyType::ObjectProperty),
+                    }
+                    .into(),
+                ),
+            ],
+        ),
+    ] {
+        schemas.insert(schema.name.clone(), schema);
+    }
+
+    Usmap {
+        version: EUsmapVersion::PackageVersioning,
+        name_map: Vec::new(),
+        enum_map,
+        schemas,
+        extension_version: UsmapExtensionVersion::PATHS,
+        object_version: ObjectVersion::VER_UE4_AUTOMATIC_VERSION,
+        object_version_ue5: ObjectVersionUE5::UNKNOWN,
+        custom_versions: vec![CustomVersion::from_version(
+            FCoreObjectVersion::LatestVersion,
+        )],
+        compression_method,
+        net_cl: 12345,
+    }
+}
+
+#[test]
+fn usmap_round_trip() {
+    for compression_method in [
+        EUsmapCompressionMethod::None,
+        EUsmapCompressionMethod::Brotli,
+        EUsmapCompressionMethod::ZStandard,
+    ] {
+        let mut usmap = test_usmap(compression_method);
+
+        let mut cursor = Cursor::new(Vec::new());
+        usmap.write(&mut cursor).unwrap();
+        let data = cursor.into_inner();
+
+        let parsed = Usmap::new(Cursor::new(data.clone())).unwrap();
+
+        // the name map is rebuilt when writing
+        assert!(parsed.name_map.contains(&"EColor::Green".to_string()));
+        assert!(parsed.name_map.contains(&"Vector".to_string()));
+        usmap.name_map = parsed.name_map.clone();
+        assert_eq!(parsed, usmap, "{compression_method:?}");
+
+        let mut cursor = Cursor::new(Vec::new());
+        parsed.write(&mut cursor).unwrap();
+        assert_eq!(cursor.into_inner(), data, "{compression_method:?}");
+    }
+}
+
+#[test]
+fn usmap_without_versioning() {
+    let mut usmap = test_usmap(EUsmapCompressionMethod::None);
+    usmap.version = EUsmapVersion::Initial;
+    usmap.extension_version = UsmapExtensionVersion::NONE;
+    usmap.object_version = ObjectVersion::UNKNOWN;
+    usmap.custom_versions = Vec::new();
+    usmap.net_cl = 0;
+    for schema in usmap.schemas.values_mut() {
+        schema.module_path = None;
+    }
+
+    let mut cursor = Cursor::new(Vec::new());
+    usmap.write(&mut cursor).unwrap();
+
+    let parsed = Usmap::new(Cursor::new(cursor.into_inner())).unwrap();
+    usmap.name_map = parsed.name_map.clone();
+    assert_eq!(parsed, usmap);
+}
+
+#[test]
+fn usmap_missing_module_path() {
+    let mut usmap = test_usmap(EUsmapCompressionMethod::None);
+    usmap
+        .schemas
+        .get_by_key_mut("TestActor")
+        .unwrap()
+        .module_path = None;
+    assert!(usmap.write(&mut Cursor::new(Vec::new())).is_err());
+
+    // merging keeps module paths only if all schemas have one
+    let mut other = mod_usmap();
+    other.extension_version = UsmapExtensionVersion::NONE;
+    usmap.merge(other).unwrap();
+    assert_eq!(usmap.extension_version, UsmapExtensionVersion::NONE);
+    usmap.write(&mut Cursor::new(Vec::new())).unwrap();
+}
+
+/// Mappings in the initial format written by mappings dumpers,
+/// names are stored as a length byte followed by the name without a terminator
+#[test]
+fn usmap_dumper_mappings() {
+    let data = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/assets/usmap/Mappings.usmap"
+    ));
+    let usmap = Usmap::new(Cursor::new(data.to_vec())).unwrap();
+
+    assert_eq!(usmap.version, EUsmapVersion::Initial);
+    assert_eq!(usmap.extension_version, UsmapExtensionVersion::NONE);
+    assert_eq!(&usmap.name_map[..4], ["X", "Y", "Z", "Vector"]);
+    assert_eq!(
+        usmap.enum_map.get_by_key("EColor").unwrap(),
+        &["EColor::Red", "EColor::Green", "EColor::EColor_MAX"]
+    );
+    assert_eq!(
+        usmap.schemas.keys().collect::<Vec<_>>(),
+        vec!["Vector", "Rotator", "Actor", "Pawn"]
+    );
+
+    // schemas without a super type
+    let vector = usmap.schemas.get_by_key("Vector").unwrap();
+    assert_eq!(vector.super_type, "");
+    assert!(vector.module_path.is_none());
+    assert_eq!(
+        vector
+            .properties
+            .values()
+            .map(|e| e.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["X", "Y", "Z"]
+    );
+
+    let pawn = usmap.schemas.get_by_key("Pawn").unwrap();
+    assert_eq!(pawn.super_type, "Actor");
+    assert_eq!(pawn.prop_count, 7);
+    assert_eq!(pawn.properties.len(), 7);
+    assert_eq!(pawn.get_property("Ammo", 5).unwrap().array_index, 2);
+    assert!(matches!(
+        pawn.get_property("Color", 0).unwrap().property_data,
+        UsmapPropertyData::UsmapEnumPropertyData(ref data) if data.name == "EColor"
+    ));
+    assert_eq!(usmap.get_all_properties("Pawn").len(), 7 + 2);
+
+    let mut cursor = Cursor::new(Vec::new());
+    usmap.write(&mut cursor).unwrap();
+    let mut parsed = Usmap::new(Cursor::new(cursor.into_inner())).unwrap();
+    parsed.name_map = usmap.name_map.clone();
+    assert_eq!(parsed, usmap);
+}
+
+#[test]
+fn usmap_unsupported_compression() {
+    let usmap = test_usmap(EUsmapCompressionMethod::Oodle);
+    assert!(usmap.write(&mut Cursor::new(Vec::new())).is_err());
+}
+
+fn mod_usmap() -> Usmap {
+    let mut usmap = test_usmap(EUsmapCompressionMethod::None);
+    usmap.enum_map = IndexedMap::new();
+    usmap.enum_map.insert(
+        "E_ModState".to_string(),
+        vec!["E_ModState::NewEnumerator0".to_string()],
+    );
+    usmap.schemas = IndexedMap::new();
+    for schema in [
+        // same as in the base usmap
+        schema(
+            "Vector",
+            "",
+            "/Script/CoreUObject",
+            vec![
+                ("X", 1, *shallow(EPropertyType::FloatProperty)),
+                ("Y", 1, *shallow(EPropertyType::FloatProperty)),
+                ("Z", 1, *shallow(EPropertyType::FloatProperty)),
+            ],
+        ),
+        schema(
+            "BP_ModActor_C",
+            "TestActor",
+            "/Game/Mods/BP_ModActor",
+            vec![
+                (
+                    "State",
+                    1,
+                    UsmapEnumPropertyData {
+                        inner_property: shallow(EPropertyType::ByteProperty),
+                        name: "E_ModState".to_string(),
+                    }
+                    .into(),
+                ),
+                (
+                    "Offset",
+                    1,
+                    UsmapStructPropertyData {
+                        struct_type: "Vector".to_string(),
+                    }
+                    .into(),
+                ),
+            ],
+        ),
+    ] {
+        usmap.schemas.insert(schema.name.clone(), schema);
+    }
+    usmap
+}
+
+#[test]
+fn usmap_merge() {
+    let mut usmap = test_usmap(EUsmapCompressionMethod::None);
+    usmap.extension_version = UsmapExtensionVersion::NONE;
+    usmap.merge(mod_usmap()).unwrap();
+
+    assert_eq!(usmap.extension_version, UsmapExtensionVersion::PATHS);
+    assert_eq!(
+        usmap.schemas.keys().collect::<Vec<_>>(),
+        vec!["Vector", "TestActor", "BP_ModActor_C"]
+    );
+    assert_eq!(
+        usmap.enum_map.keys().collect::<Vec<_>>(),
+        vec!["EColor", "E_ModState"]
+    );
+
+    // properties of mod types are found through their engine super types
+    assert_eq!(usmap.get_all_properties("BP_ModActor_C").len(), 2 + 9);
+
+    let mut cursor = Cursor::new(Vec::new());
+    usmap.write(&mut cursor).unwrap();
+    let mut parsed = Usmap::new(Cursor::new(cursor.into_inner())).unwrap();
+    parsed.name_map = usmap.name_map.clone();
+    assert_eq!(parsed, usmap);
+}
+
+#[test]
+fn usmap_merge_conflicts() {
+    let base = test_usmap(EUsmapCompressionMethod::None);
+
+    // differing property count
+    let mut other = mod_usmap();
+    other.schemas.get_by_key_mut("Vector").unwrap().prop_count = 4;
+    let mut usmap = base.clone();
+    assert!(usmap.merge(other).is_err());
+    assert_eq!(usmap, base);
+
+    // differing property type
+    let mut other = mod_usmap();
+    for property in other
+        .schemas
+        .get_by_key_mut("Vector")
+        .unwrap()
+        .properties
+        .values_mut()
+    {
+        property.property_data = *shallow(EPropertyType::DoubleProperty);
+    }
+    let mut usmap = base.clone();
+    assert!(usmap.merge(other).is_err());
+    assert_eq!(usmap, base);
+
+    // differing enum values
+    let mut other = mod_usmap();
+    other
+        .enum_map
+        .insert("EColor".to_string(), vec!["EColor::Blue".to_string()]);
+    let mut usmap = base.clone();
+    assert!(usmap.merge(other).is_err());
+    assert_eq!(usmap, base);
+}
@@ -0,0 +1,229 @@
+use std::io::Cursor;
+
+use unreal_asset::{
+    engine_version::EngineVersion,
+    fproperty::FProperty,
+    types::FName,
+    unversioned::{
+        properties::{
+            array_property::UsmapArrayPropertyData, enum_property::UsmapEnumPropertyData,
+            shallow_property::UsmapShallowPropertyData, struct_property::UsmapStructPropertyData,
+            EPropertyType, UsmapPropertyData,
+        },
+        Usmap, UsmapExtensionVersion,
+    },
+    usmap_builder::UsmapBuilder,
+    Asset, Error, Export,
+};
+#[cfg(feature = "pak")]
+use unreal_pak::{pakversion::PakVersion, PakReader, PakWriter};
+
+macro_rules! assets_folder {
+    () => {
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/assets/")
+    };
+}
+
+const WEAPON_ASSET: &[u8] = include_bytes!(concat!(
+    assets_folder!(),
+    "general/pseudoregalia/BP_looseWeapon.uasset"
+));
+const WEAPON_BULK: &[u8] = include_bytes!(concat!(
+    assets_folder!(),
+    "general/pseudoregalia/BP_looseWeapon.uexp"
+));
+const STRUCT_ASSET: &[u8] = include_bytes!(concat!(
+    assets_folder!(),
+    "user_defined_struct/achievements_STRUCT_entry.uasset"
+));
+const STRUCT_BULK: &[u8] = include_bytes!(concat!(
+    assets_folder!(),
+    "user_defined_struct/achievements_STRUCT_entry.uexp"
+));
+// uses UProperty exports instead of FProperties
+const MENU_ASSET: &[u8] = include_bytes!(concat!(
+    assets_folder!(),
+    "general/Astroneer_prebulk/DebugMenu.uasset"
+));
+
+fn shallow(property_type: EPropertyType) -> UsmapPropertyData {
+    UsmapShallowPropertyData { property_type }.into()
+}
+
+fn get_property_data<'a>(usmap: &'a Usmap, schema: &str, name: &str) -> &'a UsmapPropertyData {
+    &usmap
+        .schemas
+        .get_by_key(schema)
+        .unwrap()
+        .properties
+        .values()
+        .find(|e| e.name == name)
+        .unwrap_or_else(|| panic!("{schema} has no property {name}"))
+        .property_data
+}
+
+#[test]
+fn usmap_builder() -> Result<(), Error> {
+    let mut builder = UsmapBuilder::new();
+    for (asset_data, bulk_data, engine_version, package_name) in [
+        (
+            WEAPON_ASSET,
+            Some(WEAPON_BULK),
+            EngineVersion::VER_UE5_1,
+            "/Game/BP_looseWeapon",
+        ),
+        (
+            STRUCT_ASSET,
+            Some(STRUCT_BULK),
+            EngineVersion::VER_UE4_26,
+            "/Game/achievements_STRUCT_entry",
+        ),
+        (
+            MENU_ASSET,
+            None,
+            EngineVersion::VER_UE4_23,
+            "/Game/DebugMenu",
+        ),
+    ] {
+        let asset = Asset::new(
+            Cursor::new(asset_data),
+            bulk_data.map(Cursor::new),
+            engine_version,
+            None,
+        )?;
+        builder.add_asset(&asset, Some(package_name))?;
+    }
+    let usmap = builder.build();
+
+    let weapon = usmap.schemas.get_by_key("BP_looseWeapon_C").unwrap();
+    assert_eq!(weapon.super_type, "Actor");
+    assert_eq!(weapon.module_path.as_deref(), Some("/Game/BP_looseWeapon"));
+    assert_eq!(weapon.prop_count as usize, weapon.properties.len());
+    assert_eq!(
+        get_property_data(&usmap, "BP_looseWeapon_C", "weaponState"),
+        &UsmapEnumPropertyData {
+            inner_property: Box::new(shallow(EPropertyType::ByteProperty)),
+            name: "EN_WeaponState".to_string(),
+        }
+        .into()
+    );
+    assert_eq!(
+        get_property_data(&usmap, "BP_looseWeapon_C", "hitActorsArray"),
+        &UsmapArrayPropertyData {
+            inner_type: Box::new(shallow(EPropertyType::ObjectProperty)),
+        }
+        .into()
+    );
+    assert_eq!(
+        get_property_data(&usmap, "BP_looseWeapon_C", "ST Hitbox Data"),
+        &UsmapStructPropertyData {
+            struct_type: "ST_HitboxData".to_string(),
+        }
+        .into()
+    );
+
+    let user_defined_struct = usmap
+        .schemas
+        .get_by_key("achievements_STRUCT_entry")
+        .unwrap();
+    assert_eq!(user_defined_struct.prop_count, 2);
+    assert_eq!(
+        get_property_data(
+            &usmap,
+            "achievements_STRUCT_entry",
+            "unlockValue_21_26A8F1674CBC6C898FC0379BF365A929"
+        ),
+        &shallow(EPropertyType::IntProperty)
+    );
+
+    let menu = usmap.schemas.get_by_key("DebugMenu_C").unwrap();
+    assert_eq!(menu.super_type, "UserWidget");
+    assert_eq!(menu.prop_count, 74);
+    // numbered names keep their instance number
+    assert_eq!(
+        get_property_data(&usmap, "DebugMenu_C", "Button_0"),
+        &shallow(EPropertyType::ObjectProperty)
+    );
+
+    // the built mappings can be written and read back
+    let mut cursor = Cursor::new(Vec::new());
+    usmap.write(&mut cursor)?;
+    let mut parsed = Usmap::new(Cursor::new(cursor.into_inner()))?;
+    assert_eq!(parsed.extension_version, UsmapExtensionVersion::PATHS);
+    parsed.name_map = usmap.name_map.clone();
+    assert_eq!(parsed, usmap);
+
+    Ok(())
+}
+
+#[test]
+fn usmap_builder_unknown_property_type() -> Result<(), Error> {
+    let mut asset = Asset::new(
+        Cursor::new(WEAPON_ASSET),
+        Some(Cursor::new(WEAPON_BULK)),
+        EngineVersion::VER_UE5_1,
+        None,
+    )?;
+    let generic_property = asset
+        .asset_data
+        .exports
+        .iter_mut()
+        .filter_map(|e| match e {
+            Export::ClassExport(class_export) => Some(class_export),
+            _ => None,
+        })
+        .flat_map(|e| e.struct_export.loaded_properties.iter_mut())
+        .find_map(|e| match e {
+            FProperty::FGenericProperty(property) => Some(property),
+            _ => None,
+        })
+        .unwrap();
+    generic_property.serialized_type = Some(FName::new_dummy("CustomProperty".to_string(), 0));
+
+    let mut builder = UsmapBuilder::new();
+    assert!(builder
+        .add_asset(&asset, Some("/Game/BP_looseWeapon"))
+        .is_err());
+
+    Ok(())
+}
+
+#[cfg(feature = "pak")]
+#[test]
+fn usmap_builder_pak() -> Result<(), Error> {
+    let mut output = Cursor::new(Vec::new());
+    let mut pak = PakWriter::new(&mut output, PakVersion::Fnv64BugFix);
+    for (name, data) in [
+        ("Game/Content/Weapons/BP_looseWeapon.uasset", WEAPON_ASSET),
+        ("Game/Content/Weapons/BP_looseWeapon.uexp", WEAPON_BULK),
+        ("Game/Content/Broken.uasset", b"not an asset".as_slice()),
+    ] {
+        pak.write_entry(&name.to_string(), &data.to_vec(), true)
+            .unwrap();
+    }
+    pak.finish_write().unwrap();
+
+    let mut pak = PakReader::new(Cursor::new(output.into_inner()));
+    pak.load_index().unwrap();
+
+    let mut builder = UsmapBuilder::new();
+    let failed = builder.add_pak(&mut pak, EngineVersion::VER_UE5_1).unwrap();
+    assert_eq!(
+        failed.into_iter().map(|(name, _)| name).collect::<Vec<_>>(),
+        vec!["Game/Content/Broken.uasset"]
+    );
+
+    let usmap = builder.build();
+    assert_eq!(usmap.schemas.len(), 1);
+    assert_eq!(
+        usmap
+            .schemas
+            .get_by_key("BP_looseWeapon_C")
+            .unwrap()
+            .module_path
+            .as_deref(),
+        Some("/Game/Weapons/BP_looseWeapon")
+    );
+
+    Ok(())
+}
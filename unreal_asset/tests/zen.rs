@@ -0,0 +1,399 @@
+use std::io::{Cursor, Write};
+
+use byteorder::{WriteBytesExt, LE};
+
+use unreal_asset::{
+    cast,
+    engine_version::EngineVersion,
+    enums::{EExportCommandType, EIoContainerHeaderVersion, HASH_VERSION_CITYHASH64},
+    exports::{ExportBaseTrait, ExportNormalTrait},
+    flags::EPackageFlags,
+    io_store::{container_header::get_package_id, IoContainerHeader, IoPackageStoreEntry},
+    reader::ArchiveTrait,
+    types::{EPackageObjectIndexType, FName, PackageIndex, PackageObjectIndex},
+    zen::{GlobalImportDatabase, ImportedExport, ScriptObjects, ZenAsset},
+    Asset, Error, Export,
+};
+
+const SUMMARY_SIZE: usize = 44;
+
+fn script_engine() -> PackageObjectIndex {
+    PackageObjectIndex::from_type(EPackageObjectIndexType::ScriptImport, 0x10)
+}
+
+fn script_static_mesh() -> PackageObjectIndex {
+    PackageObjectIndex::from_type(EPackageObjectIndexType::ScriptImport, 0x11)
+}
+
+fn other_package_import() -> PackageObjectIndex {
+    PackageObjectIndex::from_package_import(0, 0)
+}
+
+/// Container header of a container with the test package, which imports `/Game/Other`
+fn build_container_header() -> IoContainerHeader {
+    let mut container_header =
+        IoContainerHeader::new(EIoContainerHeaderVersion::LocalizedPackages, 0);
+    container_header
+        .package_ids
+        .push(get_package_id("/Game/Test"));
+    container_header.store_entries.push(IoPackageStoreEntry {
+        imported_packages: vec![get_package_id("/Game/Other")],
+        ..Default::default()
+    });
+    container_header
+}
+
+fn write_name_batch(cursor: &mut Cursor<Vec<u8>>, names: &[&str]) {
+    cursor.write_i32::<LE>(names.len() as i32).unwrap();
+    cursor
+        .write_u32::<LE>(names.iter().map(|e| e.len() as u32).sum())
+        .unwrap();
+    cursor.write_u64::<LE>(HASH_VERSION_CITYHASH64).unwrap();
+    for _ in names {
+        cursor.write_u64::<LE>(0).unwrap();
+    }
+    for name in names {
+        cursor.write_u8((name.len() >> 8) as u8).unwrap();
+        cursor.write_u8(name.len() as u8).unwrap();
+    }
+    for name in names {
+        cursor.write_all(name.as_bytes()).unwrap();
+    }
+}
+
+fn build_script_objects() -> Vec<u8> {
+    let mut cursor = Cursor::new(Vec::new());
+    write_name_batch(&mut cursor, &["/Script/Engine", "StaticMesh"]);
+
+    let entries = [
+        (0, script_engine(), PackageObjectIndex::NULL),
+        (1, script_static_mesh(), script_engine()),
+    ];
+    cursor.write_i32::<LE>(entries.len() as i32).unwrap();
+    for (name, global_index, outer_index) in entries {
+        cursor.write_u32::<LE>(name).unwrap();
+        cursor.write_u32::<LE>(0).unwrap();
+        cursor.write_u64::<LE>(global_index.value).unwrap();
+        cursor.write_u64::<LE>(outer_index.value).unwrap();
+        cursor
+            .write_u64::<LE>(PackageObjectIndex::NULL.value)
+            .unwrap();
+    }
+
+    cursor.into_inner()
+}
+
+/// Build a UE 5.1 zen package with a single export of a script class
+/// Build a package, packages since UE 5.2 have a bulk data map after the name batch
+fn build_package(bulk_data_map_size: Option<i64>) -> Vec<u8> {
+    let mut cursor = Cursor::new(vec![0u8; SUMMARY_SIZE]);
+    cursor.set_position(SUMMARY_SIZE as u64);
+    write_name_batch(&mut cursor, &["/Game/Test", "Test", "None"]);
+    if let Some(bulk_data_map_size) = bulk_data_map_size {
+        cursor.write_i64::<LE>(bulk_data_map_size).unwrap();
+    }
+
+    let imported_public_export_hashes_offset = cursor.position();
+    cursor.write_u64::<LE>(0x1234_5678).unwrap();
+
+    let import_map_offset = cursor.position();
+    cursor.write_u64::<LE>(script_static_mesh().value).unwrap();
+    cursor
+        .write_u64::<LE>(other_package_import().value)
+        .unwrap();
+
+    let export_map_offset = cursor.position();
+    // export data is an empty property list followed by 4 bytes of extras
+    cursor.write_u64::<LE>(0).unwrap();
+    cursor.write_u64::<LE>(12).unwrap();
+    cursor.write_u32::<LE>(1).unwrap();
+    cursor.write_u32::<LE>(0).unwrap();
+    cursor
+        .write_u64::<LE>(PackageObjectIndex::NULL.value)
+        .unwrap();
+    cursor.write_u64::<LE>(script_static_mesh().value).unwrap();
+    cursor
+        .write_u64::<LE>(PackageObjectIndex::NULL.value)
+        .unwrap();
+    cursor
+        .write_u64::<LE>(PackageObjectIndex::NULL.value)
+        .unwrap();
+    cursor.write_u64::<LE>(0xdead_beef).unwrap();
+    cursor.write_u32::<LE>(0).unwrap();
+    cursor.write_u8(0).unwrap();
+    cursor.write_all(&[0u8; 3]).unwrap();
+
+    let export_bundle_entries_offset = cursor.position();
+    for command_type in [EExportCommandType::Create, EExportCommandType::Serialize] {
+        cursor.write_u32::<LE>(0).unwrap();
+        cursor.write_u32::<LE>(command_type.into()).unwrap();
+    }
+
+    let graph_data_offset = cursor.position();
+    let header_size = cursor.position();
+
+    cursor.write_i32::<LE>(2).unwrap();
+    cursor.write_i32::<LE>(0).unwrap();
+    cursor.write_u32::<LE>(0).unwrap();
+
+    cursor.set_position(0);
+    cursor.write_u32::<LE>(0).unwrap();
+    cursor.write_u32::<LE>(header_size as u32).unwrap();
+    cursor.write_u32::<LE>(0).unwrap();
+    cursor.write_u32::<LE>(0).unwrap();
+    cursor
+        .write_u32::<LE>((EPackageFlags::PKG_COOKED | EPackageFlags::PKG_FILTER_EDITOR_ONLY).bits())
+        .unwrap();
+    cursor.write_u32::<LE>(header_size as u32).unwrap();
+    for offset in [
+        imported_public_export_hashes_offset,
+        import_map_offset,
+        export_map_offset,
+        export_bundle_entries_offset,
+        graph_data_offset,
+    ] {
+        cursor.write_i32::<LE>(offset as i32).unwrap();
+    }
+
+    cursor.into_inner()
+}
+
+#[test]
+fn zen_script_objects() -> Result<(), Error> {
+    let script_objects = ScriptObjects::read(Cursor::new(build_script_objects()))?;
+
+    assert_eq!(script_objects.entries.len(), 2);
+    assert_eq!(
+        script_objects
+            .get(script_static_mesh())
+            .unwrap()
+            .object_name,
+        "StaticMesh"
+    );
+    assert_eq!(
+        script_objects.get_object_path(script_static_mesh()),
+        Some("/Script/Engine.StaticMesh".to_string())
+    );
+    assert_eq!(
+        script_objects.get_object_path(script_engine()),
+        Some("/Script/Engine".to_string())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn zen_package() -> Result<(), Error> {
+    let script_objects = ScriptObjects::read(Cursor::new(build_script_objects()))?;
+    let asset = ZenAsset::new(
+        Cursor::new(build_package(None)),
+        EngineVersion::VER_UE5_1,
+        None,
+        Some(&script_objects),
+    )?;
+
+    assert_eq!(asset.get_package_name().get_owned_content(), "/Game/Test");
+    assert_eq!(asset.imported_public_export_hashes, vec![0x1234_5678]);
+    assert_eq!(
+        asset.import_map,
+        vec![script_static_mesh(), other_package_import()]
+    );
+    assert_eq!(asset.export_bundle_entries.len(), 2);
+
+    assert_eq!(asset.export_map.len(), 1);
+    let entry = &asset.export_map[0];
+    assert_eq!(entry.object_name.get_owned_content(), "Test");
+    assert_eq!(entry.public_export_hash, 0xdead_beef);
+    assert!(entry.outer_index.is_null());
+
+    let class_name = asset
+        .get_object_name_packageindex(PackageIndex::new(-1))
+        .unwrap();
+    assert_eq!(class_name.get_owned_content(), "StaticMesh");
+
+    assert_eq!(asset.asset_data.exports.len(), 1);
+    let export = &asset.asset_data.exports[0];
+    assert_eq!(export.get_base_export().class_index, script_static_mesh());
+    assert_eq!(
+        export.get_base_export().serial_offset,
+        asset.summary.header_size as i64
+    );
+
+    let normal_export = cast!(Export, NormalExport, export).unwrap();
+    assert!(normal_export.properties.is_empty());
+    assert_eq!(export.get_normal_export().unwrap().extras, vec![0u8; 4]);
+
+    Ok(())
+}
+
+#[test]
+fn zen_package_no_engine_version() {
+    assert!(ZenAsset::new(
+        Cursor::new(build_package(None)),
+        EngineVersion::UNKNOWN,
+        None,
+        None
+    )
+    .is_err());
+}
+
+#[test]
+fn zen_package_invalid_bulk_data_map() {
+    assert!(ZenAsset::new(
+        Cursor::new(build_package(Some(0))),
+        EngineVersion::VER_UE5_2,
+        None,
+        None
+    )
+    .is_ok());
+
+    for bulk_data_map_size in [-32, 32 * 1000] {
+        assert!(ZenAsset::new(
+            Cursor::new(build_package(Some(bulk_data_map_size))),
+            EngineVersion::VER_UE5_2,
+            None,
+            None
+        )
+        .is_err());
+    }
+}
+
+#[test]
+fn zen_package_invalid_offsets() {
+    let package = build_package(None);
+    let offset = |index: usize| i32::from_le_bytes(package[index..index + 4].try_into().unwrap());
+    let export_map_offset = offset(32);
+
+    // import map offset past the export map offset
+    let mut data = package.clone();
+    data[28..32].copy_from_slice(&(export_map_offset + 8).to_le_bytes());
+    assert!(ZenAsset::new(Cursor::new(data), EngineVersion::VER_UE5_1, None, None).is_err());
+
+    // graph data offset past the end of the package
+    let mut data = package.clone();
+    data[40..44].copy_from_slice(&(package.len() as i32 + 16).to_le_bytes());
+    assert!(ZenAsset::new(Cursor::new(data), EngineVersion::VER_UE5_1, None, None).is_err());
+}
+
+#[test]
+fn zen_to_legacy() -> Result<(), Error> {
+    let mut database =
+        GlobalImportDatabase::new(ScriptObjects::read(Cursor::new(build_script_objects()))?);
+    // packages before 5.3 store imported package ids in the container header
+    database.add_container_header(&build_container_header());
+    database.add_package(
+        "/Game/Other".to_string(),
+        [(
+            0x1234_5678,
+            ImportedExport {
+                object_name: "Other".to_string(),
+                class_package: "/Script/Engine".to_string(),
+                class_name: "StaticMesh".to_string(),
+            },
+        )],
+    );
+
+    let mut zen_asset = ZenAsset::new(
+        Cursor::new(build_package(None)),
+        EngineVersion::VER_UE5_1,
+        None,
+        Some(&database.script_objects),
+    )?;
+
+    let mut uasset = Cursor::new(Vec::new());
+    let mut uexp = Cursor::new(Vec::new());
+    zen_asset.write_legacy(&database, &mut uasset, &mut uexp)?;
+
+    uasset.set_position(0);
+    uexp.set_position(0);
+    let asset = Asset::new(uasset, Some(uexp), EngineVersion::VER_UE5_1, None)?;
+
+    let imports = asset
+        .imports
+        .iter()
+        .map(|e| {
+            (
+                e.class_package.get_owned_content(),
+                e.class_name.get_owned_content(),
+                e.outer_index.index,
+                e.object_name.get_owned_content(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        imports,
+        vec![
+            (
+                "/Script/CoreUObject".to_string(),
+                "Class".to_string(),
+                -3,
+                "StaticMesh".to_string()
+            ),
+            (
+                "/Script/Engine".to_string(),
+                "StaticMesh".to_string(),
+                -4,
+                "Other".to_string()
+            ),
+            (
+                "/Script/CoreUObject".to_string(),
+                "Package".to_string(),
+                0,
+                "/Script/Engine".to_string()
+            ),
+            (
+                "/Script/CoreUObject".to_string(),
+                "Package".to_string(),
+                0,
+                "/Game/Other".to_string()
+            ),
+        ]
+    );
+
+    assert_eq!(asset.asset_data.exports.len(), 1);
+    let export = &asset.asset_data.exports[0];
+    let base_export = export.get_base_export();
+    assert_eq!(base_export.object_name.get_owned_content(), "Test");
+    assert_eq!(base_export.class_index, PackageIndex::new(-1));
+    assert!(base_export.is_asset);
+    assert_eq!(export.get_normal_export().unwrap().extras, vec![0u8; 4]);
+
+    Ok(())
+}
+
+#[test]
+fn zen_to_legacy_unresolved_import() -> Result<(), Error> {
+    let mut database =
+        GlobalImportDatabase::new(ScriptObjects::read(Cursor::new(build_script_objects()))?);
+
+    let mut zen_asset = ZenAsset::new(
+        Cursor::new(build_package(None)),
+        EngineVersion::VER_UE5_1,
+        None,
+        Some(&database.script_objects),
+    )?;
+    // the imported package isn't known without the container header
+    assert!(zen_asset.to_legacy(&database).is_err());
+
+    // the imported package is known, but its exports aren't
+    database.add_container_header(&build_container_header());
+    assert!(zen_asset.to_legacy(&database).is_err());
+
+    // imported package names take precedence over the container header
+    database.add_package(
+        "/Game/Other".to_string(),
+        [(
+            0x1234_5678,
+            ImportedExport {
+                object_name: "Other".to_string(),
+                class_package: "/Script/Engine".to_string(),
+                class_name: "StaticMesh".to_string(),
+            },
+        )],
+    );
+    assert!(zen_asset.to_legacy(&database).is_ok());
+    zen_asset.imported_package_names = vec![FName::from_slice("/Game/Missing")];
+    assert!(zen_asset.to_legacy(&database).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,263 @@
+use std::io::Cursor;
+
+use unreal_asset::{
+    compression::CompressionMethod,
+    engine_version::EngineVersion,
+    enums::{EIoChunkType, EIoContainerHeaderVersion},
+    io_store::{
+        container_header::{get_container_id, get_package_id},
+        IoChunkId, IoContainerHeader, IoStoreReader,
+    },
+    zen::{container::get_package_name, ZenAsset, ZenContainerWriter, ZenPackage},
+    Asset, Error,
+};
+#[cfg(feature = "pak")]
+use unreal_pak::{pakversion::PakVersion, PakReader, PakWriter};
+
+macro_rules! assets_folder {
+    () => {
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/assets/ue5/")
+    };
+}
+
+const TEST_ASSETS: [(&str, &[u8], &[u8]); 2] = [
+    (
+        "Game/Content/Maps/PublicHousingPlot_Root.umap",
+        include_bytes!(concat!(assets_folder!(), "PublicHousingPlot_Root.umap")),
+        include_bytes!(concat!(assets_folder!(), "PublicHousingPlot_Root.uexp")),
+    ),
+    (
+        "Game/Content/Maps/Village_Root.umap",
+        include_bytes!(concat!(assets_folder!(), "Village_Root.umap")),
+        include_bytes!(concat!(assets_folder!(), "Village_Root.uexp")),
+    ),
+];
+
+struct Container {
+    toc: Vec<u8>,
+    partition: Vec<u8>,
+}
+
+/// Create a container writer with both test assets, a bulk data file and a loose file
+fn container_writer(
+    compression: CompressionMethod,
+) -> Result<ZenContainerWriter<Cursor<Vec<u8>>>, Error> {
+    let mut writer = ZenContainerWriter::new(
+        Cursor::new(Vec::new()),
+        "pakchunk0-Windows",
+        "../../../".to_string(),
+        EngineVersion::VER_UE5_1,
+    )?;
+    writer.set_compression(compression);
+
+    for (path, asset_data, bulk_data) in TEST_ASSETS {
+        writer.write_legacy_package(path, asset_data.to_vec(), Some(bulk_data.to_vec()))?;
+    }
+    writer.write_file("Game/Content/Maps/Village_Root.ubulk", b"bulk".repeat(64))?;
+    writer.write_file("Game/Config/DefaultGame.ini", b"[/Script/Engine]".to_vec())?;
+
+    Ok(writer)
+}
+
+fn build_container(compression: CompressionMethod) -> Result<Container, Error> {
+    let writer = container_writer(compression)?;
+
+    let mut toc = Cursor::new(Vec::new());
+    let partition = writer.finish_write(&mut toc)?;
+
+    Ok(Container {
+        toc: toc.into_inner(),
+        partition: partition.into_inner(),
+    })
+}
+
+#[test]
+fn zen_package_name() {
+    assert_eq!(
+        get_package_name("../../../Game/Content/Maps/Map.umap"),
+        Some("/Game/Maps/Map".to_string())
+    );
+    assert_eq!(
+        get_package_name("MyGame/Content/Blueprints/BP_Test.uasset"),
+        Some("/Game/Blueprints/BP_Test".to_string())
+    );
+    assert_eq!(
+        get_package_name("Engine/Content/EngineMaterials/Default.uasset"),
+        Some("/Engine/EngineMaterials/Default".to_string())
+    );
+    assert_eq!(
+        get_package_name("MyGame/Plugins/MyPlugin/Content/Test.uasset"),
+        Some("/MyPlugin/Test".to_string())
+    );
+    assert_eq!(get_package_name("MyGame/Config/DefaultGame.ini"), None);
+}
+
+#[test]
+fn zen_from_legacy() -> Result<(), Error> {
+    for (path, asset_data, bulk_data) in TEST_ASSETS {
+        let asset = Asset::new(
+            Cursor::new(asset_data),
+            Some(Cursor::new(bulk_data)),
+            EngineVersion::VER_UE5_1,
+            None,
+        )?;
+
+        let package_name = get_package_name(path).unwrap();
+        let package = asset.to_zen(&package_name)?;
+        assert_eq!(package.package_id, get_package_id(&package_name));
+        assert_eq!(
+            package.store_entry.export_count as usize,
+            asset.asset_data.exports.len()
+        );
+
+        let zen_asset = ZenAsset::new(
+            Cursor::new(package.data.as_slice()),
+            EngineVersion::VER_UE5_1,
+            None,
+            None,
+        )?;
+        assert_eq!(
+            zen_asset.get_package_name().get_owned_content(),
+            package_name
+        );
+        assert_eq!(zen_asset.import_map.len(), asset.imports.len());
+        assert_eq!(
+            zen_asset.export_bundle_entries.len(),
+            asset.asset_data.exports.len() * 2
+        );
+
+        // export data is stored unchanged after the header
+        let legacy_data = [asset_data, bulk_data].concat();
+        let mut offset = zen_asset.summary.header_size as usize;
+        assert_eq!(zen_asset.export_map.len(), asset.asset_data.exports.len());
+        for entry in &zen_asset.export_map {
+            let start = entry.cooked_serial_offset as usize;
+            let size = entry.cooked_serial_size as usize;
+            assert_eq!(
+                package.data[offset..offset + size],
+                legacy_data[start..start + size]
+            );
+            offset += size;
+        }
+        assert_eq!(offset, package.data.len());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn zen_from_legacy_no_engine_version() {
+    let (_, asset_data, bulk_data) = TEST_ASSETS[0];
+    assert!(ZenPackage::from_legacy(
+        "/Game/Maps/PublicHousingPlot_Root",
+        asset_data.to_vec(),
+        Some(bulk_data.to_vec()),
+        EngineVersion::VER_UE4_27,
+        None,
+    )
+    .is_err());
+}
+
+#[test]
+fn zen_container_writer() -> Result<(), Error> {
+    for compression in [CompressionMethod::None, CompressionMethod::Zlib] {
+        let Container { toc, partition } = build_container(compression)?;
+
+        let mut reader = IoStoreReader::new(&mut Cursor::new(toc), vec![Cursor::new(partition)])?;
+        assert_eq!(reader.mount_point, "../../../");
+        assert_eq!(
+            reader.get_file_names(),
+            vec![
+                "../../../Game/Content/Maps/PublicHousingPlot_Root.umap",
+                "../../../Game/Content/Maps/Village_Root.ubulk",
+                "../../../Game/Content/Maps/Village_Root.umap",
+            ]
+        );
+        assert_eq!(
+            reader.read_file("../../../Game/Content/Maps/Village_Root.ubulk")?,
+            b"bulk".repeat(64)
+        );
+
+        let package_id = get_package_id("/Game/Maps/Village_Root");
+        assert_eq!(
+            reader.get_file_chunk_id("../../../Game/Content/Maps/Village_Root.umap"),
+            Some(IoChunkId::new(
+                package_id,
+                0,
+                EIoChunkType::ExportBundleData
+            ))
+        );
+
+        let package = reader.read_file("../../../Game/Content/Maps/Village_Root.umap")?;
+        let zen_asset = ZenAsset::new(Cursor::new(package), EngineVersion::VER_UE5_1, None, None)?;
+        assert_eq!(
+            zen_asset.get_package_name().get_owned_content(),
+            "/Game/Maps/Village_Root"
+        );
+
+        let container_id = get_container_id("pakchunk0-Windows");
+        let container_header = IoContainerHeader::read(&mut Cursor::new(reader.read_chunk(
+            &IoChunkId::new(container_id, 0, EIoChunkType::ContainerHeader),
+        )?))?;
+        assert_eq!(
+            container_header.version,
+            EIoContainerHeaderVersion::LocalizedPackages
+        );
+        assert_eq!(container_header.container_id, container_id);
+        assert_eq!(
+            container_header.package_ids,
+            vec![
+                get_package_id("/Game/Maps/PublicHousingPlot_Root"),
+                package_id
+            ]
+        );
+        assert_eq!(container_header.store_entries.len(), 2);
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "pak")]
+#[test]
+fn zen_container_writer_companion_pak() -> Result<(), Error> {
+    let writer = container_writer(CompressionMethod::Zlib)?;
+
+    let mut pak = Cursor::new(Vec::new());
+    let mut pak_writer = PakWriter::new(&mut pak, PakVersion::Fnv64BugFix);
+    writer.write_companion_pak(&mut pak_writer).unwrap();
+    pak_writer.finish_write().unwrap();
+
+    let mut pak_reader = PakReader::new(Cursor::new(pak.into_inner()));
+    pak_reader.load_index().unwrap();
+    assert_eq!(pak_reader.mount_point, "../../../");
+    assert_eq!(
+        pak_reader.get_entry_names(),
+        vec!["Game/Config/DefaultGame.ini"]
+    );
+    assert_eq!(
+        pak_reader
+            .read_entry(&"Game/Config/DefaultGame.ini".to_string())
+            .unwrap(),
+        b"[/Script/Engine]"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn zen_container_writer_double_write() -> Result<(), Error> {
+    let mut writer = ZenContainerWriter::new(
+        Cursor::new(Vec::new()),
+        "pakchunk0-Windows",
+        "../../../".to_string(),
+        EngineVersion::VER_UE5_1,
+    )?;
+
+    let (path, asset_data, bulk_data) = TEST_ASSETS[0];
+    writer.write_legacy_package(path, asset_data.to_vec(), Some(bulk_data.to_vec()))?;
+    assert!(writer
+        .write_legacy_package(path, asset_data.to_vec(), Some(bulk_data.to_vec()))
+        .is_err());
+
+    Ok(())
+}
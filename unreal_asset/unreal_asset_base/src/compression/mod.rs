@@ -1,11 +1,15 @@
-//! Unreal decompression
+//! Unreal compression and decompression
 
-use std::io::Read;
+use std::io::{Read, Write};
 
 use flate2::bufread::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
 
 use crate::Error;
 
+#[cfg(feature = "oodle")]
+pub mod oodle;
+
 /// Compression method
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
 pub enum CompressionMethod {
@@ -18,6 +22,8 @@ pub enum CompressionMethod {
     Gzip,
     /// Lz4 compression
     Lz4,
+    /// Oodle compression, requires the `oodle` feature and a loaded Oodle library
+    Oodle,
     /// Unknown compression format
     Unknown(Box<str>),
 }
@@ -30,6 +36,7 @@ impl CompressionMethod {
             "Zlib" => Self::Zlib,
             "Gzip" => Self::Gzip,
             "LZ4" => Self::Lz4,
+            "Oodle" => Self::Oodle,
             _ => Self::Unknown(name.to_string().into_boxed_str()),
         }
     }
@@ -42,6 +49,7 @@ impl std::fmt::Display for CompressionMethod {
             CompressionMethod::Zlib => f.write_str("Zlib"),
             CompressionMethod::Gzip => f.write_str("Gzip"),
             CompressionMethod::Lz4 => f.write_str("LZ4"),
+            CompressionMethod::Oodle => f.write_str("Oodle"),
             CompressionMethod::Unknown(e) => write!(f, "{e}"),
         }
     }
@@ -64,6 +72,33 @@ pub fn decompress(
             lz4_flex::block::decompress_into(compressed, decompressed)?;
             Ok(())
         }
+        #[cfg(feature = "oodle")]
+        CompressionMethod::Oodle => oodle::decompress(compressed, decompressed),
+        #[cfg(not(feature = "oodle"))]
+        CompressionMethod::Oodle => Err(Error::OodleNotInitialized),
+        CompressionMethod::Unknown(name) => Err(Error::UnknownCompressionMethod(name)),
+    }
+}
+
+/// Compress data with the given compression method
+pub fn compress(method: CompressionMethod, data: &[u8]) -> Result<Vec<u8>, Error> {
+    match method {
+        CompressionMethod::None => Ok(data.to_vec()),
+        CompressionMethod::Zlib => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+        CompressionMethod::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+        CompressionMethod::Lz4 => Ok(lz4_flex::block::compress(data)),
+        #[cfg(feature = "oodle")]
+        CompressionMethod::Oodle => oodle::compress(data),
+        #[cfg(not(feature = "oodle"))]
+        CompressionMethod::Oodle => Err(Error::OodleNotInitialized),
         CompressionMethod::Unknown(name) => Err(Error::UnknownCompressionMethod(name)),
     }
 }
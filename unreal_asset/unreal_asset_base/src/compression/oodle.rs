@@ -0,0 +1,30 @@
+//! Oodle compression and decompression
+//!
+//! Oodle can not be distributed with this crate, instead the shared library shipped with a game
+//! (e.g. `oo2core_9_win64.dll` or `liboo2corelinux64.so.9`) is loaded at runtime with [`load`].
+//! The library is shared with `unreal_pak`, so it only has to be loaded once.
+
+use unreal_helpers::error::OodleError;
+use unreal_helpers::oodle::{self, OodleCompressor, LEVEL_NORMAL};
+
+pub use unreal_helpers::oodle::{is_loaded, load};
+
+use crate::Error;
+
+/// Decompress Oodle compressed data, `decompressed` has to be the size of the decompressed data
+pub fn decompress(compressed: &[u8], decompressed: &mut [u8]) -> Result<(), Error> {
+    oodle::decompress(compressed, decompressed).map_err(to_error)
+}
+
+/// Compress data with the Kraken compressor
+pub fn compress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    oodle::compress(data, OodleCompressor::Kraken, LEVEL_NORMAL).map_err(to_error)
+}
+
+/// Convert an `OodleError` to an `Error`
+fn to_error(e: OodleError) -> Error {
+    match e {
+        OodleError::NotLoaded => Error::OodleNotInitialized,
+        _ => Error::Oodle,
+    }
+}
@@ -72,7 +72,9 @@ pub enum ECustomVersionSerializationFormat {
 }
 
 /// Zen package version
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, TryFromPrimitive, IntoPrimitive)]
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, TryFromPrimitive, IntoPrimitive,
+)]
 #[repr(u32)]
 pub enum EZenPackageVersion {
     /// Initial
@@ -88,6 +90,16 @@ pub enum EZenPackageVersion {
     LatestPlusOne,
 }
 
+/// Zen package export bundle command type
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, TryFromPrimitive, IntoPrimitive)]
+#[repr(u32)]
+pub enum EExportCommandType {
+    /// Create the export
+    Create,
+    /// Serialize the export
+    Serialize,
+}
+
 /// IoStore container header version
 #[derive(
     Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, TryFromPrimitive, IntoPrimitive,
@@ -108,3 +120,63 @@ pub enum EIoContainerHeaderVersion {
     /// Latest plus one
     LatestPlusOne,
 }
+
+/// IoStore table of contents version
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, TryFromPrimitive, IntoPrimitive,
+)]
+#[repr(u8)]
+pub enum EIoStoreTocVersion {
+    /// Invalid
+    Invalid = 0,
+    /// Initial
+    Initial,
+    /// Directory index
+    DirectoryIndex,
+    /// Partition size
+    PartitionSize,
+    /// Perfect hash
+    PerfectHash,
+    /// Perfect hash with overflow
+    PerfectHashWithOverflow,
+    /// On demand metadata
+    OnDemandMetaData,
+    /// Removed on demand metadata
+    RemovedOnDemandMetaData,
+    /// Replace IoChunkHash with IoHash
+    ReplaceIoChunkHashWithIoHash,
+}
+
+/// IoStore chunk type
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum EIoChunkType {
+    /// Invalid
+    Invalid = 0,
+    /// Export bundle data
+    ExportBundleData,
+    /// Bulk data
+    BulkData,
+    /// Optional bulk data
+    OptionalBulkData,
+    /// Memory mapped bulk data
+    MemoryMappedBulkData,
+    /// Script objects
+    ScriptObjects,
+    /// Container header
+    ContainerHeader,
+    /// External file
+    ExternalFile,
+    /// Shader code library
+    ShaderCodeLibrary,
+    /// Shader code
+    ShaderCode,
+    /// Package store entry
+    PackageStoreEntry,
+    /// Derived data
+    DerivedData,
+    /// Editor derived data
+    EditorDerivedData,
+    /// Package resource
+    PackageResource,
+}
@@ -34,7 +34,7 @@ impl KismetError {
     }
 }
 
-/// Thrown when a usmap file failed to deserialize
+/// Thrown when a usmap file failed to deserialize or serialize
 #[derive(Error, Debug)]
 pub enum UsmapError {
     /// Unsupported usmap compression
@@ -46,6 +46,18 @@ pub enum UsmapError {
     /// Name map index out of range
     #[error("Name map index out of range, name map size: {0}, got: {1}")]
     NameMapIndexOutOfRange(usize, i32),
+    /// Name not found in the name map
+    #[error("Name {0} not found in the name map")]
+    NameNotFound(Box<str>),
+    /// Two schemas with the same name have different properties
+    #[error("Conflicting definitions for schema {0}")]
+    SchemaConflict(Box<str>),
+    /// Two enums with the same name have different values
+    #[error("Conflicting definitions for enum {0}")]
+    EnumConflict(Box<str>),
+    /// A schema has no module path while the usmap stores module paths
+    #[error("Schema {0} has no module path")]
+    MissingModulePath(Box<str>),
 }
 
 impl UsmapError {
@@ -63,6 +75,26 @@ impl UsmapError {
     pub fn name_map_index_out_of_range(name_map_size: usize, index: i32) -> Self {
         UsmapError::NameMapIndexOutOfRange(name_map_size, index)
     }
+
+    /// Create an `UsmapError` for a name that is not in the name map
+    pub fn name_not_found(name: &str) -> Self {
+        UsmapError::NameNotFound(name.to_string().into_boxed_str())
+    }
+
+    /// Create an `UsmapError` for a schema that is defined differently in two usmaps
+    pub fn schema_conflict(name: &str) -> Self {
+        UsmapError::SchemaConflict(name.to_string().into_boxed_str())
+    }
+
+    /// Create an `UsmapError` for an enum that is defined differently in two usmaps
+    pub fn enum_conflict(name: &str) -> Self {
+        UsmapError::EnumConflict(name.to_string().into_boxed_str())
+    }
+
+    /// Create an `UsmapError` for a schema without a module path
+    pub fn missing_module_path(name: &str) -> Self {
+        UsmapError::MissingModulePath(name.to_string().into_boxed_str())
+    }
 }
 
 /// Thrown when asset registry failed to deserialize
@@ -258,9 +290,15 @@ pub enum IoStoreError {
     /// Invalid enum value
     #[error("{0}")]
     InvalidEnumValue(Box<str>),
-    /// Tried to get a non-existent file from an `IoStoreFileProvider`
+    /// Tried to get a non-existent file from an `IoStoreReader`
     #[error("Tried to get a non-existent file {0}")]
     NoFile(Box<str>),
+    /// Tried to write the same chunk twice to an `IoStoreWriter`
+    #[error("Tried to write chunk {0} twice")]
+    DoubleWrite(Box<str>),
+    /// Tried to change the compression block size of an `IoStoreWriter` after writing chunks
+    #[error("Can't change the compression block size after chunks were written")]
+    CompressionBlockSizeChanged,
 
     /// No encryption key was provided for an encrypted file
     #[error("No encryption key was provided for an encrypted file")]
@@ -351,17 +389,17 @@ pub enum Error {
     #[error(transparent)]
     IoStore(#[from] IoStoreError),
 
-    /// Tried to decompress data with an unknown compression method
+    /// Tried to compress or decompress data with an unknown compression method
     #[error("Unknown compression method {0}")]
     UnknownCompressionMethod(Box<str>),
     /// An LZ4 decompression error occured
     #[error(transparent)]
     Lz4(#[from] lz4_flex::block::DecompressError),
-    /// Oodle decompression failed
-    #[error("Oodle decompression failed")]
+    /// Oodle compression or decompression failed
+    #[error("Oodle compression or decompression failed")]
     Oodle,
     /// Oodle library not initialized
-    #[error("Oodle decompression library is not initialized")]
+    #[error("Oodle library is not loaded")]
     OodleNotInitialized,
 
     /// A `ZenError` occured
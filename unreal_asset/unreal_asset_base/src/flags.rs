@@ -509,3 +509,28 @@ impl Default for EStructFlags {
         Self::NO_FLAGS
     }
 }
+
+bitflags! {
+    /// IoStore container flags
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    pub struct EIoContainerFlags : u8 {
+        /// No flags
+        const NONE = 0x00;
+        /// Compressed
+        const COMPRESSED = 0x01;
+        /// Encrypted
+        const ENCRYPTED = 0x02;
+        /// Signed
+        const SIGNED = 0x04;
+        /// Indexed
+        const INDEXED = 0x08;
+        /// On demand
+        const ON_DEMAND = 0x10;
+    }
+}
+
+impl Default for EIoContainerFlags {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
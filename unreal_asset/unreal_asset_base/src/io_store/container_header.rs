@@ -0,0 +1,240 @@
+//! IoStore container header
+//!
+//! The container header is stored in the `ContainerHeader` chunk of a container,
+//! it lists the packages stored in the container together with their store entries.
+
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
+
+use crate::crc;
+use crate::enums::EIoContainerHeaderVersion;
+use crate::Error;
+
+/// Container header signature
+pub const CONTAINER_HEADER_SIGNATURE: u32 = 0x496f436e;
+
+/// Get the package id of a package, e.g. `/Game/Maps/Map`
+pub fn get_package_id(package_name: &str) -> u64 {
+    crc::cityhash64_to_lower(package_name)
+}
+
+/// Get the container id of a container from its name, e.g. `pakchunk0-Windows`
+pub fn get_container_id(container_name: &str) -> u64 {
+    crc::cityhash64_to_lower(container_name)
+}
+
+/// Package store entry
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct IoPackageStoreEntry {
+    /// Export count, only used before [`EIoContainerHeaderVersion::NoExportInfo`]
+    pub export_count: i32,
+    /// Export bundle count, only used before [`EIoContainerHeaderVersion::NoExportInfo`]
+    pub export_bundle_count: i32,
+    /// Ids of imported packages
+    pub imported_packages: Vec<u64>,
+    /// Shader map hashes
+    pub shader_map_hashes: Vec<[u8; 20]>,
+}
+
+impl IoPackageStoreEntry {
+    /// Get the serialized size of an `IoPackageStoreEntry` without its array data
+    fn serialized_size(version: EIoContainerHeaderVersion) -> u32 {
+        match version >= EIoContainerHeaderVersion::NoExportInfo {
+            true => 16,
+            false => 24,
+        }
+    }
+}
+
+/// IoStore container header
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IoContainerHeader {
+    /// Container header version
+    pub version: EIoContainerHeaderVersion,
+    /// Container id
+    pub container_id: u64,
+    /// Package ids
+    pub package_ids: Vec<u64>,
+    /// Store entries, one for every package id
+    pub store_entries: Vec<IoPackageStoreEntry>,
+}
+
+impl IoContainerHeader {
+    /// Create a new empty `IoContainerHeader`
+    pub fn new(version: EIoContainerHeaderVersion, container_id: u64) -> Self {
+        IoContainerHeader {
+            version,
+            container_id,
+            package_ids: Vec::new(),
+            store_entries: Vec::new(),
+        }
+    }
+
+    /// Read an `IoContainerHeader` from a reader
+    ///
+    /// Localized packages and package redirects are not read.
+    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, Error> {
+        let signature = reader.read_u32::<LE>()?;
+        if signature != CONTAINER_HEADER_SIGNATURE {
+            return Err(Error::invalid_file(format!(
+                "Invalid container header signature {signature:#x}"
+            )));
+        }
+
+        let version = EIoContainerHeaderVersion::try_from(reader.read_u32::<LE>()?)?;
+        let container_id = reader.read_u64::<LE>()?;
+
+        let package_count = reader.read_i32::<LE>()?;
+        let package_ids = (0..package_count)
+            .map(|_| reader.read_u64::<LE>())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let store_entries_size = reader.read_i32::<LE>()?;
+        let mut store_entries_data = vec![0u8; store_entries_size.max(0) as usize];
+        reader.read_exact(&mut store_entries_data)?;
+
+        let mut store_entries_reader = Cursor::new(store_entries_data);
+        let mut store_entries = Vec::with_capacity(package_ids.len());
+        for i in 0..package_ids.len() as u64 {
+            let entry_offset = i * IoPackageStoreEntry::serialized_size(version) as u64;
+            store_entries_reader.seek(SeekFrom::Start(entry_offset))?;
+
+            let mut entry = IoPackageStoreEntry::default();
+            if version < EIoContainerHeaderVersion::NoExportInfo {
+                entry.export_count = store_entries_reader.read_i32::<LE>()?;
+                entry.export_bundle_count = store_entries_reader.read_i32::<LE>()?;
+            }
+
+            let imported_packages =
+                read_array_view(&mut store_entries_reader, |reader| reader.read_u64::<LE>())?;
+            let shader_map_hashes = read_array_view(&mut store_entries_reader, |reader| {
+                let mut hash = [0u8; 20];
+                reader.read_exact(&mut hash)?;
+                Ok(hash)
+            })?;
+
+            entry.imported_packages = imported_packages;
+            entry.shader_map_hashes = shader_map_hashes;
+            store_entries.push(entry);
+        }
+
+        Ok(IoContainerHeader {
+            version,
+            container_id,
+            package_ids,
+            store_entries,
+        })
+    }
+
+    /// Write an `IoContainerHeader` to a writer
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        if self.package_ids.len() != self.store_entries.len() {
+            return Err(Error::no_data(format!(
+                "Container header has {} package ids but {} store entries",
+                self.package_ids.len(),
+                self.store_entries.len()
+            )));
+        }
+
+        writer.write_u32::<LE>(CONTAINER_HEADER_SIGNATURE)?;
+        writer.write_u32::<LE>(self.version.into())?;
+        writer.write_u64::<LE>(self.container_id)?;
+
+        writer.write_i32::<LE>(self.package_ids.len() as i32)?;
+        for package_id in &self.package_ids {
+            writer.write_u64::<LE>(*package_id)?;
+        }
+
+        let store_entries = self.write_store_entries()?;
+        writer.write_i32::<LE>(store_entries.len() as i32)?;
+        writer.write_all(&store_entries)?;
+
+        if self.version >= EIoContainerHeaderVersion::OptionalSegmentPackages {
+            // optional segment package ids and store entries
+            writer.write_i32::<LE>(0)?;
+            writer.write_i32::<LE>(0)?;
+        }
+
+        match self.version >= EIoContainerHeaderVersion::LocalizedPackages {
+            true => {
+                // redirects name map, localized packages and package redirects
+                writer.write_i32::<LE>(0)?;
+                writer.write_i32::<LE>(0)?;
+                writer.write_i32::<LE>(0)?;
+            }
+            false => {
+                // culture package map and package redirects
+                writer.write_i32::<LE>(0)?;
+                writer.write_i32::<LE>(0)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write store entries, array data is stored after all entries
+    /// and referenced by an offset relative to the array view
+    fn write_store_entries(&self) -> Result<Vec<u8>, Error> {
+        let entry_size = IoPackageStoreEntry::serialized_size(self.version);
+        let mut entries = Cursor::new(Vec::new());
+        let mut array_data = Vec::new();
+        let array_data_start = self.store_entries.len() as u32 * entry_size;
+
+        for entry in &self.store_entries {
+            if self.version < EIoContainerHeaderVersion::NoExportInfo {
+                entries.write_i32::<LE>(entry.export_count)?;
+                entries.write_i32::<LE>(entry.export_bundle_count)?;
+            }
+
+            let mut write_array_view = |count: usize, data: &[u8]| -> Result<(), Error> {
+                let view_offset = entries.position() as u32;
+                let data_offset = array_data_start + array_data.len() as u32;
+                entries.write_u32::<LE>(count as u32)?;
+                entries.write_u32::<LE>(match count {
+                    0 => 0,
+                    _ => data_offset - view_offset,
+                })?;
+                array_data.extend_from_slice(data);
+                Ok(())
+            };
+
+            let imported_packages = entry
+                .imported_packages
+                .iter()
+                .flat_map(|e| e.to_le_bytes())
+                .collect::<Vec<_>>();
+            write_array_view(entry.imported_packages.len(), &imported_packages)?;
+            write_array_view(
+                entry.shader_map_hashes.len(),
+                &entry.shader_map_hashes.concat(),
+            )?;
+        }
+
+        let mut entries = entries.into_inner();
+        entries.extend_from_slice(&array_data);
+        Ok(entries)
+    }
+}
+
+/// Read an array view, the array data is located at an offset relative to the view
+fn read_array_view<T>(
+    reader: &mut Cursor<Vec<u8>>,
+    mut read_element: impl FnMut(&mut Cursor<Vec<u8>>) -> std::io::Result<T>,
+) -> Result<Vec<T>, Error> {
+    let view_offset = reader.position();
+    let count = reader.read_u32::<LE>()?;
+    let data_offset = reader.read_u32::<LE>()?;
+    let end = reader.position();
+
+    let mut elements = Vec::with_capacity(count as usize);
+    if count > 0 {
+        reader.seek(SeekFrom::Start(view_offset + data_offset as u64))?;
+        for _ in 0..count {
+            elements.push(read_element(reader)?);
+        }
+    }
+
+    reader.seek(SeekFrom::Start(end))?;
+    Ok(elements)
+}
@@ -0,0 +1,317 @@
+//! IoStore directory index
+
+use std::io::{Read, Seek, Write};
+
+use bitvec::prelude::*;
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
+
+use unreal_helpers::{UnrealReadExt, UnrealWriteExt};
+
+use crate::Error;
+
+/// Marks a missing directory/file/name index
+pub const INVALID_INDEX: u32 = u32::MAX;
+
+/// Directory index entry
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct IoDirectoryIndexEntry {
+    /// Directory name index in the string table
+    pub name: u32,
+    /// First child directory
+    pub first_child_entry: u32,
+    /// Next sibling directory
+    pub next_sibling_entry: u32,
+    /// First file in this directory
+    pub first_file_entry: u32,
+}
+
+impl IoDirectoryIndexEntry {
+    /// Read an `IoDirectoryIndexEntry` from a reader
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        Ok(IoDirectoryIndexEntry {
+            name: reader.read_u32::<LE>()?,
+            first_child_entry: reader.read_u32::<LE>()?,
+            next_sibling_entry: reader.read_u32::<LE>()?,
+            first_file_entry: reader.read_u32::<LE>()?,
+        })
+    }
+
+    /// Write an `IoDirectoryIndexEntry` to a writer
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        writer.write_u32::<LE>(self.name)?;
+        writer.write_u32::<LE>(self.first_child_entry)?;
+        writer.write_u32::<LE>(self.next_sibling_entry)?;
+        writer.write_u32::<LE>(self.first_file_entry)?;
+        Ok(())
+    }
+}
+
+/// File index entry
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct IoFileIndexEntry {
+    /// File name index in the string table
+    pub name: u32,
+    /// Next file in the same directory
+    pub next_file_entry: u32,
+    /// User data, this is the index of the file's chunk in the toc
+    pub user_data: u32,
+}
+
+impl IoFileIndexEntry {
+    /// Read an `IoFileIndexEntry` from a reader
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        Ok(IoFileIndexEntry {
+            name: reader.read_u32::<LE>()?,
+            next_file_entry: reader.read_u32::<LE>()?,
+            user_data: reader.read_u32::<LE>()?,
+        })
+    }
+
+    /// Write an `IoFileIndexEntry` to a writer
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        writer.write_u32::<LE>(self.name)?;
+        writer.write_u32::<LE>(self.next_file_entry)?;
+        writer.write_u32::<LE>(self.user_data)?;
+        Ok(())
+    }
+}
+
+/// IoStore directory index
+///
+/// Maps file paths to chunks, the first directory entry is the root directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct IoDirectoryIndex {
+    /// Mount point
+    pub mount_point: String,
+    /// Directory entries
+    pub directory_entries: Vec<IoDirectoryIndexEntry>,
+    /// File entries
+    pub file_entries: Vec<IoFileIndexEntry>,
+    /// String table
+    pub string_table: Vec<String>,
+}
+
+impl IoDirectoryIndex {
+    /// Create a new `IoDirectoryIndex` containing only the root directory
+    pub fn new(mount_point: String) -> Self {
+        IoDirectoryIndex {
+            mount_point,
+            directory_entries: vec![IoDirectoryIndexEntry {
+                name: INVALID_INDEX,
+                first_child_entry: INVALID_INDEX,
+                next_sibling_entry: INVALID_INDEX,
+                first_file_entry: INVALID_INDEX,
+            }],
+            file_entries: Vec::new(),
+            string_table: Vec::new(),
+        }
+    }
+
+    /// Add a file to this directory index
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - file path relative to the mount point
+    /// * `user_data` - the file's toc entry index
+    pub fn add_file(&mut self, path: &str, user_data: u32) -> Result<(), Error> {
+        if self.directory_entries.is_empty() {
+            *self = Self::new(std::mem::take(&mut self.mount_point));
+        }
+
+        let mut components = path
+            .split('/')
+            .filter(|e| !e.is_empty())
+            .collect::<Vec<_>>();
+        let file_name = components
+            .pop()
+            .ok_or_else(|| Error::no_data(format!("Invalid directory index path {path}")))?;
+
+        let mut directory_index = 0;
+        for component in components {
+            directory_index = self.get_or_add_directory(directory_index, component)?;
+        }
+
+        let name = self.get_or_add_name(file_name);
+        let file_index = self.file_entries.len() as u32;
+        self.file_entries.push(IoFileIndexEntry {
+            name,
+            next_file_entry: INVALID_INDEX,
+            user_data,
+        });
+
+        let directory = self.get_directory(directory_index)?;
+        match directory.first_file_entry {
+            INVALID_INDEX => {
+                self.directory_entries[directory_index as usize].first_file_entry = file_index
+            }
+            mut last => {
+                while self.file_entries[last as usize].next_file_entry != INVALID_INDEX {
+                    last = self.file_entries[last as usize].next_file_entry;
+                }
+                self.file_entries[last as usize].next_file_entry = file_index;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read an `IoDirectoryIndex` from a reader
+    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, Error> {
+        let mount_point = reader.read_fstring()?.unwrap_or_default();
+
+        let directory_count = reader.read_i32::<LE>()?;
+        let directory_entries = (0..directory_count)
+            .map(|_| IoDirectoryIndexEntry::read(reader))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let file_count = reader.read_i32::<LE>()?;
+        let file_entries = (0..file_count)
+            .map(|_| IoFileIndexEntry::read(reader))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let string_count = reader.read_i32::<LE>()?;
+        let mut string_table = Vec::with_capacity(string_count.max(0) as usize);
+        for _ in 0..string_count {
+            string_table.push(reader.read_fstring()?.unwrap_or_default());
+        }
+
+        Ok(IoDirectoryIndex {
+            mount_point,
+            directory_entries,
+            file_entries,
+            string_table,
+        })
+    }
+
+    /// Write an `IoDirectoryIndex` to a writer
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        writer.write_fstring(Some(&self.mount_point))?;
+
+        writer.write_i32::<LE>(self.directory_entries.len() as i32)?;
+        for entry in &self.directory_entries {
+            entry.write(writer)?;
+        }
+
+        writer.write_i32::<LE>(self.file_entries.len() as i32)?;
+        for entry in &self.file_entries {
+            entry.write(writer)?;
+        }
+
+        writer.write_i32::<LE>(self.string_table.len() as i32)?;
+        for string in &self.string_table {
+            writer.write_fstring(Some(string))?;
+        }
+
+        Ok(())
+    }
+
+    /// Get all files in this directory index
+    ///
+    /// Returns the full path of each file, prefixed with the mount point, together with the
+    /// file's toc entry index.
+    pub fn get_files(&self) -> Result<Vec<(String, u32)>, Error> {
+        let mut files = Vec::with_capacity(self.file_entries.len());
+        if self.directory_entries.is_empty() {
+            return Ok(files);
+        }
+
+        let mut root = self.mount_point.clone();
+        if !root.is_empty() && !root.ends_with('/') {
+            root.push('/');
+        }
+
+        // malformed indices can link entries in a cycle, every entry may only be visited once
+        let mut visited_directories = bitvec![0; self.directory_entries.len()];
+        let mut visited_files = bitvec![0; self.file_entries.len()];
+        visited_directories.set(0, true);
+
+        // (directory index, path) pairs left to visit
+        let mut stack = vec![(0u32, root)];
+        while let Some((directory_index, path)) = stack.pop() {
+            let directory = self.get_directory(directory_index)?;
+
+            let mut file_index = directory.first_file_entry;
+            while file_index != INVALID_INDEX {
+                let file = self.file_entries.get(file_index as usize).ok_or_else(|| {
+                    Error::invalid_file(format!("Invalid directory index file {file_index}"))
+                })?;
+                if visited_files.replace(file_index as usize, true) {
+                    return Err(Error::invalid_file(format!(
+                        "Directory index file {file_index} is linked more than once"
+                    )));
+                }
+
+                files.push((path.clone() + self.get_name(file.name)?, file.user_data));
+                file_index = file.next_file_entry;
+            }
+
+            let mut child_index = directory.first_child_entry;
+            while child_index != INVALID_INDEX {
+                let child = self.get_directory(child_index)?;
+                if visited_directories.replace(child_index as usize, true) {
+                    return Err(Error::invalid_file(format!(
+                        "Directory index directory {child_index} is linked more than once"
+                    )));
+                }
+                stack.push((child_index, path.clone() + self.get_name(child.name)? + "/"));
+                child_index = child.next_sibling_entry;
+            }
+        }
+
+        Ok(files)
+    }
+
+    fn get_directory(&self, index: u32) -> Result<&IoDirectoryIndexEntry, Error> {
+        self.directory_entries.get(index as usize).ok_or_else(|| {
+            Error::invalid_file(format!("Invalid directory index directory {index}"))
+        })
+    }
+
+    fn get_or_add_directory(&mut self, parent: u32, name: &str) -> Result<u32, Error> {
+        let mut last = INVALID_INDEX;
+        let mut child_index = self.get_directory(parent)?.first_child_entry;
+        while child_index != INVALID_INDEX {
+            let child = self.get_directory(child_index)?;
+            if self.get_name(child.name)? == name {
+                return Ok(child_index);
+            }
+            last = child_index;
+            child_index = child.next_sibling_entry;
+        }
+
+        let name = self.get_or_add_name(name);
+        let directory_index = self.directory_entries.len() as u32;
+        self.directory_entries.push(IoDirectoryIndexEntry {
+            name,
+            first_child_entry: INVALID_INDEX,
+            next_sibling_entry: INVALID_INDEX,
+            first_file_entry: INVALID_INDEX,
+        });
+
+        match last {
+            INVALID_INDEX => {
+                self.directory_entries[parent as usize].first_child_entry = directory_index
+            }
+            last => self.directory_entries[last as usize].next_sibling_entry = directory_index,
+        }
+
+        Ok(directory_index)
+    }
+
+    fn get_or_add_name(&mut self, name: &str) -> u32 {
+        match self.string_table.iter().position(|e| e == name) {
+            Some(index) => index as u32,
+            None => {
+                self.string_table.push(name.to_string());
+                self.string_table.len() as u32 - 1
+            }
+        }
+    }
+
+    fn get_name(&self, index: u32) -> Result<&str, Error> {
+        self.string_table
+            .get(index as usize)
+            .map(|e| e.as_str())
+            .ok_or_else(|| Error::invalid_file(format!("Invalid directory index name {index}")))
+    }
+}
@@ -0,0 +1,17 @@
+//! IoStore (.utoc/.ucas) container support
+//!
+//! IoStore containers are used by UE5 and late UE4 games to store cooked packages.
+//! A `.utoc` file contains the table of contents, the data is stored in one or more
+//! `.ucas` partitions.
+
+pub mod container_header;
+pub mod directory_index;
+pub mod reader;
+pub mod toc;
+pub mod writer;
+
+pub use container_header::{IoContainerHeader, IoPackageStoreEntry};
+pub use directory_index::IoDirectoryIndex;
+pub use reader::IoStoreReader;
+pub use toc::{IoChunkId, IoStoreToc};
+pub use writer::IoStoreWriter;
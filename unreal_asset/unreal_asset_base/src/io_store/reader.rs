@@ -0,0 +1,392 @@
+//! IoStore container reader
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use aes::cipher::{generic_array::GenericArray, BlockDecrypt, KeyInit};
+use aes::Aes256;
+
+use crate::compression::{self, CompressionMethod};
+use crate::error::IoStoreError;
+use crate::flags::EIoContainerFlags;
+use crate::Error;
+
+use super::directory_index::IoDirectoryIndex;
+use super::toc::{IoChunkId, IoStoreToc};
+
+/// AES block size, encrypted data is always aligned to this
+const AES_BLOCK_SIZE: u64 = 16;
+
+/// Data shared between the reader and its iterator
+struct ContainerData {
+    toc: IoStoreToc,
+    key: Option<Aes256>,
+}
+
+/// An IoStore container reader with its data kept on disk and only read on demand.
+///
+/// An IoStore container consists of a `.utoc` table of contents and one or
+/// more `.ucas` partitions containing the actual data.
+pub struct IoStoreReader<R>
+where
+    R: Read + Seek,
+{
+    /// Mount point of the container
+    pub mount_point: String,
+    data: ContainerData,
+    files: BTreeMap<String, usize>,
+    chunks: HashMap<IoChunkId, usize>,
+    partitions: Vec<R>,
+}
+
+impl IoStoreReader<BufReader<File>> {
+    /// Open an IoStore container from the path to its `.utoc` file
+    ///
+    /// All `.ucas` partitions are expected to be next to the `.utoc` file.
+    pub fn open<P: AsRef<Path>>(path: P, key: Option<[u8; 32]>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let mut toc_reader = BufReader::new(File::open(path)?);
+        let toc = IoStoreToc::read(&mut toc_reader)?;
+
+        let mut partitions = Vec::with_capacity(toc.header.partition_count as usize);
+        for i in 0..toc.header.partition_count {
+            let extension = match i {
+                0 => "ucas".to_string(),
+                _ => format!("_s{i}.ucas"),
+            };
+            let partition_path = match i {
+                0 => path.with_extension(extension),
+                _ => {
+                    let mut base = path.with_extension("").into_os_string();
+                    base.push(extension);
+                    base.into()
+                }
+            };
+            partitions.push(BufReader::new(File::open(partition_path)?));
+        }
+
+        Self::from_toc(toc, partitions, key)
+    }
+}
+
+impl<R> IoStoreReader<R>
+where
+    R: Read + Seek,
+{
+    /// Creates a new `IoStoreReader` from a `.utoc` reader and readers for all `.ucas` partitions.
+    /// When using readers that use syscalls like a `File` it is recommended to wrap them in a
+    /// [`std::io::BufReader`] to avoid unnecessary syscalls.
+    pub fn new<T: Read + Seek>(toc_reader: &mut T, partitions: Vec<R>) -> Result<Self, Error> {
+        Self::from_toc(IoStoreToc::read(toc_reader)?, partitions, None)
+    }
+
+    /// Creates a new `IoStoreReader` which decrypts the container with the given AES key.
+    pub fn new_encrypted<T: Read + Seek>(
+        toc_reader: &mut T,
+        partitions: Vec<R>,
+        key: [u8; 32],
+    ) -> Result<Self, Error> {
+        Self::from_toc(IoStoreToc::read(toc_reader)?, partitions, Some(key))
+    }
+
+    /// Creates a new `IoStoreReader` from an already read table of contents.
+    pub fn from_toc(
+        toc: IoStoreToc,
+        partitions: Vec<R>,
+        key: Option<[u8; 32]>,
+    ) -> Result<Self, Error> {
+        if partitions.len() < toc.header.partition_count as usize {
+            return Err(Error::invalid_file(format!(
+                "IoStore container has {} partitions, got {}",
+                toc.header.partition_count,
+                partitions.len()
+            )));
+        }
+
+        let key = key.map(|e| Aes256::new(&GenericArray::from(e)));
+        let encrypted = toc
+            .header
+            .container_flags
+            .contains(EIoContainerFlags::ENCRYPTED);
+        if encrypted && key.is_none() {
+            return Err(IoStoreError::NoEncryptionKey.into());
+        }
+
+        let chunks = toc
+            .chunk_ids
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (*e, i))
+            .collect();
+
+        let mut mount_point = String::new();
+        let mut files = BTreeMap::new();
+        if !toc.directory_index.is_empty() {
+            let mut directory_index = toc.directory_index.clone();
+            if let Some(key) = key.as_ref().filter(|_| encrypted) {
+                decrypt(key, &mut directory_index)?;
+            }
+
+            let directory_index = IoDirectoryIndex::read(&mut Cursor::new(directory_index))?;
+            mount_point = directory_index.mount_point.clone();
+            for (name, index) in directory_index.get_files()? {
+                files.insert(name, index as usize);
+            }
+        }
+
+        Ok(IoStoreReader {
+            mount_point,
+            data: ContainerData { toc, key },
+            files,
+            chunks,
+            partitions,
+        })
+    }
+
+    /// Get the container's table of contents
+    pub fn get_toc(&self) -> &IoStoreToc {
+        &self.data.toc
+    }
+
+    /// Returns the names of all files in the container's directory index
+    pub fn get_file_names(&self) -> Vec<&String> {
+        self.files.keys().collect()
+    }
+
+    /// Returns the ids of all chunks in the container
+    pub fn get_chunk_ids(&self) -> &[IoChunkId] {
+        &self.data.toc.chunk_ids
+    }
+
+    /// Checks if the container contains a file with the given name
+    pub fn contains_file(&self, name: &str) -> bool {
+        self.files.contains_key(name)
+    }
+
+    /// Checks if the container contains a chunk with the given id
+    pub fn contains_chunk(&self, chunk_id: &IoChunkId) -> bool {
+        self.chunks.contains_key(chunk_id)
+    }
+
+    /// Get the chunk id of a file
+    pub fn get_file_chunk_id(&self, name: &str) -> Option<IoChunkId> {
+        self.files
+            .get(name)
+            .and_then(|e| self.data.toc.chunk_ids.get(*e))
+            .copied()
+    }
+
+    /// Reads a file from the container into memory and returns its data.
+    pub fn read_file(&mut self, name: &str) -> Result<Vec<u8>, Error> {
+        let index = *self
+            .files
+            .get(name)
+            .ok_or_else(|| IoStoreError::NoFile(name.to_string().into_boxed_str()))?;
+        self.data.read_chunk(&mut self.partitions, index)
+    }
+
+    /// Reads a chunk from the container into memory and returns its data.
+    pub fn read_chunk(&mut self, chunk_id: &IoChunkId) -> Result<Vec<u8>, Error> {
+        let index = *self
+            .chunks
+            .get(chunk_id)
+            .ok_or_else(|| IoStoreError::NoFile(format!("{chunk_id:?}").into_boxed_str()))?;
+        self.data.read_chunk(&mut self.partitions, index)
+    }
+
+    /// Iterate over the files in the container
+    pub fn iter(&mut self) -> IoStoreReaderIter<R> {
+        IoStoreReaderIter {
+            data: &self.data,
+            partitions: &mut self.partitions,
+            iter: self.files.iter(),
+        }
+    }
+
+    /// Consumes the `IoStoreReader`, returning the wrapped partition readers.
+    /// There are no guarantees for what state the readers might be in.
+    pub fn into_inner(self) -> Vec<R> {
+        self.partitions
+    }
+}
+
+impl ContainerData {
+    fn read_chunk<R: Read + Seek>(
+        &self,
+        partitions: &mut [R],
+        index: usize,
+    ) -> Result<Vec<u8>, Error> {
+        let toc = &self.toc;
+        let offset_length = toc.chunk_offset_lengths.get(index).ok_or_else(|| {
+            Error::invalid_file(format!("No offset and length for chunk {index}"))
+        })?;
+        if offset_length.length == 0 {
+            return Ok(Vec::new());
+        }
+
+        let block_size = toc.header.compression_block_size as u64;
+        if block_size == 0 {
+            return Err(Error::invalid_file(
+                "IoStore compression block size is 0".to_string(),
+            ));
+        }
+
+        // chunks can't extend past the uncompressed size of all blocks
+        let container_size = toc.compression_blocks.len() as u64 * block_size;
+        if offset_length.offset + offset_length.length > container_size {
+            return Err(Error::invalid_file(format!(
+                "Chunk {index} at offset {} with length {} is out of bounds, container size: {container_size}",
+                offset_length.offset, offset_length.length
+            )));
+        }
+
+        let first_block = offset_length.offset / block_size;
+        let last_block = (offset_length.offset + offset_length.length - 1) / block_size;
+        let encrypted = toc
+            .header
+            .container_flags
+            .contains(EIoContainerFlags::ENCRYPTED);
+
+        let mut data = Vec::with_capacity(offset_length.length as usize);
+        let mut skip = (offset_length.offset - first_block * block_size) as usize;
+        let mut compressed = Vec::new();
+        let mut decompressed = Vec::new();
+
+        for block_index in first_block..=last_block {
+            let block = toc
+                .compression_blocks
+                .get(block_index as usize)
+                .ok_or_else(|| {
+                    Error::invalid_file(format!("Invalid compression block {block_index}"))
+                })?;
+
+            let method = match block.compression_method_index {
+                0 => CompressionMethod::None,
+                i => toc
+                    .compression_methods
+                    .get(i as usize - 1)
+                    .cloned()
+                    .ok_or_else(|| {
+                        Error::invalid_file(format!("Invalid compression method index {i}"))
+                    })?,
+            };
+
+            if block.uncompressed_size as u64 > block_size
+                || (method == CompressionMethod::None
+                    && block.compressed_size != block.uncompressed_size)
+            {
+                return Err(Error::invalid_file(format!(
+                    "Invalid compression block {block_index}, compressed size: {}, uncompressed size: {}",
+                    block.compressed_size, block.uncompressed_size
+                )));
+            }
+
+            let partition_index = block.offset / toc.header.partition_size;
+            let partition_offset = block.offset % toc.header.partition_size;
+            let partition = partitions
+                .get_mut(partition_index as usize)
+                .ok_or_else(|| {
+                    Error::invalid_file(format!("Invalid .ucas partition {partition_index}"))
+                })?;
+
+            let read_size = match encrypted {
+                true => align(block.compressed_size as u64),
+                false => block.compressed_size as u64,
+            };
+            compressed.resize(read_size as usize, 0);
+            partition.seek(SeekFrom::Start(partition_offset))?;
+            partition.read_exact(&mut compressed)?;
+
+            if encrypted {
+                let key = self.key.as_ref().ok_or(IoStoreError::NoEncryptionKey)?;
+                decrypt(key, &mut compressed)?;
+            }
+
+            decompressed.resize(block.uncompressed_size as usize, 0);
+            compression::decompress(
+                method,
+                &compressed[..block.compressed_size as usize],
+                &mut decompressed,
+            )?;
+
+            let remaining = offset_length.length as usize - data.len();
+            let end = decompressed.len().min(skip + remaining);
+            data.extend_from_slice(&decompressed[skip.min(end)..end]);
+            skip = 0;
+        }
+
+        Ok(data)
+    }
+}
+
+/// Round a size up to the next multiple of the AES block size
+fn align(size: u64) -> u64 {
+    (size + AES_BLOCK_SIZE - 1) & !(AES_BLOCK_SIZE - 1)
+}
+
+/// Decrypt data in place, data length has to be a multiple of the AES block size
+fn decrypt(key: &Aes256, data: &mut [u8]) -> Result<(), Error> {
+    if align(data.len() as u64) != data.len() as u64 {
+        return Err(Error::invalid_file(
+            "Encrypted IoStore data is not aligned to the AES block size".to_string(),
+        ));
+    }
+
+    for block in data.chunks_exact_mut(AES_BLOCK_SIZE as usize) {
+        key.decrypt_block(GenericArray::from_mut_slice(block));
+    }
+    Ok(())
+}
+
+impl<R> fmt::Debug for IoStoreReader<R>
+where
+    R: Read + Seek,
+{
+    // never print the key
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IoStoreReader")
+            .field("mount_point", &self.mount_point)
+            .field("toc", &self.data.toc)
+            .field("files", &self.files)
+            .finish()
+    }
+}
+
+/// An iterator over the files of an IoStoreReader
+pub struct IoStoreReaderIter<'a, R>
+where
+    R: Read + Seek,
+{
+    data: &'a ContainerData,
+    partitions: &'a mut Vec<R>,
+    iter: std::collections::btree_map::Iter<'a, String, usize>,
+}
+
+impl<'a, R> Iterator for IoStoreReaderIter<'a, R>
+where
+    R: Read + Seek,
+{
+    type Item = (&'a String, Result<Vec<u8>, Error>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next()
+            .map(|(name, index)| (name, self.data.read_chunk(self.partitions, *index)))
+    }
+}
+
+impl<'a, R> IntoIterator for &'a mut IoStoreReader<R>
+where
+    R: Read + Seek,
+{
+    type Item = (&'a String, Result<Vec<u8>, Error>);
+
+    type IntoIter = IoStoreReaderIter<'a, R>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
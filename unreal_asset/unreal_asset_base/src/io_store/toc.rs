@@ -0,0 +1,514 @@
+//! IoStore table of contents (.utoc)
+
+use std::io::{Read, Seek, Write};
+
+use byteorder::{ReadBytesExt, WriteBytesExt, BE, LE};
+
+use unreal_helpers::{Guid, UnrealReadExt, UnrealWriteExt};
+
+use crate::compression::CompressionMethod;
+use crate::enums::{EIoChunkType, EIoStoreTocVersion};
+use crate::error::IoStoreError;
+use crate::flags::EIoContainerFlags;
+use crate::Error;
+
+/// .utoc magic
+pub const TOC_MAGIC: [u8; 16] = *b"-==--==--==--==-";
+/// .utoc header size
+pub const TOC_HEADER_SIZE: u32 = 144;
+/// Serialized size of a compressed block entry
+pub const COMPRESSED_BLOCK_ENTRY_SIZE: u32 = 12;
+/// Length of a compression method name
+pub const COMPRESSION_METHOD_NAME_LENGTH: u32 = 32;
+/// Largest accepted .utoc signature size, RSA signatures of up to 32768 bit keys
+const MAX_SIGNATURE_SIZE: i32 = 0x1000;
+
+/// IoStore chunk id
+///
+/// Identifies a single chunk inside of an IoStore container
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IoChunkId {
+    /// Chunk id, this is the package id for package chunks
+    pub id: u64,
+    /// Chunk index
+    pub index: u16,
+    /// Padding
+    pub padding: u8,
+    /// Chunk type
+    pub chunk_type: u8,
+}
+
+impl IoChunkId {
+    /// Create a new `IoChunkId`
+    pub fn new(id: u64, index: u16, chunk_type: EIoChunkType) -> Self {
+        IoChunkId {
+            id,
+            index,
+            padding: 0,
+            chunk_type: chunk_type.into(),
+        }
+    }
+
+    /// Get this chunk's type
+    pub fn get_chunk_type(&self) -> Result<EIoChunkType, Error> {
+        Ok(EIoChunkType::try_from(self.chunk_type)?)
+    }
+
+    /// Read an `IoChunkId` from a reader
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let id = reader.read_u64::<LE>()?;
+        let index = reader.read_u16::<BE>()?;
+        let padding = reader.read_u8()?;
+        let chunk_type = reader.read_u8()?;
+
+        Ok(IoChunkId {
+            id,
+            index,
+            padding,
+            chunk_type,
+        })
+    }
+
+    /// Write an `IoChunkId` to a writer
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        writer.write_u64::<LE>(self.id)?;
+        writer.write_u16::<BE>(self.index)?;
+        writer.write_u8(self.padding)?;
+        writer.write_u8(self.chunk_type)?;
+        Ok(())
+    }
+}
+
+/// Offset and length of a chunk in the uncompressed container
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct IoOffsetAndLength {
+    /// Offset
+    pub offset: u64,
+    /// Length
+    pub length: u64,
+}
+
+impl IoOffsetAndLength {
+    /// Read an `IoOffsetAndLength` from a reader
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let offset = reader.read_uint::<BE>(5)?;
+        let length = reader.read_uint::<BE>(5)?;
+        Ok(IoOffsetAndLength { offset, length })
+    }
+
+    /// Write an `IoOffsetAndLength` to a writer
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        writer.write_uint::<BE>(self.offset, 5)?;
+        writer.write_uint::<BE>(self.length, 5)?;
+        Ok(())
+    }
+}
+
+/// Compressed block entry
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct IoStoreTocCompressedBlockEntry {
+    /// Offset of the block in the .ucas partitions
+    pub offset: u64,
+    /// Compressed size
+    pub compressed_size: u32,
+    /// Uncompressed size
+    pub uncompressed_size: u32,
+    /// Compression method index, 0 means no compression
+    pub compression_method_index: u8,
+}
+
+impl IoStoreTocCompressedBlockEntry {
+    /// Read an `IoStoreTocCompressedBlockEntry` from a reader
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let offset = reader.read_uint::<LE>(5)?;
+        let compressed_size = reader.read_uint::<LE>(3)? as u32;
+        let uncompressed_size = reader.read_uint::<LE>(3)? as u32;
+        let compression_method_index = reader.read_u8()?;
+
+        Ok(IoStoreTocCompressedBlockEntry {
+            offset,
+            compressed_size,
+            uncompressed_size,
+            compression_method_index,
+        })
+    }
+
+    /// Write an `IoStoreTocCompressedBlockEntry` to a writer
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        writer.write_uint::<LE>(self.offset, 5)?;
+        writer.write_uint::<LE>(self.compressed_size as u64, 3)?;
+        writer.write_uint::<LE>(self.uncompressed_size as u64, 3)?;
+        writer.write_u8(self.compression_method_index)?;
+        Ok(())
+    }
+}
+
+/// Chunk metadata
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct IoStoreTocEntryMeta {
+    /// Chunk hash, only the first 20 bytes are used since
+    /// [`EIoStoreTocVersion::ReplaceIoChunkHashWithIoHash`]
+    pub chunk_hash: [u8; 32],
+    /// Meta flags
+    pub flags: u8,
+}
+
+impl IoStoreTocEntryMeta {
+    /// Read an `IoStoreTocEntryMeta` from a reader
+    pub fn read<R: Read>(reader: &mut R, version: EIoStoreTocVersion) -> Result<Self, Error> {
+        let mut chunk_hash = [0u8; 32];
+        reader.read_exact(&mut chunk_hash[..Self::hash_size(version)])?;
+        let flags = reader.read_u8()?;
+
+        Ok(IoStoreTocEntryMeta { chunk_hash, flags })
+    }
+
+    /// Write an `IoStoreTocEntryMeta` to a writer
+    pub fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        version: EIoStoreTocVersion,
+    ) -> Result<(), Error> {
+        writer.write_all(&self.chunk_hash[..Self::hash_size(version)])?;
+        writer.write_u8(self.flags)?;
+        Ok(())
+    }
+
+    fn hash_size(version: EIoStoreTocVersion) -> usize {
+        match version >= EIoStoreTocVersion::ReplaceIoChunkHashWithIoHash {
+            true => 20,
+            false => 32,
+        }
+    }
+}
+
+/// Container signatures
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct IoStoreTocSignatures {
+    /// Toc signature
+    pub toc_signature: Vec<u8>,
+    /// Block signature
+    pub block_signature: Vec<u8>,
+    /// SHA-1 hashes of every compressed block
+    pub chunk_block_signatures: Vec<[u8; 20]>,
+}
+
+/// .utoc header
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IoStoreTocHeader {
+    /// Toc version
+    pub version: EIoStoreTocVersion,
+    /// Chunk count
+    pub entry_count: u32,
+    /// Compressed block count
+    pub compressed_block_entry_count: u32,
+    /// Compression method name count
+    pub compression_method_name_count: u32,
+    /// Size of a single compression block
+    pub compression_block_size: u32,
+    /// Directory index size
+    pub directory_index_size: u32,
+    /// Partition count
+    pub partition_count: u32,
+    /// Container id
+    pub container_id: u64,
+    /// Encryption key guid
+    pub encryption_key_guid: Guid,
+    /// Container flags
+    pub container_flags: EIoContainerFlags,
+    /// Perfect hash seeds count
+    pub perfect_hash_seeds_count: u32,
+    /// Maximum size of a single .ucas partition
+    pub partition_size: u64,
+    /// Count of chunks without a perfect hash
+    pub chunks_without_perfect_hash_count: u32,
+}
+
+impl IoStoreTocHeader {
+    /// Read an `IoStoreTocHeader` from a reader
+    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, Error> {
+        let mut magic = [0u8; 16];
+        reader.read_exact(&mut magic)?;
+        if magic != TOC_MAGIC {
+            return Err(IoStoreError::InvalidTocMagic(magic).into());
+        }
+
+        let version = EIoStoreTocVersion::try_from(reader.read_u8()?)?;
+        let _reserved0 = reader.read_u8()?;
+        let _reserved1 = reader.read_u16::<LE>()?;
+
+        let header_size = reader.read_u32::<LE>()?;
+        if header_size != TOC_HEADER_SIZE {
+            return Err(IoStoreError::invalid_toc_header_size(TOC_HEADER_SIZE, header_size).into());
+        }
+
+        let entry_count = reader.read_u32::<LE>()?;
+        let compressed_block_entry_count = reader.read_u32::<LE>()?;
+        let compressed_block_entry_size = reader.read_u32::<LE>()?;
+        if compressed_block_entry_size != COMPRESSED_BLOCK_ENTRY_SIZE {
+            return Err(Error::invalid_file(format!(
+                "Invalid .utoc compressed block entry size, expected: {COMPRESSED_BLOCK_ENTRY_SIZE}, got: {compressed_block_entry_size}"
+            )));
+        }
+
+        let compression_method_name_count = reader.read_u32::<LE>()?;
+        let compression_method_name_length = reader.read_u32::<LE>()?;
+        if compression_method_name_length != COMPRESSION_METHOD_NAME_LENGTH {
+            return Err(Error::invalid_file(format!(
+                "Invalid .utoc compression method name length, expected: {COMPRESSION_METHOD_NAME_LENGTH}, got: {compression_method_name_length}"
+            )));
+        }
+
+        let compression_block_size = reader.read_u32::<LE>()?;
+        let directory_index_size = reader.read_u32::<LE>()?;
+        let partition_count = reader.read_u32::<LE>()?;
+        let container_id = reader.read_u64::<LE>()?;
+        let encryption_key_guid = reader.read_guid()?;
+        let container_flags = EIoContainerFlags::from_bits_retain(reader.read_u8()?);
+        let _reserved3 = reader.read_u8()?;
+        let _reserved4 = reader.read_u16::<LE>()?;
+        let perfect_hash_seeds_count = reader.read_u32::<LE>()?;
+        let partition_size = reader.read_u64::<LE>()?;
+        let chunks_without_perfect_hash_count = reader.read_u32::<LE>()?;
+        let _reserved7 = reader.read_u32::<LE>()?;
+        let mut _reserved8 = [0u8; 5 * 8];
+        reader.read_exact(&mut _reserved8)?;
+
+        // partitions were introduced later, older containers only have a single partition
+        let (partition_count, partition_size) = match version < EIoStoreTocVersion::PartitionSize {
+            true => (1, u64::MAX),
+            false => (partition_count, partition_size),
+        };
+        if partition_count == 0 || partition_size == 0 {
+            return Err(Error::invalid_file(format!(
+                "Invalid .utoc partitions, count: {partition_count}, size: {partition_size}"
+            )));
+        }
+
+        Ok(IoStoreTocHeader {
+            version,
+            entry_count,
+            compressed_block_entry_count,
+            compression_method_name_count,
+            compression_block_size,
+            directory_index_size,
+            partition_count,
+            container_id,
+            encryption_key_guid,
+            container_flags,
+            perfect_hash_seeds_count,
+            partition_size,
+            chunks_without_perfect_hash_count,
+        })
+    }
+
+    /// Write an `IoStoreTocHeader` to a writer
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        writer.write_all(&TOC_MAGIC)?;
+        writer.write_u8(self.version.into())?;
+        writer.write_u8(0)?;
+        writer.write_u16::<LE>(0)?;
+        writer.write_u32::<LE>(TOC_HEADER_SIZE)?;
+        writer.write_u32::<LE>(self.entry_count)?;
+        writer.write_u32::<LE>(self.compressed_block_entry_count)?;
+        writer.write_u32::<LE>(COMPRESSED_BLOCK_ENTRY_SIZE)?;
+        writer.write_u32::<LE>(self.compression_method_name_count)?;
+        writer.write_u32::<LE>(COMPRESSION_METHOD_NAME_LENGTH)?;
+        writer.write_u32::<LE>(self.compression_block_size)?;
+        writer.write_u32::<LE>(self.directory_index_size)?;
+        writer.write_u32::<LE>(self.partition_count)?;
+        writer.write_u64::<LE>(self.container_id)?;
+        writer.write_guid(&self.encryption_key_guid)?;
+        writer.write_u8(self.container_flags.bits())?;
+        writer.write_u8(0)?;
+        writer.write_u16::<LE>(0)?;
+        writer.write_u32::<LE>(self.perfect_hash_seeds_count)?;
+        writer.write_u64::<LE>(self.partition_size)?;
+        writer.write_u32::<LE>(self.chunks_without_perfect_hash_count)?;
+        writer.write_u32::<LE>(0)?;
+        writer.write_all(&[0u8; 5 * 8])?;
+        Ok(())
+    }
+}
+
+/// IoStore table of contents
+///
+/// The directory index is kept as raw data since it might be encrypted,
+/// see [`super::directory_index::IoDirectoryIndex`] for parsing it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IoStoreToc {
+    /// Header
+    pub header: IoStoreTocHeader,
+    /// Chunk ids
+    pub chunk_ids: Vec<IoChunkId>,
+    /// Chunk offsets and lengths, one for every chunk id
+    pub chunk_offset_lengths: Vec<IoOffsetAndLength>,
+    /// Perfect hash seeds
+    pub chunk_perfect_hash_seeds: Vec<i32>,
+    /// Indices of chunks which are not covered by the perfect hash
+    pub chunk_indices_without_perfect_hash: Vec<i32>,
+    /// Compression blocks
+    pub compression_blocks: Vec<IoStoreTocCompressedBlockEntry>,
+    /// Compression methods
+    pub compression_methods: Vec<CompressionMethod>,
+    /// Signatures, only present on signed containers
+    pub signatures: Option<IoStoreTocSignatures>,
+    /// Raw directory index data
+    pub directory_index: Vec<u8>,
+    /// Chunk metadata, one for every chunk id
+    pub chunk_metas: Vec<IoStoreTocEntryMeta>,
+}
+
+impl IoStoreToc {
+    /// Read an `IoStoreToc` from a reader
+    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, Error> {
+        let header = IoStoreTocHeader::read(reader)?;
+
+        let chunk_ids = (0..header.entry_count)
+            .map(|_| IoChunkId::read(reader))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let chunk_offset_lengths = (0..header.entry_count)
+            .map(|_| IoOffsetAndLength::read(reader))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut chunk_perfect_hash_seeds = Vec::new();
+        let mut chunk_indices_without_perfect_hash = Vec::new();
+        if header.version >= EIoStoreTocVersion::PerfectHash {
+            for _ in 0..header.perfect_hash_seeds_count {
+                chunk_perfect_hash_seeds.push(reader.read_i32::<LE>()?);
+            }
+        }
+        if header.version >= EIoStoreTocVersion::PerfectHashWithOverflow {
+            for _ in 0..header.chunks_without_perfect_hash_count {
+                chunk_indices_without_perfect_hash.push(reader.read_i32::<LE>()?);
+            }
+        }
+
+        let compression_blocks = (0..header.compressed_block_entry_count)
+            .map(|_| IoStoreTocCompressedBlockEntry::read(reader))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut compression_methods =
+            Vec::with_capacity(header.compression_method_name_count as usize);
+        for _ in 0..header.compression_method_name_count {
+            let mut name = [0u8; COMPRESSION_METHOD_NAME_LENGTH as usize];
+            reader.read_exact(&mut name)?;
+
+            let len = name.iter().position(|e| *e == 0).unwrap_or(name.len());
+            let name = String::from_utf8(name[..len].to_vec())?;
+            compression_methods.push(CompressionMethod::new(&name));
+        }
+
+        let signatures = match header.container_flags.contains(EIoContainerFlags::SIGNED) {
+            true => {
+                let hash_size = reader.read_i32::<LE>()?;
+                if !(0..=MAX_SIGNATURE_SIZE).contains(&hash_size) {
+                    return Err(Error::invalid_file(format!(
+                        "Invalid .utoc signature size {hash_size}"
+                    )));
+                }
+                let mut toc_signature = vec![0u8; hash_size as usize];
+                reader.read_exact(&mut toc_signature)?;
+                let mut block_signature = vec![0u8; hash_size as usize];
+                reader.read_exact(&mut block_signature)?;
+
+                let mut chunk_block_signatures =
+                    Vec::with_capacity(header.compressed_block_entry_count as usize);
+                for _ in 0..header.compressed_block_entry_count {
+                    let mut hash = [0u8; 20];
+                    reader.read_exact(&mut hash)?;
+                    chunk_block_signatures.push(hash);
+                }
+
+                Some(IoStoreTocSignatures {
+                    toc_signature,
+                    block_signature,
+                    chunk_block_signatures,
+                })
+            }
+            false => None,
+        };
+
+        let mut directory_index = Vec::new();
+        if header.version >= EIoStoreTocVersion::DirectoryIndex
+            && header.container_flags.contains(EIoContainerFlags::INDEXED)
+        {
+            directory_index.resize(header.directory_index_size as usize, 0);
+            reader.read_exact(&mut directory_index)?;
+        }
+
+        let chunk_metas = (0..header.entry_count)
+            .map(|_| IoStoreTocEntryMeta::read(reader, header.version))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(IoStoreToc {
+            header,
+            chunk_ids,
+            chunk_offset_lengths,
+            chunk_perfect_hash_seeds,
+            chunk_indices_without_perfect_hash,
+            compression_blocks,
+            compression_methods,
+            signatures,
+            directory_index,
+            chunk_metas,
+        })
+    }
+
+    /// Write an `IoStoreToc` to a writer
+    ///
+    /// Counts and sizes in the header are taken from the header as is,
+    /// they have to match the data contained in the toc.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        let header = &self.header;
+        header.write(writer)?;
+
+        for chunk_id in &self.chunk_ids {
+            chunk_id.write(writer)?;
+        }
+        for offset_length in &self.chunk_offset_lengths {
+            offset_length.write(writer)?;
+        }
+
+        if header.version >= EIoStoreTocVersion::PerfectHash {
+            for seed in &self.chunk_perfect_hash_seeds {
+                writer.write_i32::<LE>(*seed)?;
+            }
+        }
+        if header.version >= EIoStoreTocVersion::PerfectHashWithOverflow {
+            for index in &self.chunk_indices_without_perfect_hash {
+                writer.write_i32::<LE>(*index)?;
+            }
+        }
+
+        for block in &self.compression_blocks {
+            block.write(writer)?;
+        }
+
+        for method in &self.compression_methods {
+            let method = method.to_string();
+            let mut name = [0u8; COMPRESSION_METHOD_NAME_LENGTH as usize];
+            let len = method.len().min(name.len() - 1);
+            name[..len].copy_from_slice(&method.as_bytes()[..len]);
+            writer.write_all(&name)?;
+        }
+
+        if let Some(signatures) = &self.signatures {
+            writer.write_i32::<LE>(signatures.toc_signature.len() as i32)?;
+            writer.write_all(&signatures.toc_signature)?;
+            writer.write_all(&signatures.block_signature)?;
+            for hash in &signatures.chunk_block_signatures {
+                writer.write_all(hash)?;
+            }
+        }
+
+        writer.write_all(&self.directory_index)?;
+
+        for meta in &self.chunk_metas {
+            meta.write(writer, header.version)?;
+        }
+
+        Ok(())
+    }
+}
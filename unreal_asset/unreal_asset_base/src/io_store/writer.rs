@@ -0,0 +1,263 @@
+//! IoStore container writer
+
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{Cursor, Write};
+
+use unreal_helpers::Guid;
+
+use crate::compression::{self, CompressionMethod};
+use crate::enums::EIoStoreTocVersion;
+use crate::error::IoStoreError;
+use crate::flags::EIoContainerFlags;
+use crate::Error;
+
+use super::directory_index::IoDirectoryIndex;
+use super::toc::{
+    IoChunkId, IoOffsetAndLength, IoStoreToc, IoStoreTocCompressedBlockEntry, IoStoreTocEntryMeta,
+    IoStoreTocHeader,
+};
+
+/// Blocks in the .ucas file are aligned to the AES block size
+const BLOCK_ALIGNMENT: u64 = 16;
+
+/// Chunk meta flag for compressed chunks
+const META_FLAG_COMPRESSED: u8 = 0x01;
+
+/// An IoStore container writer which writes chunks to a single `.ucas` partition
+/// as they are added and writes the `.utoc` table of contents when finished.
+pub struct IoStoreWriter<W: Write> {
+    /// Toc version
+    pub version: EIoStoreTocVersion,
+    /// Container id
+    pub container_id: u64,
+    compression_block_size: u32,
+    compression: CompressionMethod,
+    compression_methods: Vec<CompressionMethod>,
+    directory_index: IoDirectoryIndex,
+    chunk_ids: Vec<IoChunkId>,
+    chunk_lookup: HashSet<IoChunkId>,
+    chunk_offset_lengths: Vec<IoOffsetAndLength>,
+    chunk_metas: Vec<IoStoreTocEntryMeta>,
+    compression_blocks: Vec<IoStoreTocCompressedBlockEntry>,
+    partition_offset: u64,
+    writer: W,
+}
+
+impl<W: Write> IoStoreWriter<W> {
+    /// Creates a new `IoStoreWriter` that writes the `.ucas` partition to the provided writer.
+    /// When using a writer that uses syscalls like a `File` it is recommended to wrap it in a
+    /// [`std::io::BufWriter`] to avoid unnecessary syscalls.
+    pub fn new(
+        writer: W,
+        version: EIoStoreTocVersion,
+        container_id: u64,
+        mount_point: String,
+    ) -> Self {
+        IoStoreWriter {
+            version,
+            container_id,
+            compression_block_size: 0x10000,
+            compression: CompressionMethod::None,
+            compression_methods: Vec::new(),
+            directory_index: IoDirectoryIndex::new(mount_point),
+            chunk_ids: Vec::new(),
+            chunk_lookup: HashSet::new(),
+            chunk_offset_lengths: Vec::new(),
+            chunk_metas: Vec::new(),
+            compression_blocks: Vec::new(),
+            partition_offset: 0,
+            writer,
+        }
+    }
+
+    /// Set the compression method used for chunks written from now on.
+    /// Blocks which don't get smaller when compressed are stored uncompressed.
+    pub fn set_compression(&mut self, compression: CompressionMethod) {
+        self.compression = compression;
+    }
+
+    /// Get the compression block size
+    pub fn get_compression_block_size(&self) -> u32 {
+        self.compression_block_size
+    }
+
+    /// Set the compression block size, the default is 64KiB
+    ///
+    /// Chunk offsets depend on the block size, so it can only be set before the first chunk is written.
+    pub fn set_compression_block_size(&mut self, compression_block_size: u32) -> Result<(), Error> {
+        if !self.chunk_ids.is_empty() {
+            return Err(IoStoreError::CompressionBlockSizeChanged.into());
+        }
+        if compression_block_size == 0 {
+            return Err(Error::invalid_file(
+                "Compression block size can't be 0".to_string(),
+            ));
+        }
+
+        self.compression_block_size = compression_block_size;
+        Ok(())
+    }
+
+    /// Checks if a chunk with the given id has already been written
+    pub fn contains_chunk(&self, chunk_id: &IoChunkId) -> bool {
+        self.chunk_lookup.contains(chunk_id)
+    }
+
+    /// Writes a chunk into the `.ucas` partition
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_id` - id of the chunk
+    /// * `path` - path of the chunk relative to the mount point, chunks without a path
+    ///   are not added to the directory index
+    /// * `data` - chunk data
+    pub fn write_chunk(
+        &mut self,
+        chunk_id: IoChunkId,
+        path: Option<&str>,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        if !self.chunk_lookup.insert(chunk_id) {
+            return Err(IoStoreError::DoubleWrite(format!("{chunk_id:?}").into_boxed_str()).into());
+        }
+
+        let chunk_index = self.chunk_ids.len() as u32;
+        if let Some(path) = path {
+            self.directory_index.add_file(path, chunk_index)?;
+        }
+
+        // chunks always start at a new block in the uncompressed address space
+        self.chunk_offset_lengths.push(IoOffsetAndLength {
+            offset: self.compression_blocks.len() as u64 * self.compression_block_size as u64,
+            length: data.len() as u64,
+        });
+
+        let mut compressed = false;
+        for block in data.chunks(self.compression_block_size as usize) {
+            let (compression_method_index, block_data) = match self.compression {
+                CompressionMethod::None => (0, block.to_vec()),
+                _ => {
+                    let compressed_block = compression::compress(self.compression.clone(), block)?;
+                    match compressed_block.len() < block.len() {
+                        true => (self.get_compression_method_index(), compressed_block),
+                        false => (0, block.to_vec()),
+                    }
+                }
+            };
+            compressed |= compression_method_index != 0;
+
+            self.compression_blocks
+                .push(IoStoreTocCompressedBlockEntry {
+                    offset: self.partition_offset,
+                    compressed_size: block_data.len() as u32,
+                    uncompressed_size: block.len() as u32,
+                    compression_method_index,
+                });
+
+            let aligned_size = align(block_data.len() as u64);
+            self.writer.write_all(&block_data)?;
+            self.writer.write_all(&vec![
+                0u8;
+                (aligned_size - block_data.len() as u64) as usize
+            ])?;
+            self.partition_offset += aligned_size;
+        }
+
+        // chunk hashes are the first 20 bytes of a blake3 hash
+        let mut chunk_hash = [0u8; 32];
+        chunk_hash[..20].copy_from_slice(&blake3::hash(data).as_bytes()[..20]);
+        self.chunk_metas.push(IoStoreTocEntryMeta {
+            chunk_hash,
+            flags: match compressed {
+                true => META_FLAG_COMPRESSED,
+                false => 0,
+            },
+        });
+        self.chunk_ids.push(chunk_id);
+
+        Ok(())
+    }
+
+    /// Get the 1-based index of the current compression method in the method table,
+    /// adding it to the table if no block was compressed with it yet
+    fn get_compression_method_index(&mut self) -> u8 {
+        let index = match self
+            .compression_methods
+            .iter()
+            .position(|e| *e == self.compression)
+        {
+            Some(index) => index,
+            None => {
+                self.compression_methods.push(self.compression.clone());
+                self.compression_methods.len() - 1
+            }
+        };
+        index as u8 + 1
+    }
+
+    /// Finish writing the container by writing the table of contents to the provided writer
+    ///
+    /// Returns the `.ucas` partition writer.
+    pub fn finish_write<T: Write>(mut self, toc_writer: &mut T) -> Result<W, Error> {
+        self.writer.flush()?;
+
+        let mut directory_index = Cursor::new(Vec::new());
+        self.directory_index.write(&mut directory_index)?;
+        let directory_index = directory_index.into_inner();
+
+        let compression_methods = self.compression_methods;
+
+        let mut container_flags = EIoContainerFlags::INDEXED;
+        if !compression_methods.is_empty() {
+            container_flags |= EIoContainerFlags::COMPRESSED;
+        }
+
+        let toc = IoStoreToc {
+            header: IoStoreTocHeader {
+                version: self.version,
+                entry_count: self.chunk_ids.len() as u32,
+                compressed_block_entry_count: self.compression_blocks.len() as u32,
+                compression_method_name_count: compression_methods.len() as u32,
+                compression_block_size: self.compression_block_size,
+                directory_index_size: directory_index.len() as u32,
+                partition_count: 1,
+                container_id: self.container_id,
+                encryption_key_guid: Guid::default(),
+                container_flags,
+                perfect_hash_seeds_count: 0,
+                partition_size: u64::MAX,
+                chunks_without_perfect_hash_count: 0,
+            },
+            chunk_ids: self.chunk_ids,
+            chunk_offset_lengths: self.chunk_offset_lengths,
+            chunk_perfect_hash_seeds: Vec::new(),
+            chunk_indices_without_perfect_hash: Vec::new(),
+            compression_blocks: self.compression_blocks,
+            compression_methods,
+            signatures: None,
+            directory_index,
+            chunk_metas: self.chunk_metas,
+        };
+        toc.write(toc_writer)?;
+
+        Ok(self.writer)
+    }
+}
+
+/// Round a size up to the next multiple of the block alignment
+fn align(size: u64) -> u64 {
+    (size + BLOCK_ALIGNMENT - 1) & !(BLOCK_ALIGNMENT - 1)
+}
+
+impl<W: Write> fmt::Debug for IoStoreWriter<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IoStoreWriter")
+            .field("version", &self.version)
+            .field("container_id", &self.container_id)
+            .field("compression_block_size", &self.compression_block_size)
+            .field("compression", &self.compression)
+            .field("chunk_ids", &self.chunk_ids)
+            .finish()
+    }
+}
@@ -17,6 +17,7 @@ pub use error::Error;
 pub mod flags;
 pub mod import;
 pub use import::Import;
+pub mod io_store;
 pub mod object_version;
 pub mod reader;
 pub mod types;
@@ -139,7 +139,7 @@ pub trait ArchiveReader<Index: PackageIndexTrait>: ArchiveTrait<Index> + Read {
             return Ok((Vec::new(), 0));
         }
 
-        let _strings_length = self.read_u64::<LE>()?;
+        let _strings_length = self.read_u32::<LE>()?;
         let hash_version = self.read_u64::<LE>()?;
 
         let hashes = match hash_version {
@@ -164,7 +164,11 @@ pub trait ArchiveReader<Index: PackageIndexTrait>: ArchiveTrait<Index> + Read {
         let mut name_batch = Vec::with_capacity(num_strings as usize);
 
         for name_header in name_headers {
-            name_batch.push(self.read_name_map_string(Some(name_header)).map(|e| e.0)?);
+            // name batch strings have neither a terminator nor a hash
+            name_batch.push(
+                self.read_fstring_name_header(name_header)?
+                    .unwrap_or_default(),
+            );
         }
 
         if verify_hashes {
@@ -229,7 +233,7 @@ pub trait ArchiveReader<Index: PackageIndexTrait>: ArchiveTrait<Index> + Read {
 
     /// Read an FString
     fn read_fstring(&mut self) -> Result<Option<String>, Error>;
-    /// Read an FString with a `SerializedNameHeader`
+    /// Read an unterminated string with a `SerializedNameHeader`
     fn read_fstring_name_header(
         &mut self,
         serialized_name_header: SerializedNameHeader,
@@ -4,10 +4,12 @@ use std::io::{self, Write};
 
 use byteorder::{WriteBytesExt, LE};
 
+use crate::crc;
+use crate::enums;
 use crate::error::{Error, FNameError};
 use crate::object_version::ObjectVersion;
 use crate::reader::ArchiveTrait;
-use crate::types::{FName, PackageIndexTrait};
+use crate::types::{FName, PackageIndexTrait, SerializedNameHeader};
 use crate::Guid;
 
 /// A trait that allows for writing to an archive in an asset-specific way
@@ -59,6 +61,64 @@ pub trait ArchiveWriter<Index: PackageIndexTrait>: ArchiveTrait<Index> + Write {
         }
     }
 
+    /// Write a name batch
+    ///
+    /// Names are hashed with cityhash64, non-ASCII names are written as UTF-16
+    fn write_name_batch(&mut self, names: &[String]) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        self.write_i32::<LE>(names.len() as i32)?;
+        if names.is_empty() {
+            return Ok(());
+        }
+
+        let name_headers = names
+            .iter()
+            .map(|e| match e.is_ascii() {
+                true => SerializedNameHeader {
+                    is_wide: false,
+                    len: e.len() as i32,
+                },
+                false => SerializedNameHeader {
+                    is_wide: true,
+                    len: e.encode_utf16().count() as i32,
+                },
+            })
+            .collect::<Vec<_>>();
+
+        let strings_length = name_headers
+            .iter()
+            .map(|e| match e.is_wide {
+                true => e.len as u32 * 2,
+                false => e.len as u32,
+            })
+            .sum::<u32>();
+        self.write_u32::<LE>(strings_length)?;
+        self.write_u64::<LE>(enums::HASH_VERSION_CITYHASH64)?;
+
+        for name in names {
+            self.write_u64::<LE>(crc::cityhash64_to_lower(name))?;
+        }
+        for name_header in &name_headers {
+            name_header.write(self)?;
+        }
+
+        // name batch strings have neither a terminator nor a hash
+        for (name, name_header) in names.iter().zip(&name_headers) {
+            match name_header.is_wide {
+                true => {
+                    for character in name.encode_utf16() {
+                        self.write_u16::<LE>(character)?;
+                    }
+                }
+                false => self.write_all(name.as_bytes())?,
+            }
+        }
+
+        Ok(())
+    }
+
     /// Write an FString
     fn write_fstring(&mut self, value: Option<&str>) -> Result<usize, Error>;
     /// Write a guid.
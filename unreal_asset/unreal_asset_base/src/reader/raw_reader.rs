@@ -3,7 +3,7 @@
 use std::io::{self, Read, Seek};
 use std::marker::PhantomData;
 
-use unreal_helpers::{read_ext::read_fstring_len, Guid, UnrealReadExt};
+use unreal_helpers::{read_ext::read_fstring_len_noterm, Guid, UnrealReadExt};
 
 use crate::containers::{Chain, IndexedMap, NameMap, SharedResource};
 use crate::custom_version::{CustomVersion, CustomVersionTrait};
@@ -141,7 +141,7 @@ impl<Index: PackageIndexTrait, C: Read + Seek> ArchiveReader<Index> for RawReade
             return Ok(None);
         }
 
-        Ok(read_fstring_len(
+        Ok(read_fstring_len_noterm(
             &mut self.cursor,
             serialized_name_header.len,
             serialized_name_header.is_wide,
@@ -162,6 +162,14 @@ impl FName {
         }
     }
 
+    /// Get this `FName`'s content including its instance number, e.g. `Name_0` for number 1
+    pub fn to_string_with_number(&self) -> String {
+        match self.get_number() {
+            0 => self.get_owned_content(),
+            number => format!("{}_{}", self.get_owned_content(), number - 1),
+        }
+    }
+
     /// Compare `FNames` based on their content
     pub fn eq_content(&self, other: &Self) -> bool {
         self.get_content(|this| other == this)
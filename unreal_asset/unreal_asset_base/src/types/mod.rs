@@ -3,6 +3,7 @@
 pub mod fname;
 use byteorder::{ReadBytesExt, WriteBytesExt};
 pub use fname::FName;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 pub mod movie;
 pub mod vector;
@@ -122,6 +123,128 @@ impl std::fmt::Display for PackageIndex {
     }
 }
 
+/// `PackageObjectIndex` type
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, TryFromPrimitive, IntoPrimitive)]
+#[repr(u64)]
+pub enum EPackageObjectIndexType {
+    /// Export
+    Export,
+    /// Script import
+    ScriptImport,
+    /// Package import
+    PackageImport,
+    /// Null
+    Null,
+}
+
+/// PackageObjectIndex is used instead of `PackageIndex` in Zen (IoStore) packages
+///
+/// The upper 2 bits store the index type, the other 62 bits store the value
+/// which is either an export index, a global script object hash or a package import.
+#[derive(Debug, Hash, Copy, Clone, PartialEq, Eq)]
+pub struct PackageObjectIndex {
+    /// Raw index value
+    pub value: u64,
+}
+
+impl PackageObjectIndex {
+    /// Amount of bits used for the index value
+    pub const INDEX_BITS: u64 = 62;
+    /// Index value mask
+    pub const INDEX_MASK: u64 = (1 << Self::INDEX_BITS) - 1;
+    /// Null index
+    pub const NULL: PackageObjectIndex = PackageObjectIndex { value: u64::MAX };
+
+    /// Create a new `PackageObjectIndex` from a raw value
+    pub fn new(value: u64) -> Self {
+        PackageObjectIndex { value }
+    }
+
+    /// Create a new `PackageObjectIndex` from a type and an index value
+    pub fn from_type(ty: EPackageObjectIndexType, index: u64) -> Self {
+        match ty {
+            EPackageObjectIndexType::Null => Self::NULL,
+            _ => PackageObjectIndex {
+                value: (u64::from(ty) << Self::INDEX_BITS) | (index & Self::INDEX_MASK),
+            },
+        }
+    }
+
+    /// Create an export `PackageObjectIndex`
+    pub fn from_export(export_index: u32) -> Self {
+        Self::from_type(EPackageObjectIndexType::Export, export_index as u64)
+    }
+
+    /// Create a package import `PackageObjectIndex`, as used since UE5.3
+    pub fn from_package_import(
+        imported_package_index: u32,
+        imported_public_export_hash_index: u32,
+    ) -> Self {
+        Self::from_type(
+            EPackageObjectIndexType::PackageImport,
+            ((imported_package_index as u64) << 32) | imported_public_export_hash_index as u64,
+        )
+    }
+
+    /// Get index type
+    pub fn get_type(&self) -> EPackageObjectIndexType {
+        // can never fail, there are 4 variants for 2 bits
+        EPackageObjectIndexType::try_from(self.value >> Self::INDEX_BITS)
+            .unwrap_or(EPackageObjectIndexType::Null)
+    }
+
+    /// Get index value without the type
+    pub fn get_index(&self) -> u64 {
+        self.value & Self::INDEX_MASK
+    }
+
+    /// Check if this index is null
+    pub fn is_null(&self) -> bool {
+        self.get_type() == EPackageObjectIndexType::Null
+    }
+
+    /// Check if this index is a script import
+    pub fn is_script_import(&self) -> bool {
+        self.get_type() == EPackageObjectIndexType::ScriptImport
+    }
+
+    /// Check if this index is a package import
+    pub fn is_package_import(&self) -> bool {
+        self.get_type() == EPackageObjectIndexType::PackageImport
+    }
+
+    /// Get imported package index and imported public export hash index of a package import,
+    /// as used since UE5.3
+    pub fn get_package_import(&self) -> Option<(u32, u32)> {
+        match self.is_package_import() {
+            true => Some(((self.get_index() >> 32) as u32, self.get_index() as u32)),
+            false => None,
+        }
+    }
+}
+
+impl Default for PackageObjectIndex {
+    fn default() -> Self {
+        Self::NULL
+    }
+}
+
+impl PackageIndexTrait for PackageObjectIndex {
+    fn is_import(&self) -> bool {
+        self.is_script_import() || self.is_package_import()
+    }
+
+    fn is_export(&self) -> bool {
+        self.get_type() == EPackageObjectIndexType::Export
+    }
+}
+
+impl std::fmt::Display for PackageObjectIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}({:#x})", self.get_type(), self.get_index())
+    }
+}
+
 // /// Create a Guid from 4 u32 values
 // #[rustfmt::skip]
 // pub const fn new_guid(a: u32, b: u32, c: u32, d: u32) -> Guid {
@@ -1,31 +1,32 @@
 //! Allows reading unversioned assets using mappings
 
+use std::collections::HashSet;
 use std::hash::Hash;
-use std::io::{Cursor, Read, Seek};
+use std::io::{Cursor, Read, Seek, Write};
 
 use bitflags::bitflags;
-use byteorder::{ReadBytesExt, LE};
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
+use crate::compression::{self, CompressionMethod};
 use crate::containers::{Chain, IndexedMap, NameMap};
 use crate::custom_version::CustomVersion;
 use crate::error::{Error, UsmapError};
 use crate::object_version::{ObjectVersion, ObjectVersionUE5};
-use crate::reader::{ArchiveReader, ArchiveTrait, RawReader};
+use crate::reader::{ArchiveReader, ArchiveTrait, ArchiveWriter, RawReader, RawWriter};
 
 use crate::types::{FName, PackageIndex};
 
 pub mod ancestry;
 pub mod header;
-#[cfg(feature = "oodle")]
-pub(crate) mod oodle;
 pub mod properties;
 pub mod usmap_reader;
 pub mod usmap_writer;
 
 pub use self::ancestry::Ancestry;
-use self::properties::UsmapProperty;
+use self::properties::{UsmapProperty, UsmapPropertyData};
 use self::usmap_reader::UsmapReader;
+use self::usmap_writer::UsmapWriter;
 
 /// Usmap file version
 #[derive(
@@ -80,7 +81,7 @@ type UsmapPropertyKey = (String, u32);
 pub struct UsmapSchema {
     /// Name
     pub name: String,
-    /// Super type
+    /// Super type, empty if the schema has no super type
     pub super_type: String,
     /// Properties count
     pub prop_count: u16,
@@ -96,7 +97,8 @@ impl UsmapSchema {
         reader: &mut UsmapReader<'_, '_, R>,
     ) -> Result<UsmapSchema, Error> {
         let name = reader.read_name()?;
-        let super_type = reader.read_name()?;
+        // schemas without a super type store -1
+        let super_type = reader.read_optional_name()?.unwrap_or_default();
 
         let prop_count = reader.read_u16::<LE>()?;
         let serializable_property_count = reader.read_u16::<LE>()?;
@@ -127,12 +129,57 @@ impl UsmapSchema {
         })
     }
 
+    /// Write a `UsmapSchema` to an archive
+    pub fn write<W: ArchiveWriter<PackageIndex>>(
+        &self,
+        writer: &mut UsmapWriter<'_, '_, W>,
+    ) -> Result<(), Error> {
+        writer.write_name(&self.name)?;
+        writer.write_optional_name(match self.super_type.is_empty() {
+            true => None,
+            false => Some(&self.super_type),
+        })?;
+
+        // properties with an array size are only serialized once
+        let properties = self
+            .properties
+            .values()
+            .filter(|e| e.array_index == 0)
+            .collect::<Vec<_>>();
+
+        writer.write_u16::<LE>(self.prop_count)?;
+        writer.write_u16::<LE>(properties.len() as u16)?;
+
+        for property in properties {
+            property.write(writer)?;
+        }
+
+        Ok(())
+    }
+
     /// Gets a usmap property
     pub fn get_property(&self, name: &str, duplication_index: u32) -> Option<&UsmapProperty> {
         // todo: remove to_string
         self.properties
             .get_by_key(&(name.to_string(), duplication_index))
     }
+
+    /// Check if another definition of this schema has a different layout
+    ///
+    /// Schemas conflict if their property count or the type of any of their properties differ.
+    pub fn conflicts_with(&self, other: &UsmapSchema) -> bool {
+        fn property_types(schema: &UsmapSchema) -> Vec<(u16, &UsmapPropertyData)> {
+            let mut property_types = schema
+                .properties
+                .values()
+                .map(|e| (e.schema_index, &e.property_data))
+                .collect::<Vec<_>>();
+            property_types.sort_by_key(|(schema_index, _)| *schema_index);
+            property_types
+        }
+
+        self.prop_count != other.prop_count || property_types(self) != property_types(other)
+    }
 }
 
 /// Usmap file
@@ -198,11 +245,7 @@ impl Usmap {
         let mut optional_schema_name = ancestry.get_parent().map(|e| e.get_owned_content());
 
         let mut global_index = 0;
-        loop {
-            let Some(schema_name) = optional_schema_name else {
-                break;
-            };
-
+        while let Some(schema_name) = optional_schema_name {
             let Some(schema) = self.schemas.get_by_key(&schema_name) else {
                 break;
             };
@@ -231,6 +274,62 @@ impl Usmap {
         )
     }
 
+    /// Merge the schemas and enums of another usmap into this one
+    ///
+    /// Schemas and enums that are only defined in `other` are added, ones that are defined in both
+    /// usmaps have to match, otherwise an error is returned and this usmap is left unchanged.
+    /// This allows layering mappings for mod-added types over the mappings of a game.
+    /// The `PATHS` extension is dropped if not every schema has a module path afterwards.
+    pub fn merge(&mut self, other: Usmap) -> Result<(), Error> {
+        for schema in other.schemas.values() {
+            if let Some(existing) = self.schemas.get_by_key(&schema.name) {
+                if existing.conflicts_with(schema) {
+                    return Err(UsmapError::schema_conflict(&schema.name).into());
+                }
+            }
+        }
+
+        for (_, name, values) in other.enum_map.iter() {
+            if let Some(existing) = self.enum_map.get_by_key(name) {
+                if existing != values {
+                    return Err(UsmapError::enum_conflict(name).into());
+                }
+            }
+        }
+
+        for (_, name, values) in other.enum_map {
+            if !self.enum_map.contains_key(&name) {
+                self.enum_map.insert(name, values);
+            }
+        }
+
+        for (_, name, schema) in other.schemas {
+            match self.schemas.get_by_key_mut(&name) {
+                Some(existing) => {
+                    if existing.module_path.is_none() {
+                        existing.module_path = schema.module_path;
+                    }
+                }
+                None => self.schemas.insert(name, schema),
+            }
+        }
+
+        let mut names = self.name_map.iter().cloned().collect::<HashSet<_>>();
+        for name in other.name_map {
+            if names.insert(name.clone()) {
+                self.name_map.push(name);
+            }
+        }
+
+        self.extension_version |= other.extension_version;
+        // module paths can only be written if every schema has one
+        if self.schemas.values().any(|e| e.module_path.is_none()) {
+            self.extension_version.remove(UsmapExtensionVersion::PATHS);
+        }
+
+        Ok(())
+    }
+
     /// Parse usmap file
     pub fn parse_data<C: Read + Seek>(&mut self, cursor: C) -> Result<(), Error> {
         let mut reader = RawReader::<PackageIndex, C>::new(
@@ -248,9 +347,9 @@ impl Usmap {
             ));
         }
 
-        let usmap_version = EUsmapVersion::try_from(reader.read_u8()?)?;
+        self.version = EUsmapVersion::try_from(reader.read_u8()?)?;
 
-        let mut has_versioning = usmap_version >= EUsmapVersion::PackageVersioning;
+        let mut has_versioning = self.version >= EUsmapVersion::PackageVersioning;
         if has_versioning {
             has_versioning = reader.read_bool()?;
         }
@@ -297,24 +396,13 @@ impl Usmap {
                 decompressed_data.into_inner()
             }
             EUsmapCompressionMethod::Oodle => {
-                #[cfg(not(feature = "oodle"))]
-                return Err(
-                    UsmapError::unsupported_compression(self.compression_method as u8).into(),
-                );
-
-                #[cfg(feature = "oodle")]
-                {
-                    let compressed = vec![0u8; compressed_size as usize];
-
-                    let decompressed = oodle::decompress(
-                        &compressed,
-                        compressed_size as u64,
-                        decompressed_size as u64,
-                    )
-                    .ok_or_else(|| UsmapError::invalid_compression_data())?;
-
-                    decompressed
-                }
+                let mut decompressed_data = vec![0u8; decompressed_size as usize];
+                compression::decompress(
+                    CompressionMethod::Oodle,
+                    &compressed_data,
+                    &mut decompressed_data,
+                )?;
+                decompressed_data
             }
             EUsmapCompressionMethod::Unknown => {
                 return Err(
@@ -333,7 +421,7 @@ impl Usmap {
 
         self.name_map = reader.read_array(|reader| {
             let name_length = reader.read_u8()?;
-            let mut buf = vec![0u8; name_length as usize - 1];
+            let mut buf = vec![0u8; name_length as usize];
             reader.read_exact(&mut buf)?;
             Ok(String::from_utf8(buf)?)
         })?;
@@ -385,7 +473,12 @@ impl Usmap {
                         true => reader.read_u16::<LE>()?,
                         false => reader.read_u8()? as u16,
                     };
-                    schema.module_path = Some(module_paths[index as usize].clone());
+                    let module_path = module_paths.get(index as usize).ok_or_else(|| {
+                        Error::invalid_file(format!(
+                            "Module path index {index} out of range, module path count: {num_module_paths}"
+                        ))
+                    })?;
+                    schema.module_path = Some(module_path.clone());
                 }
             }
         }
@@ -393,6 +486,206 @@ impl Usmap {
         Ok(())
     }
 
+    /// Write usmap file
+    ///
+    /// The name map is rebuilt from the names used by the enums and schemas,
+    /// the data is compressed with `compression_method`.
+    /// With the `PATHS` extension every schema needs a module path, otherwise an error is returned.
+    pub fn write<W: Write + Seek>(&self, cursor: &mut W) -> Result<(), Error> {
+        let name_map = self.collect_names();
+        let module_paths = self.collect_module_paths();
+
+        let mut data = Cursor::new(Vec::new());
+        let mut data_writer = RawWriter::<PackageIndex, _>::new(
+            &mut data,
+            self.object_version,
+            self.object_version_ue5,
+            false,
+            NameMap::new(),
+        );
+
+        data_writer.write_i32::<LE>(name_map.len() as i32)?;
+        for name in &name_map {
+            let name_length = u8::try_from(name.len()).map_err(|_| {
+                Error::invalid_file(format!("Name {name} is too long for a usmap file"))
+            })?;
+            data_writer.write_u8(name_length)?;
+            data_writer.write_all(name.as_bytes())?;
+        }
+
+        data_writer.write_u32::<LE>(self.enum_map.len() as u32)?;
+
+        let mut writer = UsmapWriter::new(&mut data_writer, &name_map, &self.custom_versions);
+
+        for (_, enum_name, enum_names) in self.enum_map.iter() {
+            let enum_names_len = u8::try_from(enum_names.len()).map_err(|_| {
+                Error::invalid_file(format!("Enum {enum_name} has too many values"))
+            })?;
+
+            writer.write_name(enum_name)?;
+            writer.write_u8(enum_names_len)?;
+            for name in enum_names {
+                writer.write_name(name)?;
+            }
+        }
+
+        writer.write_u32::<LE>(self.schemas.len() as u32)?;
+        for schema in self.schemas.values() {
+            schema.write(&mut writer)?;
+        }
+
+        // write extensions
+
+        if self.extension_version != UsmapExtensionVersion::NONE {
+            writer.write_u32::<LE>(self.extension_version.bits())?;
+
+            if self
+                .extension_version
+                .contains(UsmapExtensionVersion::PATHS)
+            {
+                writer.write_u16::<LE>(module_paths.len() as u16)?;
+                for module_path in module_paths.iter().copied() {
+                    writer.write_fstring(Some(module_path))?;
+                }
+
+                for schema in self.schemas.values() {
+                    let index = schema
+                        .module_path
+                        .as_ref()
+                        .and_then(|module_path| module_paths.iter().position(|e| e == module_path))
+                        .ok_or_else(|| UsmapError::missing_module_path(&schema.name))?;
+                    match module_paths.len() > u8::MAX as usize {
+                        true => writer.write_u16::<LE>(index as u16)?,
+                        false => writer.write_u8(index as u8)?,
+                    }
+                }
+            }
+        }
+
+        let data = data.into_inner();
+
+        let compressed_data = match self.compression_method {
+            EUsmapCompressionMethod::None => data.clone(),
+            EUsmapCompressionMethod::Brotli => {
+                let mut compressed_data = Vec::new();
+                brotli::BrotliCompress(
+                    &mut Cursor::new(&data),
+                    &mut compressed_data,
+                    &brotli::enc::BrotliEncoderParams::default(),
+                )?;
+                compressed_data
+            }
+            EUsmapCompressionMethod::ZStandard => {
+                zstd::stream::encode_all(Cursor::new(&data), zstd::DEFAULT_COMPRESSION_LEVEL)?
+            }
+            EUsmapCompressionMethod::Oodle => {
+                compression::compress(CompressionMethod::Oodle, &data)?
+            }
+            EUsmapCompressionMethod::Unknown => {
+                return Err(
+                    UsmapError::unsupported_compression(self.compression_method as u8).into(),
+                );
+            }
+        };
+
+        let mut writer = RawWriter::<PackageIndex, _>::new(
+            cursor,
+            ObjectVersion::UNKNOWN,
+            ObjectVersionUE5::UNKNOWN,
+            false,
+            NameMap::new(),
+        );
+
+        writer.write_u16::<LE>(Self::ASSET_MAGIC)?;
+        writer.write_u8(self.version as u8)?;
+
+        if self.version >= EUsmapVersion::PackageVersioning {
+            let has_versioning = self.object_version != ObjectVersion::UNKNOWN;
+            writer.write_bool(has_versioning)?;
+
+            if has_versioning {
+                writer.write_i32::<LE>(self.object_version as i32)?;
+                writer.write_i32::<LE>(self.object_version_ue5 as i32)?;
+                writer.write_i32::<LE>(self.custom_versions.len() as i32)?;
+                for custom_version in &self.custom_versions {
+                    custom_version.write(&mut writer)?;
+                }
+                writer.write_u32::<LE>(self.net_cl)?;
+            }
+        }
+
+        writer.write_u8(self.compression_method as u8)?;
+        writer.write_u32::<LE>(compressed_data.len() as u32)?;
+        writer.write_u32::<LE>(data.len() as u32)?;
+        writer.write_all(&compressed_data)?;
+
+        Ok(())
+    }
+
+    /// Collect all names used by the enums and schemas in the order they are written
+    fn collect_names(&self) -> Vec<String> {
+        fn collect_property_names<'a>(
+            property_data: &'a UsmapPropertyData,
+            names: &mut Vec<&'a str>,
+        ) {
+            match property_data {
+                UsmapPropertyData::UsmapEnumPropertyData(data) => {
+                    collect_property_names(&data.inner_property, names);
+                    names.push(&data.name);
+                }
+                UsmapPropertyData::UsmapStructPropertyData(data) => {
+                    names.push(&data.struct_type);
+                }
+                UsmapPropertyData::UsmapSetPropertyData(data) => {
+                    collect_property_names(&data.inner_type, names);
+                }
+                UsmapPropertyData::UsmapArrayPropertyData(data) => {
+                    collect_property_names(&data.inner_type, names);
+                }
+                UsmapPropertyData::UsmapMapPropertyData(data) => {
+                    collect_property_names(&data.inner_type, names);
+                    collect_property_names(&data.value_type, names);
+                }
+                UsmapPropertyData::UsmapShallowPropertyData(_) => {}
+            }
+        }
+
+        let mut names = Vec::new();
+
+        for (_, enum_name, enum_names) in self.enum_map.iter() {
+            names.push(enum_name.as_str());
+            names.extend(enum_names.iter().map(|e| e.as_str()));
+        }
+
+        for schema in self.schemas.values() {
+            names.push(schema.name.as_str());
+            if !schema.super_type.is_empty() {
+                names.push(schema.super_type.as_str());
+            }
+            for property in schema.properties.values() {
+                names.push(property.name.as_str());
+                collect_property_names(&property.property_data, &mut names);
+            }
+        }
+
+        let mut seen = HashSet::new();
+        names
+            .into_iter()
+            .filter(|e| seen.insert(*e))
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    /// Collect all unique module paths of the schemas
+    fn collect_module_paths(&self) -> Vec<&str> {
+        let mut seen = HashSet::new();
+        self.schemas
+            .values()
+            .filter_map(|e| e.module_path.as_deref())
+            .filter(|e| seen.insert(*e))
+            .collect()
+    }
+
     /// Create a new usmap file
     pub fn new(cursor: Cursor<Vec<u8>>) -> Result<Self, Error> {
         let mut usmap = Usmap {
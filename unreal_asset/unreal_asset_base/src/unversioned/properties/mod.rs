@@ -2,13 +2,14 @@
 
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::mem::size_of;
 
-use byteorder::{ReadBytesExt, LE};
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
 use enum_dispatch::enum_dispatch;
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 use crate::reader::{ArchiveReader, ArchiveWriter};
-use crate::types::{PackageIndex};
+use crate::types::PackageIndex;
 use crate::unversioned::{usmap_reader::UsmapReader, usmap_writer::UsmapWriter};
 use crate::Error;
 
@@ -126,6 +127,49 @@ impl std::fmt::Display for EPropertyType {
     }
 }
 
+impl std::str::FromStr for EPropertyType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "ByteProperty" => EPropertyType::ByteProperty,
+            "BoolProperty" => EPropertyType::BoolProperty,
+            "IntProperty" => EPropertyType::IntProperty,
+            "FloatProperty" => EPropertyType::FloatProperty,
+            "ObjectProperty" => EPropertyType::ObjectProperty,
+            "NameProperty" => EPropertyType::NameProperty,
+            "DelegateProperty" => EPropertyType::DelegateProperty,
+            "DoubleProperty" => EPropertyType::DoubleProperty,
+            "ArrayProperty" => EPropertyType::ArrayProperty,
+            "StructProperty" => EPropertyType::StructProperty,
+            "StrProperty" => EPropertyType::StrProperty,
+            "TextProperty" => EPropertyType::TextProperty,
+            "InterfaceProperty" => EPropertyType::InterfaceProperty,
+            "MulticastDelegateProperty" => EPropertyType::MulticastDelegateProperty,
+            "WeakObjectProperty" => EPropertyType::WeakObjectProperty,
+            "LazyObjectProperty" => EPropertyType::LazyObjectProperty,
+            "AssetObjectProperty" => EPropertyType::AssetObjectProperty,
+            "SoftObjectProperty" => EPropertyType::SoftObjectProperty,
+            "UInt64Property" => EPropertyType::UInt64Property,
+            "UInt32Property" => EPropertyType::UInt32Property,
+            "UInt16Property" => EPropertyType::UInt16Property,
+            "Int64Property" => EPropertyType::Int64Property,
+            "Int16Property" => EPropertyType::Int16Property,
+            "Int8Property" => EPropertyType::Int8Property,
+            "MapProperty" => EPropertyType::MapProperty,
+            "SetProperty" => EPropertyType::SetProperty,
+            "EnumProperty" => EPropertyType::EnumProperty,
+            "FieldPathProperty" => EPropertyType::FieldPathProperty,
+            "Unknown" => EPropertyType::Unknown,
+            _ => {
+                return Err(Error::invalid_file(format!(
+                    "Unknown usmap property type {s}"
+                )))
+            }
+        })
+    }
+}
+
 /// This must be implemented for all UsmapPropertyDatas
 #[enum_dispatch]
 pub trait UsmapPropertyDataTrait: Debug + Hash + Clone + PartialEq + Eq {
@@ -213,4 +257,17 @@ impl UsmapProperty {
             property_data,
         })
     }
+
+    /// Write an `UsmapProperty` to an asset
+    pub fn write<W: ArchiveWriter<PackageIndex>>(
+        &self,
+        asset: &mut UsmapWriter<'_, '_, W>,
+    ) -> Result<usize, Error> {
+        asset.write_u16::<LE>(self.schema_index)?;
+        asset.write_u8(self.array_size)?;
+        let mut size = size_of::<u16>() + size_of::<u8>();
+        size += asset.write_name(&self.name)?;
+        size += self.property_data.write(asset)?;
+        Ok(size)
+    }
 }
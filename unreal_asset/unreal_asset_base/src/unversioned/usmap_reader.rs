@@ -57,6 +57,24 @@ impl<'parent_reader, 'asset, R: ArchiveReader<PackageIndex>>
             UsmapError::name_map_index_out_of_range(self.name_map.len(), index).into()
         })
     }
+
+    /// Read a name that may be absent from this archive, absent names are stored as -1
+    pub fn read_optional_name(&mut self) -> Result<Option<String>, Error> {
+        let index = self.read_i32::<LE>()?;
+        if index == -1 {
+            return Ok(None);
+        }
+        if index < 0 {
+            return Err(UsmapError::name_map_index_out_of_range(self.name_map.len(), index).into());
+        }
+        self.name_map
+            .get(index as usize)
+            .cloned()
+            .map(Some)
+            .ok_or_else(|| {
+                UsmapError::name_map_index_out_of_range(self.name_map.len(), index).into()
+            })
+    }
 }
 
 impl<'parent_reader, 'asset, R: ArchiveReader<PackageIndex>> ArchiveTrait<PackageIndex>
@@ -1,12 +1,16 @@
 //! Usmap file writer
 
+use std::collections::HashMap;
 use std::io::{Seek, Write};
+use std::mem::size_of;
+
+use byteorder::{WriteBytesExt, LE};
 
 use crate::{
     containers::{indexed_map::IndexedMap, name_map::NameMap, shared_resource::SharedResource},
     custom_version::{CustomVersion, CustomVersionTrait},
     engine_version::EngineVersion,
-    error::Error,
+    error::{Error, UsmapError},
     object_version::{ObjectVersion, ObjectVersionUE5},
     passthrough_archive_writer,
     reader::{
@@ -22,8 +26,8 @@ use super::Usmap;
 pub struct UsmapWriter<'parent_writer, 'asset, W: ArchiveWriter<PackageIndex>> {
     /// Parent writer
     parent_writer: &'parent_writer mut W,
-    /// Name map
-    _name_map: &'asset [String],
+    /// Name map lookup
+    name_map: HashMap<&'asset str, i32>,
     /// Custom versions
     custom_versions: &'asset [CustomVersion],
 }
@@ -31,9 +35,42 @@ pub struct UsmapWriter<'parent_writer, 'asset, W: ArchiveWriter<PackageIndex>> {
 impl<'parent_writer, 'asset, W: ArchiveWriter<PackageIndex>>
     UsmapWriter<'parent_writer, 'asset, W>
 {
+    /// Create a new `UsmapWriter` instance
+    pub fn new(
+        parent_writer: &'parent_writer mut W,
+        name_map: &'asset [String],
+        custom_versions: &'asset [CustomVersion],
+    ) -> Self {
+        UsmapWriter {
+            parent_writer,
+            name_map: name_map
+                .iter()
+                .enumerate()
+                .map(|(index, name)| (name.as_str(), index as i32))
+                .collect(),
+            custom_versions,
+        }
+    }
+
     /// Write a name to this archive
-    pub fn write_name(&mut self, _: &str) -> Result<usize, Error> {
-        todo!()
+    pub fn write_name(&mut self, name: &str) -> Result<usize, Error> {
+        let index = *self
+            .name_map
+            .get(name)
+            .ok_or_else(|| UsmapError::name_not_found(name))?;
+        self.write_i32::<LE>(index)?;
+        Ok(size_of::<i32>())
+    }
+
+    /// Write a name that may be absent to this archive, absent names are written as -1
+    pub fn write_optional_name(&mut self, name: Option<&str>) -> Result<usize, Error> {
+        match name {
+            Some(name) => self.write_name(name),
+            None => {
+                self.write_i32::<LE>(-1)?;
+                Ok(size_of::<i32>())
+            }
+        }
     }
 }
 
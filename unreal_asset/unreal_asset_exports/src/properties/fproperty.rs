@@ -35,6 +35,10 @@ macro_rules! parse_simple_property {
         }
 
         impl FPropertyTrait for $prop_name {
+            fn get_generic_property(&self) -> &FGenericProperty {
+                &self.generic_property
+            }
+
             fn write<Writer: ArchiveWriter<impl PackageIndexTrait>>(
                 &self,
                 asset: &mut Writer,
@@ -79,6 +83,10 @@ macro_rules! parse_simple_property_index {
         }
 
         impl FPropertyTrait for $prop_name {
+            fn get_generic_property(&self) -> &FGenericProperty {
+                &self.generic_property
+            }
+
             fn write<Writer: ArchiveWriter<impl PackageIndexTrait>>(&self, asset: &mut Writer) -> Result<(), Error> {
                 self.generic_property.write(asset)?;
                 $(
@@ -122,6 +130,10 @@ macro_rules! parse_simple_property_prop {
         }
 
         impl FPropertyTrait for $prop_name {
+            fn get_generic_property(&self) -> &FGenericProperty {
+                &self.generic_property
+            }
+
             fn write<Writer: ArchiveWriter<impl PackageIndexTrait>>(&self, asset: &mut Writer) -> Result<(), Error> {
                 self.generic_property.write(asset)?;
                 $(
@@ -136,6 +148,9 @@ macro_rules! parse_simple_property_prop {
 /// This must be implemented for all FProperties
 #[enum_dispatch]
 pub trait FPropertyTrait: Debug + Clone + PartialEq + Eq + Hash {
+    /// Get the generic property data shared by all properties
+    fn get_generic_property(&self) -> &FGenericProperty;
+
     /// Write `FProperty` to an asset
     fn write<Writer: ArchiveWriter<impl PackageIndexTrait>>(
         &self,
@@ -362,6 +377,10 @@ impl FGenericProperty {
 }
 
 impl FPropertyTrait for FGenericProperty {
+    fn get_generic_property(&self) -> &FGenericProperty {
+        self
+    }
+
     fn write<Writer: ArchiveWriter<impl PackageIndexTrait>>(
         &self,
         asset: &mut Writer,
@@ -396,6 +415,10 @@ impl FEnumProperty {
 }
 
 impl FPropertyTrait for FEnumProperty {
+    fn get_generic_property(&self) -> &FGenericProperty {
+        &self.generic_property
+    }
+
     fn write<Writer: ArchiveWriter<impl PackageIndexTrait>>(
         &self,
         asset: &mut Writer,
@@ -433,6 +456,10 @@ impl FBoolProperty {
 }
 
 impl FPropertyTrait for FBoolProperty {
+    fn get_generic_property(&self) -> &FGenericProperty {
+        &self.generic_property
+    }
+
     fn write<Writer: ArchiveWriter<impl PackageIndexTrait>>(
         &self,
         asset: &mut Writer,
@@ -0,0 +1,223 @@
+//! Asset registry query index
+
+use std::collections::HashMap;
+
+use crate::{
+    objects::{asset_data::AssetData, depends_node::DependsNode},
+    AssetRegistryState,
+};
+
+/// Indexed lookups over an [`AssetRegistryState`]
+///
+/// Names are compared by their string representation, including the instance number,
+/// so the index works the same for registries with and without a name table.
+#[derive(Debug)]
+pub struct AssetRegistryIndex<'a> {
+    /// Indexed asset registry state
+    state: &'a AssetRegistryState,
+
+    /// Asset data index by object path
+    by_object_path: HashMap<String, usize>,
+    /// Asset data indices by package name
+    by_package_name: HashMap<String, Vec<usize>>,
+    /// Asset data indices by asset class
+    by_class: HashMap<String, Vec<usize>>,
+    /// Asset data indices by tag key
+    by_tag: HashMap<String, Vec<usize>>,
+
+    /// Depends node index by serialized node index
+    nodes_by_index: HashMap<i32, usize>,
+    /// Depends node index by package name
+    nodes_by_package_name: HashMap<String, usize>,
+    /// Depends node indices of the nodes depending on each depends node
+    referencers: Vec<Vec<usize>>,
+}
+
+impl<'a> AssetRegistryIndex<'a> {
+    /// Build an index for an `AssetRegistryState`
+    pub fn new(state: &'a AssetRegistryState) -> Self {
+        let mut by_object_path = HashMap::new();
+        let mut by_package_name = HashMap::<_, Vec<_>>::new();
+        let mut by_class = HashMap::<_, Vec<_>>::new();
+        let mut by_tag = HashMap::<_, Vec<_>>::new();
+
+        for (i, asset_data) in state.assets_data.iter().enumerate() {
+            by_object_path
+                .entry(asset_data.object_path.to_string_with_number())
+                .or_insert(i);
+            by_package_name
+                .entry(asset_data.package_name.to_string_with_number())
+                .or_default()
+                .push(i);
+            if let Some(class) = get_asset_class(asset_data) {
+                by_class.entry(class).or_default().push(i);
+            }
+            for (_, key, _) in &asset_data.tags_and_values {
+                by_tag
+                    .entry(key.to_string_with_number())
+                    .or_default()
+                    .push(i);
+            }
+        }
+
+        let nodes_by_index = state
+            .depends_nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.get_index(), i))
+            .collect::<HashMap<_, _>>();
+
+        let mut nodes_by_package_name = HashMap::new();
+        // computed from the dependencies instead of the serialized referencers
+        // to also be correct for states which were modified after being read
+        let mut referencers = vec![Vec::new(); state.depends_nodes.len()];
+
+        for (i, node) in state.depends_nodes.iter().enumerate() {
+            if let Some(package_name) = &node.identifier.package_name {
+                nodes_by_package_name
+                    .entry(package_name.to_string_with_number())
+                    .or_insert(i);
+            }
+
+            for dependency in node
+                .hard_dependencies
+                .iter()
+                .chain(node.soft_dependencies.iter())
+            {
+                if let Some(&position) = nodes_by_index.get(&dependency.get_index()) {
+                    if !referencers[position].contains(&i) {
+                        referencers[position].push(i);
+                    }
+                }
+            }
+        }
+
+        AssetRegistryIndex {
+            state,
+            by_object_path,
+            by_package_name,
+            by_class,
+            by_tag,
+            nodes_by_index,
+            nodes_by_package_name,
+            referencers,
+        }
+    }
+
+    /// Get the indexed asset registry state
+    pub fn get_state(&self) -> &'a AssetRegistryState {
+        self.state
+    }
+
+    /// Get asset data by its object path, e.g. `/Game/Blueprints/BP_Player.BP_Player`
+    pub fn get_asset_by_object_path(&self, object_path: &str) -> Option<&'a AssetData> {
+        self.by_object_path
+            .get(object_path)
+            .map(|&i| &self.state.assets_data[i])
+    }
+
+    /// Get all asset data in a package, e.g. `/Game/Blueprints/BP_Player`
+    pub fn get_assets_by_package_name(&self, package_name: &str) -> Vec<&'a AssetData> {
+        self.get_assets(self.by_package_name.get(package_name))
+    }
+
+    /// Get all asset data of a class
+    ///
+    /// Registries with a version of `ClassPaths` or newer store the class as a `TopLevelAssetPath`,
+    /// it is looked up by its full path, e.g. `/Script/Engine.Blueprint`.
+    /// Older registries only store the class name, e.g. `Blueprint`.
+    pub fn get_assets_by_class(&self, class: &str) -> Vec<&'a AssetData> {
+        self.get_assets(self.by_class.get(class))
+    }
+
+    /// Get all asset data that has a tag, regardless of its value
+    pub fn get_assets_by_tag(&self, key: &str) -> Vec<&'a AssetData> {
+        self.get_assets(self.by_tag.get(key))
+    }
+
+    /// Get all asset data that has a tag with the given value
+    pub fn get_assets_by_tag_value(&self, key: &str, value: &str) -> Vec<&'a AssetData> {
+        self.get_assets_by_tag(key)
+            .into_iter()
+            .filter(|asset_data| {
+                asset_data
+                    .tags_and_values
+                    .iter()
+                    .any(|(_, tag, tag_value)| {
+                        tag.to_string_with_number() == key && tag_value.as_deref() == Some(value)
+                    })
+            })
+            .collect()
+    }
+
+    /// Get the depends node of a package
+    pub fn get_depends_node(&self, package_name: &str) -> Option<&'a DependsNode> {
+        self.nodes_by_package_name
+            .get(package_name)
+            .map(|&i| &self.state.depends_nodes[i])
+    }
+
+    /// Get the depends nodes of the packages a package hard or soft depends on
+    pub fn get_dependencies(&self, package_name: &str) -> Vec<&'a DependsNode> {
+        let Some(node) = self.get_depends_node(package_name) else {
+            return Vec::new();
+        };
+
+        node.hard_dependencies
+            .iter()
+            .chain(node.soft_dependencies.iter())
+            // dependencies only hold the index of the node they point to
+            .filter_map(|dependency| self.nodes_by_index.get(&dependency.get_index()))
+            .map(|&i| &self.state.depends_nodes[i])
+            .collect()
+    }
+
+    /// Get the depends nodes of the packages which hard or soft depend on a package
+    pub fn get_referencers(&self, package_name: &str) -> Vec<&'a DependsNode> {
+        self.nodes_by_package_name
+            .get(package_name)
+            .map(|&i| {
+                self.referencers[i]
+                    .iter()
+                    .map(|&referencer| &self.state.depends_nodes[referencer])
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get all asset data in the packages which hard or soft depend on a package
+    pub fn get_referencing_assets(&self, package_name: &str) -> Vec<&'a AssetData> {
+        self.get_referencers(package_name)
+            .into_iter()
+            .filter_map(|node| node.identifier.package_name.as_ref())
+            .flat_map(|referencer| {
+                self.get_assets_by_package_name(&referencer.to_string_with_number())
+            })
+            .collect()
+    }
+
+    /// Resolve asset data indices
+    fn get_assets(&self, indices: Option<&Vec<usize>>) -> Vec<&'a AssetData> {
+        indices
+            .map(|indices| {
+                indices
+                    .iter()
+                    .map(|&i| &self.state.assets_data[i])
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Get the class of an asset as a string
+fn get_asset_class(asset_data: &AssetData) -> Option<String> {
+    match (&asset_data.asset_path, &asset_data.asset_class) {
+        (Some(asset_path), _) => Some(format!(
+            "{}.{}",
+            asset_path.package_name.to_string_with_number(),
+            asset_path.asset_name.to_string_with_number()
+        )),
+        (None, Some(asset_class)) => Some(asset_class.to_string_with_number()),
+        (None, None) => None,
+    }
+}
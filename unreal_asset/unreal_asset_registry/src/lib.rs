@@ -24,10 +24,12 @@ use unreal_asset_base::{
     Error,
 };
 
+pub mod index;
 pub(crate) mod name_table_reader;
 pub(crate) mod name_table_writer;
 pub mod objects;
 
+use index::AssetRegistryIndex;
 use name_table_reader::NameTableReader;
 use name_table_writer::NameTableWriter;
 use objects::{
@@ -354,4 +356,9 @@ impl AssetRegistryState {
     pub fn get_version(&self) -> FAssetRegistryVersionType {
         self.version
     }
+
+    /// Builds an index for looking up assets by path, class or tag and for dependency lookups
+    pub fn index(&self) -> AssetRegistryIndex<'_> {
+        AssetRegistryIndex::new(self)
+    }
 }
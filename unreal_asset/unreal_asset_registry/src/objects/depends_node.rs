@@ -144,6 +144,7 @@ lazy_static! {
 
 #[allow(unused)]
 const HARD_MANAGE_BITS: u32 = 0x1;
+const HARD_MANAGE_BIT: u8 = 0;
 #[allow(unused)]
 const SOFT_MANAGE_BITS: u32 = 0x0;
 
@@ -151,9 +152,9 @@ impl DependsNode {
     /// Package `EDependencyProperty` enum into a byte
     #[allow(clippy::identity_op)] // allow for clarity
     fn package_properties_to_byte(properties: EDependencyProperty) -> u8 {
-        (0x1 * (properties & EDependencyProperty::HARD).bits() as u8)
-            | (0x2 * (properties & EDependencyProperty::GAME).bits() as u8)
-            | (0x4 * (properties & EDependencyProperty::BUILD).bits() as u8)
+        (0x1 * properties.contains(EDependencyProperty::HARD) as u8)
+            | (0x2 * properties.contains(EDependencyProperty::GAME) as u8)
+            | (0x4 * properties.contains(EDependencyProperty::BUILD) as u8)
     }
 
     /// Read `DependsNode` dependencies
@@ -161,6 +162,7 @@ impl DependsNode {
         asset: &mut Reader,
         preallocated_depends_node_buffer: &[DependsNode],
         flag_set_width: i32,
+        hard_bit: u8,
     ) -> Result<LoadedDependencyNodes, Error> {
         let mut sort_indexes = Vec::new();
         let mut pointer_dependencies = Vec::new();
@@ -205,15 +207,9 @@ impl DependsNode {
 
         for index in &sort_indexes {
             let node = pointer_dependencies[*index as usize];
-            let package_flags = node.package_flags.as_ref().ok_or_else(|| {
-                Error::invalid_file(
-                    "No package flags on asset registry with version >= AddedDependencyFlags"
-                        .to_string(),
-                )
-            })?;
-
-            if package_flags
-                .get(*HARD_BIT as usize)
+
+            if in_flag_bits
+                .get((*index * flag_set_width) as usize + hard_bit as usize)
                 .as_deref()
                 .copied()
                 .unwrap_or(false)
@@ -224,7 +220,7 @@ impl DependsNode {
             }
         }
 
-        let mut out_flag_bits = BitVec::with_capacity(num_flag_bits as usize);
+        let mut out_flag_bits = BitVec::repeat(false, num_flag_bits as usize);
         for write_index in 0..in_dependencies.len() as i32 {
             let read_index = &sort_indexes[write_index as usize];
 
@@ -367,6 +363,11 @@ impl DependsNode {
         }
     }
 
+    /// Get this node's index in the asset registry
+    pub fn get_index(&self) -> i32 {
+        self.index
+    }
+
     /// Load `DependsNode` dependencies
     pub fn load_dependencies<Reader: ArchiveReader<impl PackageIndexTrait>>(
         &mut self,
@@ -379,6 +380,7 @@ impl DependsNode {
             asset,
             preallocated_depends_node_buffer,
             PACKAGE_FLAG_SET_WIDTH,
+            *HARD_BIT,
         )?;
 
         let name_dependencies =
@@ -389,6 +391,7 @@ impl DependsNode {
                 asset,
                 preallocated_depends_node_buffer,
                 MANAGE_FLAG_SET_WIDTH,
+                HARD_MANAGE_BIT,
             )?;
 
         let referencers =
@@ -460,7 +463,7 @@ impl DependsNode {
     ) -> Result<(), Error> {
         for _ in 0..num {
             let index = asset.read_i32::<LE>()?;
-            if index < 0 || nodes.len() <= index as usize {
+            if index < 0 || preallocated_depends_node_buffer.len() <= index as usize {
                 return Err(RegistryError::InvalidIndex(index).into());
             }
 
@@ -6,7 +6,7 @@ use std::{
     string::{FromUtf16Error, FromUtf8Error},
 };
 
-#[cfg(feature = "read_write")]
+#[cfg(any(feature = "read_write", feature = "oodle"))]
 use thiserror::Error;
 
 /// Gets thrown when there is an error reading/writing an FString.
@@ -29,3 +29,32 @@ pub enum FStringError {
     #[error("Io Error {0}")]
     Io(#[from] io::Error),
 }
+
+/// Gets thrown when Oodle compression or decompression fails.
+#[cfg(feature = "oodle")]
+#[derive(Error, Debug)]
+pub enum OodleError {
+    /// No Oodle library was loaded
+    #[error("Oodle library is not loaded")]
+    NotLoaded,
+    /// The Oodle library could not be loaded
+    #[error("Failed to load the Oodle library: {0}")]
+    Load(#[from] libloading::Error),
+    /// Compression failed
+    #[error("Oodle compression failed")]
+    Compress,
+    /// Decompression failed
+    #[error("Oodle decompression failed")]
+    Decompress,
+}
+
+#[cfg(feature = "oodle")]
+impl From<OodleError> for std::io::Error {
+    fn from(e: OodleError) -> Self {
+        let kind = match e {
+            OodleError::NotLoaded | OodleError::Load(_) => std::io::ErrorKind::NotFound,
+            OodleError::Compress | OodleError::Decompress => std::io::ErrorKind::InvalidData,
+        };
+        std::io::Error::new(kind, e)
+    }
+}
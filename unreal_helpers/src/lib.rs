@@ -12,6 +12,7 @@
 //! - `guid`: Enables [`Guid`] type.
 //! - `serde`: Enables `serde` support for [`Guid`] type.
 //! - `bitvec`: Enables extension Trait [`BitVecExt`].
+//! - `oodle`: Enables the [`oodle`] module which loads the Oodle library at runtime.
 
 #[cfg(feature = "bitvec")]
 pub mod bitvec_ext;
@@ -25,6 +26,9 @@ pub mod guid;
 #[cfg(feature = "guid")]
 pub use guid::Guid;
 
+#[cfg(feature = "oodle")]
+pub mod oodle;
+
 #[cfg(feature = "path")]
 pub mod path;
 #[cfg(feature = "path")]
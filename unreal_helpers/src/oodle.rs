@@ -0,0 +1,166 @@
+//! Oodle compression and decompression
+//!
+//! Oodle can not be distributed with this crate, instead the shared library shipped with a game
+//! (e.g. `oo2core_9_win64.dll` or `liboo2corelinux64.so.9`) is loaded at runtime with [`load`].
+//! The loaded library is shared by every crate using this module.
+
+use std::ffi::{c_void, OsStr};
+use std::ptr;
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
+use libloading::Library;
+
+use crate::error::OodleError;
+
+type OodleLzDecompress = unsafe extern "system" fn(
+    comp_buf: *const u8,
+    comp_buf_size: isize,
+    raw_buf: *mut u8,
+    raw_len: isize,
+    fuzz_safe: i32,
+    check_crc: i32,
+    verbosity: i32,
+    dec_buf_base: *mut u8,
+    dec_buf_size: isize,
+    fp_callback: *mut c_void,
+    callback_user_data: *mut c_void,
+    decoder_memory: *mut c_void,
+    decoder_memory_size: isize,
+    thread_phase: i32,
+) -> isize;
+
+type OodleLzCompress = unsafe extern "system" fn(
+    compressor: i32,
+    raw_buf: *const u8,
+    raw_len: isize,
+    comp_buf: *mut u8,
+    level: i32,
+    options: *const c_void,
+    dictionary_base: *const c_void,
+    lrm: *const c_void,
+    scratch_mem: *mut c_void,
+    scratch_size: isize,
+) -> isize;
+
+/// `Normal` compression level
+pub const LEVEL_NORMAL: i32 = 4;
+
+/// Oodle compressor used when compressing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OodleCompressor {
+    /// Kraken, used by most games
+    #[default]
+    Kraken = 8,
+    /// Mermaid, faster decompression than Kraken
+    Mermaid = 9,
+    /// Selkie, fastest decompression
+    Selkie = 11,
+    /// Leviathan, highest compression ratio
+    Leviathan = 13,
+}
+
+/// Loaded Oodle library
+struct Oodle {
+    decompress: OodleLzDecompress,
+    compress: OodleLzCompress,
+    // the library has to outlive the function pointers
+    _library: Library,
+}
+
+lazy_static! {
+    static ref OODLE: RwLock<Option<Oodle>> = RwLock::new(None);
+}
+
+/// Load the Oodle shared library from the given path
+///
+/// The library is used for all Oodle compression and decompression afterwards,
+/// a previously loaded library is replaced.
+///
+/// # Safety
+///
+/// The library at the path has to be a valid Oodle library (`oo2core`), loading it executes
+/// its initialization routines.
+pub unsafe fn load<P: AsRef<OsStr>>(path: P) -> Result<(), OodleError> {
+    let library = Library::new(path)?;
+    let decompress = *library.get::<OodleLzDecompress>(b"OodleLZ_Decompress\0")?;
+    let compress = *library.get::<OodleLzCompress>(b"OodleLZ_Compress\0")?;
+
+    *OODLE.write().unwrap_or_else(|e| e.into_inner()) = Some(Oodle {
+        decompress,
+        compress,
+        _library: library,
+    });
+
+    Ok(())
+}
+
+/// Check if an Oodle library was loaded
+pub fn is_loaded() -> bool {
+    OODLE.read().unwrap_or_else(|e| e.into_inner()).is_some()
+}
+
+/// Decompress Oodle compressed data, `decompressed` has to be the size of the decompressed data
+pub fn decompress(compressed: &[u8], decompressed: &mut [u8]) -> Result<(), OodleError> {
+    let oodle = OODLE.read().unwrap_or_else(|e| e.into_inner());
+    let oodle = oodle.as_ref().ok_or(OodleError::NotLoaded)?;
+
+    let len = unsafe {
+        (oodle.decompress)(
+            compressed.as_ptr(),
+            compressed.len() as isize,
+            decompressed.as_mut_ptr(),
+            decompressed.len() as isize,
+            1,
+            0,
+            0,
+            ptr::null_mut(),
+            0,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            0,
+            3,
+        )
+    };
+
+    match len == decompressed.len() as isize {
+        true => Ok(()),
+        false => Err(OodleError::Decompress),
+    }
+}
+
+/// Compress data with the given compressor and compression level
+pub fn compress(
+    data: &[u8],
+    compressor: OodleCompressor,
+    level: i32,
+) -> Result<Vec<u8>, OodleError> {
+    let oodle = OODLE.read().unwrap_or_else(|e| e.into_inner());
+    let oodle = oodle.as_ref().ok_or(OodleError::NotLoaded)?;
+
+    // worst case size as documented by oodle
+    let mut compressed = vec![0u8; data.len() + 274 * data.len().div_ceil(0x40000)];
+
+    let len = unsafe {
+        (oodle.compress)(
+            compressor as i32,
+            data.as_ptr(),
+            data.len() as isize,
+            compressed.as_mut_ptr(),
+            level,
+            ptr::null(),
+            ptr::null(),
+            ptr::null(),
+            ptr::null_mut(),
+            0,
+        )
+    };
+
+    if len <= 0 {
+        return Err(OodleError::Compress);
+    }
+
+    compressed.truncate(len as usize);
+    Ok(compressed)
+}
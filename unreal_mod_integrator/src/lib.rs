@@ -22,7 +22,7 @@ use unreal_asset::{
     Asset,
 };
 use unreal_mod_metadata::{Metadata, SyncMode};
-use unreal_pak::{pakversion::PakVersion, PakMemory, PakReader};
+use unreal_pak::{pakversion::PakVersion, PakMemory, PakReader, PakVfs};
 
 mod assets;
 pub mod error;
@@ -387,10 +387,13 @@ pub fn integrate_mods<
     });
 
     let game_dir = fs::read_dir(game_path)?;
-    let game_files: Vec<File> = game_dir
+    let game_files: Vec<(String, File)> = game_dir
         .filter_map(|e| e.ok())
         .filter(|e| e.path().extension().map(|e| e == "pak").unwrap_or(false))
-        .filter_map(|e| File::open(e.path()).ok())
+        .filter_map(|e| {
+            let file = File::open(e.path()).ok()?;
+            Some((e.file_name().to_string_lossy().into_owned(), file))
+        })
         .collect();
     if game_files.is_empty() {
         return Err(IntegrationError::game_not_found().into());
@@ -401,17 +404,23 @@ pub fn integrate_mods<
         .chain(core_mods)
         .chain(enabled_baked_mods)
         .filter_map(|e| match e {
-            IntegratorMod::File(file_mod) => File::open(&file_mod.path).ok(),
-            IntegratorMod::Baked(baked_mod) => baked_mod.write(paks_path).ok(),
+            IntegratorMod::File(file_mod) => {
+                let name = file_mod.path.file_name()?.to_string_lossy().into_owned();
+                Some((name, File::open(&file_mod.path).ok()?))
+            }
+            IntegratorMod::Baked(baked_mod) => Some((
+                baked_mod.filename.to_owned(),
+                baked_mod.write(paks_path).ok()?,
+            )),
             _ => None,
         })
         .collect::<Vec<_>>();
 
-    let mut mod_paks = Vec::new();
+    let mut mod_paks = PakVfs::new();
     let mut read_mods = Vec::new();
     let mut optional_mods_data = HashMap::new();
 
-    for mod_file in mod_files {
+    for (mod_name, mod_file) in mod_files {
         let mut pak = PakReader::new(BufReader::new(mod_file));
         pak.load_index()?;
 
@@ -431,8 +440,11 @@ pub fn integrate_mods<
                 .push(data.clone());
         }
 
-        mod_paks.push(pak);
+        mod_paks.mount(mod_name, pak);
     }
+    // paks are ordered from the highest to the lowest priority,
+    // so the first pak containing an asset is the one the game loads it from
+    let mut mod_paks = mod_paks.into_readers();
 
     if !mods.is_empty() {
         let mut generated_pak = PakMemory::new(PakVersion::FnameBasedCompressionMethod);
@@ -487,12 +499,13 @@ pub fn integrate_mods<
             );
         }
 
-        let mut game_paks = Vec::new();
-        for game_file in game_files {
+        let mut game_paks = PakVfs::new();
+        for (game_name, game_file) in game_files {
             let mut pak = PakReader::new(BufReader::new(game_file));
             pak.load_index()?;
-            game_paks.push(pak);
+            game_paks.mount(game_name, pak);
         }
+        let mut game_paks = game_paks.into_readers();
 
         let empty_vec: Vec<Value> = Vec::new();
 
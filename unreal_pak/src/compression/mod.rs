@@ -0,0 +1,410 @@
+//! Compression abstraction
+//! Currently supportted compressions (in addition to no compression):
+//! - Zlib
+//! - Gzip
+//! - LZ4
+//! - Zstd
+//! - Oodle (requires loading the Oodle library at runtime, see [`OodleBackend::load`])
+//!
+//! Additional compression methods can be supported by implementing [`CompressionBackend`]
+//! and registering it with [`register_backend`].
+
+//* Note: when adding more compressions you should only have to update stuff in this module, but in a few places.
+
+use std::fmt::Debug;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::sync::{Arc, RwLock};
+
+use flate2::{
+    read::{GzDecoder, ZlibDecoder},
+    write::{GzEncoder, ZlibEncoder},
+};
+use lazy_static::lazy_static;
+
+use crate::error::PakError;
+use crate::pakversion::PakVersion;
+
+mod oodle;
+
+pub use oodle::{OodleBackend, OodleCompressor};
+
+/// A compression method implementation that can be used to compress and decompress pak entries.
+pub trait CompressionBackend: Debug + Send + Sync {
+    /// Name of the compression method as it is stored in the pak file, e.g. `"Zlib"`.
+    fn name(&self) -> &'static str;
+
+    /// Check if the backend can currently be used, e.g. if a required library was loaded.
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    /// Compress a single compression block.
+    fn compress(&self, data: &[u8]) -> io::Result<Vec<u8>>;
+
+    /// Decompress a single compression block and append the result to `buf`.
+    /// `decompressed_size` is the expected size of the decompressed block.
+    fn decompress(
+        &self,
+        buf: &mut Vec<u8>,
+        data: &[u8],
+        decompressed_size: usize,
+    ) -> io::Result<()>;
+}
+
+lazy_static! {
+    static ref BACKENDS: RwLock<Vec<Arc<dyn CompressionBackend>>> = RwLock::new(vec![
+        Arc::new(ZlibBackend),
+        Arc::new(GzipBackend),
+        Arc::new(Lz4Backend),
+        Arc::new(ZstdBackend),
+        Arc::new(OodleBackend::default()),
+    ]);
+}
+
+/// Register a compression backend, replacing any previously registered backend with the same name.
+pub fn register_backend<B: CompressionBackend + 'static>(backend: B) {
+    let mut backends = BACKENDS.write().unwrap_or_else(|e| e.into_inner());
+    backends.retain(|e| e.name() != backend.name());
+    backends.push(Arc::new(backend));
+}
+
+/// Get the backend registered for a compression method name.
+fn get_backend(name: &str) -> Option<Arc<dyn CompressionBackend>> {
+    BACKENDS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .find(|e| e.name() == name)
+        .cloned()
+}
+
+/// Names of compression methods that are known even when no backend is registered for them.
+const KNOWN_METHODS: [&str; 5] = ["Zlib", "Gzip", "LZ4", "Zstd", "Oodle"];
+
+/// Enum representing which compression method is being used for an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    /// No Compression
+    #[default]
+    None,
+    /// Known compression method
+    Known(&'static str),
+    /// Unknown compression method
+    Unknown([u8; 0x20]),
+}
+
+impl Compression {
+    /// Create Zlib Compression configuration
+    pub fn zlib() -> Self {
+        Self::Known("Zlib")
+    }
+
+    /// Create Gzip Compression configuration
+    pub fn gzip() -> Self {
+        Self::Known("Gzip")
+    }
+
+    /// Create LZ4 Compression configuration
+    pub fn lz4() -> Self {
+        Self::Known("LZ4")
+    }
+
+    /// Create Zstd Compression configuration
+    pub fn zstd() -> Self {
+        Self::Known("Zstd")
+    }
+
+    /// Create Oodle Compression configuration, requires the Oodle library to be loaded with [`OodleBackend::load`].
+    pub fn oodle() -> Self {
+        Self::Known("Oodle")
+    }
+
+    /// Create a Compression configuration from the name stored in the pak file.
+    pub fn from_name(name: &str) -> Self {
+        if name.is_empty() || name == "None" {
+            return Self::None;
+        }
+
+        if let Some(method) = KNOWN_METHODS.iter().find(|e| **e == name) {
+            return Self::Known(method);
+        }
+
+        match get_backend(name) {
+            Some(backend) => Self::Known(backend.name()),
+            None => Self::Unknown(pad_zeroes(name.as_bytes())),
+        }
+    }
+
+    /// Name of the compression method, `None` for unknown methods which are not valid UTF-8
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Self::None => Some("None"),
+            Self::Known(method) => Some(method),
+            Self::Unknown(method) => {
+                let len = method.iter().position(|e| *e == 0).unwrap_or(method.len());
+                std::str::from_utf8(&method[..len]).ok()
+            }
+        }
+    }
+
+    /// Check if a backend for this compression method is available.
+    pub fn is_supported(&self) -> bool {
+        match self {
+            Self::None => true,
+            Self::Known(method) => get_backend(method).is_some_and(|e| e.is_available()),
+            Self::Unknown(_) => false,
+        }
+    }
+
+    pub(crate) fn from_reader<R: Read + Seek>(reader: &mut R) -> io::Result<Self> {
+        let mut buf = [0; 0x20];
+        reader.read_exact(&mut buf)?;
+
+        if buf == [0; 0x20] {
+            return Ok(Self::None);
+        }
+
+        let len = buf.iter().position(|e| *e == 0).unwrap_or(buf.len());
+        Ok(match std::str::from_utf8(&buf[..len]) {
+            Ok(name) if pad_zeroes(name.as_bytes()) == buf => Self::from_name(name),
+            _ => Self::Unknown(buf),
+        })
+    }
+
+    pub(crate) fn from_u32(
+        compression_method_num: u32,
+        pak_version: PakVersion,
+        compression: &CompressionMethods,
+    ) -> Self {
+        if pak_version >= PakVersion::FnameBasedCompressionMethodInitial {
+            if compression_method_num == 0 {
+                Compression::None
+            } else if compression_method_num as usize <= CompressionMethods::slot_count(pak_version)
+            {
+                compression.0[compression_method_num as usize - 1]
+            } else {
+                let mut arr = [0; 0x20];
+                arr[0] = compression_method_num as u8;
+                Compression::Unknown(arr)
+            }
+        } else {
+            match compression_method_num {
+                0x01 | 0x10 | 0x20 => Compression::zlib(),
+                0x02 => Compression::gzip(),
+                // custom compression, used for Oodle by pretty much all games
+                0x04 => Compression::oodle(),
+                _ => Compression::None,
+            }
+        }
+    }
+
+    pub(crate) fn as_u32(
+        &self,
+        pak_version: PakVersion,
+        compression: &CompressionMethods,
+    ) -> Result<u32, PakError> {
+        match self {
+            Self::Known(method) => {
+                if pak_version >= PakVersion::FnameBasedCompressionMethodInitial {
+                    match compression.0[..CompressionMethods::slot_count(pak_version)]
+                        .iter()
+                        .enumerate()
+                        .find(|(_, method)| *method == self)
+                    {
+                        Some((i, _)) => Ok((i + 1) as u32),
+                        None => Err(PakError::compression_unsupported_unknown()),
+                    }
+                } else {
+                    match *method {
+                        "Zlib" => Ok(0x01),
+                        "Gzip" => Ok(0x02),
+                        "Oodle" => Ok(0x04),
+                        _ => Err(PakError::compression_unsupported(*self)),
+                    }
+                }
+            }
+            Self::None => Ok(0),
+            _ => Err(PakError::compression_unsupported_unknown()),
+        }
+    }
+
+    pub(crate) fn as_bytes(&self) -> [u8; 0x20] {
+        match self {
+            Self::None => [0; 0x20],
+            Self::Known(method) => pad_zeroes(method.as_bytes()),
+            Self::Unknown(method) => *method,
+        }
+    }
+
+    fn backend(&self) -> Result<Arc<dyn CompressionBackend>, PakError> {
+        match self {
+            Self::Known(method) => get_backend(method)
+                .filter(|e| e.is_available())
+                .ok_or_else(|| PakError::compression_unsupported(*self)),
+            _ => Err(PakError::compression_unsupported(*self)),
+        }
+    }
+
+    pub(crate) fn decompress(
+        &self,
+        buf: &mut Vec<u8>,
+        data: &[u8],
+        decompressed_size: usize,
+    ) -> Result<(), PakError> {
+        Ok(self.backend()?.decompress(buf, data, decompressed_size)?)
+    }
+
+    pub(crate) fn compress(&self, data: &[u8]) -> Result<Vec<u8>, PakError> {
+        Ok(self.backend()?.compress(data)?)
+    }
+}
+
+fn pad_zeroes(slice: &[u8]) -> [u8; 0x20] {
+    let mut arr = [0; 0x20];
+    let len = slice.len().min(0x20);
+    arr[..len].copy_from_slice(&slice[..len]);
+    arr
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct CompressionMethods(pub [Compression; 5]);
+
+impl CompressionMethods {
+    pub fn zlib() -> Self {
+        Self::single(Compression::zlib())
+    }
+
+    /// Compression methods with only the given method, used for all compressed entries
+    pub fn single(method: Compression) -> Self {
+        let mut methods = Self::default();
+        methods.0[0] = method;
+        methods
+    }
+
+    /// Number of compression methods stored in the footer, UE 4.22 only had 4 instead of 5.
+    pub fn slot_count(pak_version: PakVersion) -> usize {
+        match pak_version {
+            PakVersion::FnameBasedCompressionMethodInitial => 4,
+            _ => 5,
+        }
+    }
+
+    /// Read compression from provided reader. Position of the reader after return not specified.
+    pub fn from_reader<R: Read + Seek>(reader: &mut R) -> io::Result<Self> {
+        // Some versions of the pak file apparently have 4 instead of 5 entries.
+        // This is why first the length of the remaining stream is determined and then only
+        // the existing bytes read.
+        let old_pos = reader.stream_position()?;
+        let remaining_len = reader.seek(SeekFrom::End(0))? - old_pos;
+        reader.seek(SeekFrom::Start(old_pos))?;
+
+        let mut methods = Self::default();
+
+        // max 5 entries(0x20 len)
+        let num_entries = 5u64.min(remaining_len / 0x20);
+        for i in 0..num_entries {
+            methods.0[i as usize] = Compression::from_reader(reader)?;
+        }
+
+        Ok(methods)
+    }
+
+    pub fn as_bytes(&self, pak_version: PakVersion) -> Vec<u8> {
+        let num_entries = Self::slot_count(pak_version);
+
+        let mut buf = Vec::with_capacity(num_entries * 0x20);
+        for i in 0..num_entries {
+            buf.extend_from_slice(&self.0[i].as_bytes());
+        }
+
+        buf
+    }
+}
+
+/// Zlib compression backend
+#[derive(Debug)]
+struct ZlibBackend;
+
+impl CompressionBackend for ZlibBackend {
+    fn name(&self) -> &'static str {
+        "Zlib"
+    }
+
+    fn compress(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data)?;
+        encoder.finish()
+    }
+
+    fn decompress(&self, buf: &mut Vec<u8>, data: &[u8], _: usize) -> io::Result<()> {
+        ZlibDecoder::new(data).read_to_end(buf)?;
+        Ok(())
+    }
+}
+
+/// Gzip compression backend
+#[derive(Debug)]
+struct GzipBackend;
+
+impl CompressionBackend for GzipBackend {
+    fn name(&self) -> &'static str {
+        "Gzip"
+    }
+
+    fn compress(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data)?;
+        encoder.finish()
+    }
+
+    fn decompress(&self, buf: &mut Vec<u8>, data: &[u8], _: usize) -> io::Result<()> {
+        GzDecoder::new(data).read_to_end(buf)?;
+        Ok(())
+    }
+}
+
+/// LZ4 compression backend, uses the raw block format without size prefix
+#[derive(Debug)]
+struct Lz4Backend;
+
+impl CompressionBackend for Lz4Backend {
+    fn name(&self) -> &'static str {
+        "LZ4"
+    }
+
+    fn compress(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        Ok(lz4_flex::block::compress(data))
+    }
+
+    fn decompress(
+        &self,
+        buf: &mut Vec<u8>,
+        data: &[u8],
+        decompressed_size: usize,
+    ) -> io::Result<()> {
+        let start = buf.len();
+        buf.resize(start + decompressed_size, 0);
+        let len = lz4_flex::block::decompress_into(data, &mut buf[start..])
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        buf.truncate(start + len);
+        Ok(())
+    }
+}
+
+/// Zstd compression backend
+#[derive(Debug)]
+struct ZstdBackend;
+
+impl CompressionBackend for ZstdBackend {
+    fn name(&self) -> &'static str {
+        "Zstd"
+    }
+
+    fn compress(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        zstd::bulk::compress(data, zstd::DEFAULT_COMPRESSION_LEVEL)
+    }
+
+    fn decompress(&self, buf: &mut Vec<u8>, data: &[u8], _: usize) -> io::Result<()> {
+        zstd::stream::copy_decode(data, buf)
+    }
+}
@@ -0,0 +1,78 @@
+//! Oodle compression backend
+//! Oodle can not be distributed with this crate, instead the shared library shipped with a game
+//! (e.g. `oo2core_9_win64.dll` or `liboo2corelinux64.so.9`) is loaded at runtime.
+//! The library is shared with `unreal_asset`, so it only has to be loaded once.
+
+use std::ffi::OsStr;
+use std::io;
+
+use unreal_helpers::oodle::{self, LEVEL_NORMAL};
+
+pub use unreal_helpers::oodle::OodleCompressor;
+
+use super::CompressionBackend;
+
+/// Compression backend which uses the dynamically loaded Oodle library.
+///
+/// A backend with the default settings is always registered,
+/// it becomes available once the Oodle library is loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OodleBackend {
+    /// Compressor used when compressing data
+    pub compressor: OodleCompressor,
+    /// Compression level used when compressing data, 4 is `Normal`
+    pub level: i32,
+}
+
+impl OodleBackend {
+    /// Load the Oodle shared library from the given path.
+    ///
+    /// # Safety
+    ///
+    /// The library at the path has to be a valid Oodle library (`oo2core`), loading it executes
+    /// its initialization routines.
+    pub unsafe fn load<P: AsRef<OsStr>>(path: P) -> io::Result<Self> {
+        oodle::load(path)?;
+        Ok(OodleBackend::default())
+    }
+}
+
+impl Default for OodleBackend {
+    fn default() -> Self {
+        OodleBackend {
+            compressor: OodleCompressor::Kraken,
+            level: LEVEL_NORMAL,
+        }
+    }
+}
+
+impl CompressionBackend for OodleBackend {
+    fn name(&self) -> &'static str {
+        "Oodle"
+    }
+
+    fn is_available(&self) -> bool {
+        oodle::is_loaded()
+    }
+
+    fn compress(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        Ok(oodle::compress(data, self.compressor, self.level)?)
+    }
+
+    fn decompress(
+        &self,
+        buf: &mut Vec<u8>,
+        data: &[u8],
+        decompressed_size: usize,
+    ) -> io::Result<()> {
+        let start = buf.len();
+        buf.resize(start + decompressed_size, 0);
+
+        if let Err(e) = oodle::decompress(data, &mut buf[start..]) {
+            buf.truncate(start);
+            return Err(e.into());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+//! AES encryption support
+//!
+//! Unreal Engine encrypts pak indices and entries with AES-256 in ECB mode.
+//! Encrypted data is always padded to a multiple of the AES block size.
+
+use std::fmt;
+use std::str::FromStr;
+
+use aes::cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit};
+use aes::Aes256;
+use base64::Engine;
+
+use crate::error::PakError;
+
+/// Size of an AES block, encrypted data is always aligned to this.
+pub(crate) const AES_BLOCK_SIZE: u64 = 16;
+
+/// An AES-256 key used to decrypt or encrypt pak files.
+///
+/// The key can be constructed from raw bytes or parsed from a string, both hex
+/// (optionally prefixed with `0x`) and base64 encodings are accepted.
+#[derive(Clone)]
+pub struct AesKey(Aes256);
+
+impl AesKey {
+    /// Create a key from 32 raw bytes.
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        AesKey(Aes256::new(&GenericArray::from(bytes)))
+    }
+
+    /// Decrypt data in place, data length has to be a multiple of the AES block size.
+    pub(crate) fn decrypt(&self, data: &mut [u8]) -> Result<(), PakError> {
+        if align(data.len() as u64) != data.len() as u64 {
+            return Err(PakError::entry_invalid());
+        }
+
+        for block in data.chunks_exact_mut(AES_BLOCK_SIZE as usize) {
+            self.0.decrypt_block(GenericArray::from_mut_slice(block));
+        }
+        Ok(())
+    }
+
+    /// Pad data with zeroes to a multiple of the AES block size and encrypt it in place.
+    pub(crate) fn encrypt_padded(&self, data: &mut Vec<u8>) {
+        data.resize(align(data.len() as u64) as usize, 0);
+
+        for block in data.chunks_exact_mut(AES_BLOCK_SIZE as usize) {
+            self.0.encrypt_block(GenericArray::from_mut_slice(block));
+        }
+    }
+}
+
+impl From<[u8; 32]> for AesKey {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for AesKey {
+    type Error = PakError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| PakError::encryption_key_invalid())?;
+        Ok(Self::from_bytes(bytes))
+    }
+}
+
+impl FromStr for AesKey {
+    type Err = PakError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let hex_str = s
+            .strip_prefix("0x")
+            .or_else(|| s.strip_prefix("0X"))
+            .unwrap_or(s);
+
+        let bytes = if hex_str.len() == 64 && hex_str.bytes().all(|e| e.is_ascii_hexdigit()) {
+            hex::decode(hex_str).map_err(|_| PakError::encryption_key_invalid())?
+        } else {
+            base64::engine::general_purpose::STANDARD
+                .decode(s)
+                .map_err(|_| PakError::encryption_key_invalid())?
+        };
+
+        Self::try_from(bytes.as_slice())
+    }
+}
+
+// never print the actual key
+impl fmt::Debug for AesKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AesKey(..)")
+    }
+}
+
+/// Round a size up to the next multiple of the AES block size.
+pub(crate) fn align(size: u64) -> u64 {
+    (size + AES_BLOCK_SIZE - 1) & !(AES_BLOCK_SIZE - 1)
+}
@@ -1,9 +1,11 @@
-use std::io::{Read, Seek, SeekFrom, Write};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+
+use sha1::{Digest, Sha1};
 
 use crate::compression::CompressionMethods;
+use crate::encryption::{self, AesKey};
 use crate::error::PakError;
-use crate::hash;
-use crate::header::{Block, Header};
+use crate::header::{Block, Header, ENCRYPTED_FLAG};
 use crate::pakversion::PakVersion;
 use crate::Compression;
 
@@ -13,12 +15,16 @@ use crate::Compression;
 ///
 /// * `reader` - Anything that implements Read + Seek
 /// * `pak_version` - Version of the pak format used
+/// * `key` - AES key used to decrypt encrypted entries
 /// * `offset` - The offset of the start of the header of the file
+/// * `verify` - Name of the entry, if given the SHA-1 hash of the stored data is verified
 pub(crate) fn read_entry<R>(
     reader: &mut R,
     pak_version: PakVersion,
     compression: &CompressionMethods,
+    key: Option<&AesKey>,
     offset: u64,
+    verify: Option<&str>,
 ) -> Result<Vec<u8>, PakError>
 where
     R: Read + Seek,
@@ -27,12 +33,17 @@ where
 
     let header = Header::read(reader, pak_version, compression)?;
 
-    match header.compression_method {
-        Compression::None => {
-            let mut data = vec![0u8; header.decompressed_size as usize];
-            reader.read_exact(data.as_mut_slice())?;
-            Ok(data)
-        }
+    let key = if header.is_encrypted() {
+        Some(key.ok_or_else(PakError::encryption_key_missing)?)
+    } else {
+        None
+    };
+
+    // the hash is of the data as it is stored, so after compression and encryption
+    let mut hasher = verify.map(|_| Sha1::new());
+
+    let data = match header.compression_method {
+        Compression::None => read_data(reader, header.decompressed_size, key, hasher.as_mut())?,
         Compression::Known(_) => {
             let mut data = Vec::with_capacity(header.decompressed_size as usize);
 
@@ -40,19 +51,61 @@ where
                 .compression_blocks
                 .as_ref()
                 .ok_or_else(PakError::entry_invalid)?;
+            let block_size = match header.compression_block_size {
+                Some(block_size) if block_size > 0 => block_size as u64,
+                _ => header.decompressed_size,
+            };
             for block in compression_blocks {
                 // we do not need to seek here because the reader is at the end of the header and compression blocks are continuous
-                let mut compressed_data = vec![0u8; block.size as usize];
-                reader.read_exact(&mut compressed_data)?;
-                header
-                    .compression_method
-                    .decompress(&mut data, compressed_data.as_slice())?;
+                let compressed_data = read_data(reader, block.size, key, hasher.as_mut())?;
+                let decompressed_size =
+                    block_size.min(header.decompressed_size - data.len() as u64);
+                header.compression_method.decompress(
+                    &mut data,
+                    compressed_data.as_slice(),
+                    decompressed_size as usize,
+                )?;
             }
 
-            Ok(data)
+            data
+        }
+        _ => return Err(PakError::compression_unsupported(header.compression_method)),
+    };
+
+    if let (Some(name), Some(hasher)) = (verify, hasher) {
+        if hasher.finalize().as_slice() != header.hash {
+            return Err(PakError::entry_hash_mismatch(name.to_owned()));
         }
-        _ => Err(PakError::compression_unsupported(header.compression_method)),
     }
+
+    Ok(data)
+}
+
+/// Read data of the given size, if a key is given the data is read aligned and decrypted.
+/// The hasher is updated with the data as it is stored.
+fn read_data<R: Read>(
+    reader: &mut R,
+    size: u64,
+    key: Option<&AesKey>,
+    hasher: Option<&mut Sha1>,
+) -> Result<Vec<u8>, PakError> {
+    let stored_size = match key {
+        Some(_) => encryption::align(size),
+        None => size,
+    };
+
+    let mut data = vec![0u8; stored_size as usize];
+    reader.read_exact(&mut data)?;
+    if let Some(hasher) = hasher {
+        hasher.update(&data);
+    }
+
+    if let Some(key) = key {
+        key.decrypt(&mut data)?;
+        data.truncate(size as usize);
+    }
+
+    Ok(data)
 }
 
 /// Write an entry with Header at the position the write is at
@@ -64,6 +117,7 @@ where
 /// * `data` - Uncompressed data to be written
 /// * `compression_method` - What compression to use
 /// * `block_size` - size of the used compression blocks
+/// * `key` - AES key to encrypt the data with, `None` to write unencrypted data
 pub(crate) fn write_entry<W>(
     writer: &mut W,
     pak_version: PakVersion,
@@ -71,85 +125,431 @@ pub(crate) fn write_entry<W>(
     compress: bool,
     compression: &CompressionMethods,
     block_size: u32,
+    key: Option<&AesKey>,
 ) -> Result<Header, PakError>
 where
     W: Write + Seek,
 {
-    let offset = writer.stream_position()?;
-    let decompressed_size = data.len() as u64;
+    write_entry_from_reader(
+        writer,
+        pak_version,
+        &mut data.as_slice(),
+        Some(data.len() as u64),
+        compress,
+        compression,
+        block_size,
+        key,
+    )
+}
 
-    let compress = compress && decompressed_size >= 32;
-    let compression_method = if compress {
-        compression.0[0]
-    } else {
-        Compression::None
-    };
+/// Write an entry with Header at the position the write is at, streaming the data from a reader.
+///
+/// The data is compressed, encrypted and hashed block by block and the header is written
+/// again once the sizes and the hash are known. As the header contains the compression blocks,
+/// compressed data with an unknown size is written to a temporary file until the number of
+/// blocks is known and copied after the header from there.
+///
+/// # Arguments
+///
+/// * `writer` - Anything that implements Write + Seek
+/// * `pak_version` - Version of the pak format to be used
+/// * `reader` - Uncompressed data to be written
+/// * `size` - Size of the data if known, exactly this many bytes are read
+/// * `compression_method` - What compression to use
+/// * `block_size` - size of the used compression blocks
+/// * `key` - AES key to encrypt the data with, `None` to write unencrypted data
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn write_entry_from_reader<W, R>(
+    writer: &mut W,
+    pak_version: PakVersion,
+    reader: &mut R,
+    size: Option<u64>,
+    compress: bool,
+    compression: &CompressionMethods,
+    block_size: u32,
+    key: Option<&AesKey>,
+) -> Result<Header, PakError>
+where
+    W: Write + Seek,
+    R: Read,
+{
+    let compression_method = compression.0[0];
+    let compress = compress
+        && compression_method != Compression::None
+        && match size {
+            Some(size) => size >= MIN_COMPRESSED_SIZE,
+            None => true,
+        };
+    if !compress {
+        return write_entry_uncompressed(writer, pak_version, reader, size, compression, key);
+    }
 
-    // compress data in memory
-    let mut compressed_data = if compress {
-        Vec::with_capacity(data.len())
-    } else {
-        // this will actually never be used
-        Vec::new()
+    let Some(size) = size else {
+        return write_entry_compressed_unknown_size(
+            writer,
+            pak_version,
+            reader,
+            compression,
+            block_size,
+            key,
+        );
     };
-    let mut compression_blocks = None;
-    let data = match compression_method {
-        Compression::Known(_) => {
-            if pak_version < PakVersion::CompressionEncryption {
-                return Err(PakError::configuration_invalid());
-            }
 
-            let block_count = (data.len() as f64 / block_size as f64).ceil() as usize;
-            let mut compression_blocks_inner = Vec::with_capacity(block_count);
-            let header_len = Header::calculate_header_len(pak_version, Some(block_count as u32));
+    let offset = writer.stream_position()?;
+
+    if pak_version < PakVersion::CompressionEncryption {
+        return Err(PakError::configuration_invalid());
+    }
+    if !matches!(compression_method, Compression::Known(_)) {
+        return Err(PakError::compression_unsupported(compression_method));
+    }
+
+    let block_count = size.div_ceil(block_size as u64) as u32;
+    let header_len = Header::calculate_header_len(pak_version, Some(block_count));
+
+    // placeholder header, written again once the compressed size and the hash are known
+    let mut header = compressed_header(
+        compression_method,
+        size,
+        vec![Block { start: 0, size: 0 }; block_count as usize],
+        block_size,
+        key,
+    );
+    Header::write(writer, pak_version, compression, &header)?;
+
+    let blocks = write_compressed_blocks(
+        writer,
+        reader,
+        Some(size),
+        compression_method,
+        block_size,
+        key,
+    )?;
+
+    header.compressed_size = blocks.compressed_size;
+    header.hash = blocks.hash;
+    header.compression_blocks = Some(blocks.offset_by(header_len));
 
-            for chunk in data.chunks(block_size as usize) {
-                let begin = compressed_data.len() as u64;
+    let end = writer.stream_position()?;
+    writer.seek(SeekFrom::Start(offset))?;
+    Header::write(writer, pak_version, compression, &header)?;
+    writer.seek(SeekFrom::Start(end))?;
 
-                let block_compressed_data = compression_method.compress(chunk)?;
-                compressed_data.extend_from_slice(&block_compressed_data);
+    // the offset in the header right before the data is always 0x00, so only set here
+    header.offset = offset;
+
+    Ok(header)
+}
+
+/// Entries under this size are never compressed
+const MIN_COMPRESSED_SIZE: u64 = 32;
+
+/// Write a compressed entry of unknown size with Header at the position the write is at.
+///
+/// The compressed blocks are written to a temporary file first,
+/// as the header is only known once all blocks have been compressed.
+fn write_entry_compressed_unknown_size<W, R>(
+    writer: &mut W,
+    pak_version: PakVersion,
+    reader: &mut R,
+    compression: &CompressionMethods,
+    block_size: u32,
+    key: Option<&AesKey>,
+) -> Result<Header, PakError>
+where
+    W: Write + Seek,
+    R: Read,
+{
+    let mut start = vec![0u8; MIN_COMPRESSED_SIZE as usize];
+    let len = read_chunk(reader, &mut start)?;
+    if (len as u64) < MIN_COMPRESSED_SIZE {
+        return write_entry_uncompressed(
+            writer,
+            pak_version,
+            &mut &start[..len],
+            Some(len as u64),
+            compression,
+            key,
+        );
+    }
+
+    let compression_method = compression.0[0];
+    if pak_version < PakVersion::CompressionEncryption {
+        return Err(PakError::configuration_invalid());
+    }
+    if !matches!(compression_method, Compression::Known(_)) {
+        return Err(PakError::compression_unsupported(compression_method));
+    }
 
-                compression_blocks_inner.push(Block {
-                    start: begin + header_len,
-                    size: block_compressed_data.len() as u64,
-                });
+    let mut spill = BufWriter::new(tempfile::tempfile()?);
+    let blocks = write_compressed_blocks(
+        &mut spill,
+        &mut start.as_slice().chain(reader),
+        None,
+        compression_method,
+        block_size,
+        key,
+    )?;
+    let mut spill = spill.into_inner().map_err(|e| e.into_error())?;
+
+    let offset = writer.stream_position()?;
+    let header_len = Header::calculate_header_len(pak_version, Some(blocks.blocks.len() as u32));
+
+    let mut header = compressed_header(
+        compression_method,
+        blocks.decompressed_size,
+        Vec::new(),
+        block_size,
+        key,
+    );
+    header.compressed_size = blocks.compressed_size;
+    header.hash = blocks.hash;
+    header.compression_blocks = Some(blocks.offset_by(header_len));
+    Header::write(writer, pak_version, compression, &header)?;
+
+    spill.seek(SeekFrom::Start(0))?;
+    io::copy(&mut spill, writer)?;
+
+    header.offset = offset;
+
+    Ok(header)
+}
+
+/// Create the header of a compressed entry, the compressed size and hash are not set
+fn compressed_header(
+    compression_method: Compression,
+    size: u64,
+    compression_blocks: Vec<Block>,
+    block_size: u32,
+    key: Option<&AesKey>,
+) -> Header {
+    Header {
+        offset: 0x00,
+        compressed_size: 0,
+        decompressed_size: size,
+        compression_method,
+        hash: [0u8; 20],
+        compression_block_size: Some(match size <= block_size as u64 {
+            true => size as u32,
+            false => block_size,
+        }),
+        compression_blocks: Some(compression_blocks),
+        flags: Some(if key.is_some() { ENCRYPTED_FLAG } else { 0x00 }),
+    }
+}
+
+/// Compressed blocks written by [`write_compressed_blocks`]
+struct CompressedBlocks {
+    /// blocks with their start relative to the first block
+    blocks: Vec<Block>,
+    compressed_size: u64,
+    decompressed_size: u64,
+    /// SHA-1 hash of the blocks as they are stored
+    hash: [u8; 20],
+}
+
+impl CompressedBlocks {
+    /// Get the blocks with their start relative to the header
+    fn offset_by(self, header_len: u64) -> Vec<Block> {
+        self.blocks
+            .into_iter()
+            .map(|e| Block {
+                start: e.start + header_len,
+                size: e.size,
+            })
+            .collect()
+    }
+}
+
+/// Compress, encrypt and hash data block by block and write the blocks.
+///
+/// Exactly `size` bytes are read if a size is given, otherwise the reader is read to the end.
+fn write_compressed_blocks<W, R>(
+    writer: &mut W,
+    reader: &mut R,
+    size: Option<u64>,
+    compression_method: Compression,
+    block_size: u32,
+    key: Option<&AesKey>,
+) -> Result<CompressedBlocks, PakError>
+where
+    W: Write,
+    R: Read,
+{
+    let mut hasher = Sha1::new();
+    let mut blocks = Vec::new();
+    let mut compressed_size = 0;
+    let mut decompressed_size = 0;
+
+    let mut finished = false;
+    while !finished {
+        let mut chunks = Vec::with_capacity(BATCH_BLOCK_COUNT);
+        while !finished && chunks.len() < BATCH_BLOCK_COUNT {
+            let chunk_size = match size {
+                Some(size) => (block_size as u64).min(size - decompressed_size),
+                None => block_size as u64,
+            };
+            let mut chunk = vec![0u8; chunk_size as usize];
+            let len = read_chunk(reader, &mut chunk)?;
+            if size.is_some() && len as u64 != chunk_size {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
             }
+            decompressed_size += len as u64;
 
-            compression_blocks = Some(compression_blocks_inner);
-            &compressed_data
+            finished = match size {
+                Some(size) => decompressed_size == size,
+                None => (len as u64) < chunk_size,
+            };
+            if len > 0 {
+                chunk.truncate(len);
+                chunks.push(chunk);
+            }
         }
-        Compression::None => data,
-        _ => return Err(PakError::compression_unsupported(compression_method)),
-    };
 
-    let compression_block_size = if pak_version >= PakVersion::CompressionEncryption {
-        compression_blocks.as_ref().map(|blocks| {
-            if blocks.len() == 1 {
-                decompressed_size as u32
-            } else {
-                block_size
+        for mut block_compressed_data in compress_blocks(compression_method, &chunks)? {
+            blocks.push(Block {
+                start: compressed_size,
+                size: block_compressed_data.len() as u64,
+            });
+            // every block is padded and encrypted on its own
+            if let Some(key) = key {
+                key.encrypt_padded(&mut block_compressed_data);
             }
-        })
-    } else {
-        None
-    };
 
+            hasher.update(&block_compressed_data);
+            writer.write_all(&block_compressed_data)?;
+            compressed_size += block_compressed_data.len() as u64;
+        }
+    }
+
+    Ok(CompressedBlocks {
+        blocks,
+        compressed_size,
+        decompressed_size,
+        hash: hasher.finalize().into(),
+    })
+}
+
+/// Number of compression blocks which are read and compressed at once
+#[cfg(feature = "parallel")]
+const BATCH_BLOCK_COUNT: usize = 64;
+/// Number of compression blocks which are read and compressed at once
+#[cfg(not(feature = "parallel"))]
+const BATCH_BLOCK_COUNT: usize = 1;
+
+/// Compress blocks, on the rayon thread pool if the `parallel` feature is enabled.
+/// The blocks stay in order, so the output does not depend on the number of threads.
+fn compress_blocks(
+    compression_method: Compression,
+    blocks: &[Vec<u8>],
+) -> Result<Vec<Vec<u8>>, PakError> {
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        blocks
+            .par_iter()
+            .map(|block| compression_method.compress(block))
+            .collect()
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        blocks
+            .iter()
+            .map(|block| compression_method.compress(block))
+            .collect()
+    }
+}
+
+/// Size of the chunks uncompressed entries are streamed in, a multiple of the AES block size
+const UNCOMPRESSED_CHUNK_SIZE: usize = 0x10000;
+
+/// Write an uncompressed entry with Header at the position the write is at,
+/// streaming the data from a reader.
+fn write_entry_uncompressed<W, R>(
+    writer: &mut W,
+    pak_version: PakVersion,
+    reader: &mut R,
+    size: Option<u64>,
+    compression: &CompressionMethods,
+    key: Option<&AesKey>,
+) -> Result<Header, PakError>
+where
+    W: Write + Seek,
+    R: Read,
+{
+    if key.is_some() && pak_version < PakVersion::CompressionEncryption {
+        return Err(PakError::configuration_invalid());
+    }
+
+    let offset = writer.stream_position()?;
+
+    // placeholder header, written again once the size and the hash are known
     let mut header = Header {
         offset: 0x00,
-        compressed_size: data.len() as u64,
-        decompressed_size,
-        compression_method,
-        hash: hash(data),
-        compression_blocks,
-        compression_block_size,
-        flags: Some(0x00),
+        compressed_size: 0,
+        decompressed_size: 0,
+        compression_method: Compression::None,
+        hash: [0u8; 20],
+        compression_blocks: None,
+        // UnrealPak writes a block size of 0 for uncompressed entries
+        compression_block_size: Some(0),
+        flags: Some(if key.is_some() { ENCRYPTED_FLAG } else { 0x00 }),
     };
+    Header::write(writer, pak_version, compression, &header)?;
+
+    let mut hasher = Sha1::new();
+    let mut decompressed_size = 0;
+
+    let mut reader = reader.take(size.unwrap_or(u64::MAX));
+    let mut chunk = vec![0u8; UNCOMPRESSED_CHUNK_SIZE];
+    loop {
+        let len = read_chunk(&mut reader, &mut chunk)?;
+        if len == 0 {
+            break;
+        }
+        decompressed_size += len as u64;
+
+        // only the last chunk can be shorter and needs padding
+        let mut data = chunk[..len].to_vec();
+        if let Some(key) = key {
+            key.encrypt_padded(&mut data);
+        }
 
+        hasher.update(&data);
+        writer.write_all(&data)?;
+    }
+
+    if size.is_some_and(|size| size != decompressed_size) {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+
+    header.compressed_size = decompressed_size;
+    header.decompressed_size = decompressed_size;
+    header.hash = hasher.finalize().into();
+
+    let end = writer.stream_position()?;
+    writer.seek(SeekFrom::Start(offset))?;
     Header::write(writer, pak_version, compression, &header)?;
-    writer.write_all(data)?;
+    writer.seek(SeekFrom::Start(end))?;
 
     // the offset in the header right before the data is always 0x00, so only set here
     header.offset = offset;
 
     Ok(header)
 }
+
+/// Read into the buffer until it is full or the end of the reader is reached,
+/// returns the number of bytes read
+fn read_chunk<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(read) => len += read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(len)
+}
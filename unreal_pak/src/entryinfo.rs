@@ -0,0 +1,62 @@
+//! Metadata of pak entries
+
+use crate::compression::Compression;
+use crate::header::Header;
+
+/// Metadata of an entry in a pak file as it is stored in the index.
+///
+/// Getting an `EntryInfo` does not read or decompress any entry data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryInfo {
+    /// Name of the entry relative to the mount point
+    pub name: String,
+    /// Offset of the entry header in the pak file, the data follows right after it
+    pub offset: u64,
+    /// Size of the data as it is stored, equal to `decompressed_size` for uncompressed entries
+    pub compressed_size: u64,
+    /// Size of the data after decompressing it
+    pub decompressed_size: u64,
+    /// Compression method of the data
+    pub compression: Compression,
+    /// Number of compression blocks, 0 for uncompressed entries
+    pub compression_block_count: u32,
+    /// Decompressed size of a single compression block, 0 for uncompressed entries
+    pub compression_block_size: u32,
+    /// Is the data encrypted
+    pub encrypted: bool,
+    /// SHA-1 hash of the data as it is stored.
+    ///
+    /// Starting with [`PakVersion::PathHashIndex`](crate::pakversion::PakVersion::PathHashIndex)
+    /// most entries don't have their hash in the index, in that case this is `None` and
+    /// [`PakReader::read_entry_hash`](crate::PakReader::read_entry_hash) can be used instead.
+    pub hash: Option<[u8; 20]>,
+}
+
+impl EntryInfo {
+    pub(crate) fn new(name: String, header: &Header) -> Self {
+        EntryInfo {
+            name,
+            offset: header.offset,
+            compressed_size: header.compressed_size,
+            decompressed_size: header.decompressed_size,
+            compression: header.compression_method,
+            compression_block_count: header
+                .compression_blocks
+                .as_ref()
+                .map(|e| e.len() as u32)
+                .unwrap_or_default(),
+            compression_block_size: header.compression_block_size.unwrap_or_default(),
+            encrypted: header.is_encrypted(),
+            // encoded headers don't store the hash
+            hash: Some(header.hash).filter(|e| *e != [0u8; 20]),
+        }
+    }
+
+    /// Ratio of the compressed to the decompressed size, 1 for empty entries
+    pub fn compression_ratio(&self) -> f64 {
+        match self.decompressed_size {
+            0 => 1.0,
+            decompressed_size => self.compressed_size as f64 / decompressed_size as f64,
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Streaming reader for single pak entries
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::compression::{Compression, CompressionMethods};
+use crate::encryption::{self, AesKey};
+use crate::error::PakError;
+use crate::header::Header;
+use crate::pakversion::PakVersion;
+
+/// Size of the chunks uncompressed encrypted entries are decrypted in, a multiple of the AES block size
+const DECRYPT_CHUNK_SIZE: u64 = 0x10000;
+
+/// A block of data as it is stored in the pak file
+#[derive(Debug)]
+struct StoredBlock {
+    /// absolute offset of the block
+    offset: u64,
+    /// size of the block without the padding of encrypted blocks
+    size: u64,
+}
+
+/// A reader for a single pak entry which decompresses and decrypts the data lazily.
+///
+/// Only the compression block which is currently read from is kept in memory,
+/// seeking uses the block table of the entry to jump directly to the right block.
+/// The SHA-1 hash of the entry is not verified.
+#[derive(Debug)]
+pub struct PakEntryReader<R>
+where
+    R: Read + Seek,
+{
+    reader: R,
+    compression: Compression,
+    key: Option<AesKey>,
+    /// absolute offset of the entry data
+    data_offset: u64,
+    /// blocks which need to be decompressed or decrypted, empty if the data is read directly
+    blocks: Vec<StoredBlock>,
+    /// decompressed size of a single block
+    block_size: u64,
+    /// decompressed size of the entry
+    size: u64,
+    position: u64,
+    /// position of the underlying reader when reading directly, to avoid unnecessary seeks
+    reader_position: Option<u64>,
+    /// index and data of the currently loaded block
+    current_block: Option<(usize, Vec<u8>)>,
+}
+
+impl<R> PakEntryReader<R>
+where
+    R: Read + Seek,
+{
+    /// Open the entry whose header starts at the given offset
+    pub(crate) fn new(
+        mut reader: R,
+        pak_version: PakVersion,
+        compression: &CompressionMethods,
+        key: Option<&AesKey>,
+        offset: u64,
+    ) -> Result<Self, PakError> {
+        reader.seek(SeekFrom::Start(offset))?;
+        let header = Header::read(&mut reader, pak_version, compression)?;
+        let data_offset = reader.stream_position()?;
+
+        let key = match header.is_encrypted() {
+            true => Some(key.ok_or_else(PakError::encryption_key_missing)?.clone()),
+            false => None,
+        };
+
+        let stored_size = |size: u64| match key {
+            Some(_) => encryption::align(size),
+            None => size,
+        };
+
+        let (blocks, block_size) = match header.compression_method {
+            Compression::None if key.is_none() => (Vec::new(), header.decompressed_size),
+            Compression::None => {
+                let blocks = (0..header.decompressed_size)
+                    .step_by(DECRYPT_CHUNK_SIZE as usize)
+                    .map(|start| StoredBlock {
+                        offset: data_offset + start,
+                        size: DECRYPT_CHUNK_SIZE.min(header.decompressed_size - start),
+                    })
+                    .collect();
+                (blocks, DECRYPT_CHUNK_SIZE)
+            }
+            Compression::Known(_) => {
+                let compression_blocks = header
+                    .compression_blocks
+                    .as_ref()
+                    .ok_or_else(PakError::entry_invalid)?;
+
+                // compression blocks are continuous, so their offsets can be calculated from their sizes
+                let mut block_offset = data_offset;
+                let mut blocks = Vec::with_capacity(compression_blocks.len());
+                for block in compression_blocks {
+                    blocks.push(StoredBlock {
+                        offset: block_offset,
+                        size: block.size,
+                    });
+                    block_offset += stored_size(block.size);
+                }
+
+                let block_size = match header.compression_block_size {
+                    Some(block_size) if block_size > 0 => block_size as u64,
+                    _ => header.decompressed_size,
+                };
+                (blocks, block_size)
+            }
+            _ => return Err(PakError::compression_unsupported(header.compression_method)),
+        };
+
+        Ok(PakEntryReader {
+            reader,
+            compression: header.compression_method,
+            key,
+            data_offset,
+            blocks,
+            block_size,
+            size: header.decompressed_size,
+            position: 0,
+            reader_position: None,
+            current_block: None,
+        })
+    }
+
+    /// Decompressed size of the entry
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Consumes the `PakEntryReader`, returning the wrapped reader.
+    /// There are no guarantees for what state the reader might be in.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Load a block if it is not loaded already and return its data
+    fn load_block(&mut self, index: usize) -> Result<&[u8], PakError> {
+        if !matches!(self.current_block, Some((current, _)) if current == index) {
+            let block = self.blocks.get(index).ok_or_else(PakError::entry_invalid)?;
+
+            let mut data = vec![
+                0u8;
+                match self.key {
+                    Some(_) => encryption::align(block.size),
+                    None => block.size,
+                } as usize
+            ];
+            self.reader.seek(SeekFrom::Start(block.offset))?;
+            self.reader.read_exact(&mut data)?;
+            if let Some(key) = &self.key {
+                key.decrypt(&mut data)?;
+                data.truncate(block.size as usize);
+            }
+
+            let data = match self.compression {
+                Compression::None => data,
+                compression => {
+                    let decompressed_size = self
+                        .block_size
+                        .min(self.size.saturating_sub(index as u64 * self.block_size));
+                    let mut decompressed = Vec::with_capacity(decompressed_size as usize);
+                    compression.decompress(&mut decompressed, &data, decompressed_size as usize)?;
+                    decompressed
+                }
+            };
+
+            self.reader_position = None;
+            self.current_block = Some((index, data));
+        }
+
+        match &self.current_block {
+            Some((_, data)) => Ok(data),
+            None => Err(PakError::entry_invalid()),
+        }
+    }
+}
+
+impl<R> Read for PakEntryReader<R>
+where
+    R: Read + Seek,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.size || buf.is_empty() {
+            return Ok(0);
+        }
+        let remaining = self.size - self.position;
+
+        if self.blocks.is_empty() {
+            let position = self.data_offset + self.position;
+            if self.reader_position != Some(position) {
+                self.reader.seek(SeekFrom::Start(position))?;
+            }
+
+            let len = (buf.len() as u64).min(remaining) as usize;
+            let read = self.reader.read(&mut buf[..len])?;
+            self.position += read as u64;
+            self.reader_position = Some(position + read as u64);
+            return Ok(read);
+        }
+
+        let index = (self.position / self.block_size) as usize;
+        let block_offset = (self.position % self.block_size) as usize;
+        let data = self.load_block(index).map_err(io::Error::other)?;
+
+        let data = data
+            .get(block_offset..)
+            .filter(|e| !e.is_empty())
+            .ok_or_else(|| io::Error::other(PakError::entry_invalid()))?;
+        let len = buf.len().min(data.len());
+        buf[..len].copy_from_slice(&data[..len]);
+
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+impl<R> Seek for PakEntryReader<R>
+where
+    R: Read + Seek,
+{
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+
+        self.position = position.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.position)
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.position)
+    }
+}
@@ -39,6 +39,18 @@ impl PakError {
             kind: PakErrorKind::EncryptionUnsupported,
         }
     }
+    /// construct EncryptionKeyMissing error
+    pub fn encryption_key_missing() -> Self {
+        PakError {
+            kind: PakErrorKind::EncryptionKeyMissing,
+        }
+    }
+    /// construct EncryptionKeyInvalid error
+    pub fn encryption_key_invalid() -> Self {
+        PakError {
+            kind: PakErrorKind::EncryptionKeyInvalid,
+        }
+    }
     /// construct InvalidConfiguration error
     pub fn configuration_invalid() -> Self {
         PakError {
@@ -64,6 +76,30 @@ impl PakError {
             kind: PakErrorKind::EntryNotFound(file_name),
         }
     }
+    /// construct EntryDeleted error
+    pub fn entry_deleted(file_name: String) -> Self {
+        PakError {
+            kind: PakErrorKind::EntryDeleted(file_name),
+        }
+    }
+    /// construct EntryHashMismatch error
+    pub fn entry_hash_mismatch(file_name: String) -> Self {
+        PakError {
+            kind: PakErrorKind::EntryHashMismatch(file_name),
+        }
+    }
+    /// construct IndexHashMismatch error
+    pub fn index_hash_mismatch() -> Self {
+        PakError {
+            kind: PakErrorKind::IndexHashMismatch,
+        }
+    }
+    /// construct PathHashMismatch error
+    pub fn path_hash_mismatch(file_name: String) -> Self {
+        PakError {
+            kind: PakErrorKind::PathHashMismatch(file_name),
+        }
+    }
     /// construct InvalidFile error
     pub fn entry_invalid() -> Self {
         PakError {
@@ -82,6 +118,12 @@ impl fmt::Display for PakError {
                 format!("Unsupported compression method: {method:?}")
             }
             PakErrorKind::EncryptionUnsupported => "Encryption is not supported".to_string(),
+            PakErrorKind::EncryptionKeyMissing => {
+                "Pak file is encrypted but no encryption key was provided".to_string()
+            }
+            PakErrorKind::EncryptionKeyInvalid => {
+                "Encryption key is invalid, expected 32 bytes as hex or base64".to_string()
+            }
             PakErrorKind::ConfigurationInvalid => "Invalid configuration".to_string(),
             PakErrorKind::DoubleWrite(ref name) => {
                 format!("Attempted to write a file twice into the same PakFile, name: {name}")
@@ -91,7 +133,19 @@ impl fmt::Display for PakError {
             PakErrorKind::EntryNotFound(ref file_name) => {
                 format!("File not found: {file_name}")
             }
+            PakErrorKind::EntryDeleted(ref file_name) => {
+                format!("File is a delete record: {file_name}")
+            }
             PakErrorKind::EntryInvalid => "Invalid file".to_string(),
+            PakErrorKind::EntryHashMismatch(ref file_name) => {
+                format!("File is corrupted, SHA-1 hash does not match: {file_name}")
+            }
+            PakErrorKind::IndexHashMismatch => {
+                "Index is corrupted, SHA-1 hash does not match".to_string()
+            }
+            PakErrorKind::PathHashMismatch(ref file_name) => {
+                format!("Index is corrupted, path hash does not match: {file_name}")
+            }
 
             PakErrorKind::IoError(ref err) => {
                 format!("IO error: {err}")
@@ -132,6 +186,10 @@ pub enum PakErrorKind {
     CompressionUnsupported(Compression),
     /// encryption is not supported
     EncryptionUnsupported,
+    /// data is encrypted but no key was provided
+    EncryptionKeyMissing,
+    /// the provided encryption key could not be parsed
+    EncryptionKeyInvalid,
     /// the state of a struct is invalid
     ConfigurationInvalid,
     /// Attempted to write a file twice into the same PakFile
@@ -141,8 +199,16 @@ pub enum PakErrorKind {
     PakInvalid,
     /// a file inside the pak file was not found
     EntryNotFound(String),
+    /// a file inside the pak file is a delete record and has no data
+    EntryDeleted(String),
     /// a (compressed) file is corrupted or similar
     EntryInvalid,
+    /// the SHA-1 hash of a file inside the pak file does not match its data
+    EntryHashMismatch(String),
+    /// the SHA-1 hash of (a part of) the index does not match
+    IndexHashMismatch,
+    /// the path hash index does not match a file inside the pak file
+    PathHashMismatch(String),
 
     /// something went wrong during reading
     IoError(io::Error),
@@ -3,7 +3,7 @@
     - u64 offset (when infront of file empty)
     - u64 size
     - u64 size decompressed
-    - u32 compression method
+    - u32 compression method (u8 in version 8A)
     - 20 bytes sha1 hash
     - compression block data (only when compression method is not 0)
         - u32 number of blocks
@@ -20,9 +20,15 @@ use bitvec::prelude::*;
 use byteorder::{ReadBytesExt, WriteBytesExt, LE};
 
 use crate::compression::{Compression, CompressionMethods};
+use crate::encryption;
 use crate::error::PakError;
 use crate::pakversion::PakVersion;
 
+/// Bit in `Header::flags` marking the entry data as encrypted
+pub(crate) const ENCRYPTED_FLAG: u8 = 0x01;
+/// Bit in `Header::flags` marking the entry as a delete record
+pub(crate) const DELETED_FLAG: u8 = 0x02;
+
 #[derive(Debug)]
 pub(crate) struct Header {
     /// This may incorrectly be 0x00
@@ -46,6 +52,35 @@ pub(crate) struct Block {
 }
 
 impl Header {
+    /// Create a delete record, which removes the entry with the same name from
+    /// lower priority pak files. Delete records have no data.
+    pub(crate) fn delete_record(pak_version: PakVersion) -> Result<Self, PakError> {
+        if pak_version < PakVersion::DeleteRecords {
+            return Err(PakError::configuration_invalid());
+        }
+
+        Ok(Header {
+            offset: 0,
+            compressed_size: 0,
+            decompressed_size: 0,
+            compression_method: Compression::None,
+            hash: [0u8; 20],
+            compression_blocks: None,
+            flags: Some(DELETED_FLAG),
+            compression_block_size: Some(0),
+        })
+    }
+
+    /// Check if the data of this entry is encrypted
+    pub(crate) fn is_encrypted(&self) -> bool {
+        self.flags.unwrap_or(0) & ENCRYPTED_FLAG != 0
+    }
+
+    /// Check if this entry is a delete record
+    pub(crate) fn is_deleted(&self) -> bool {
+        self.flags.unwrap_or(0) & DELETED_FLAG != 0
+    }
+
     /// Read data from the reader into a Header, reader needs to be set at start of a header
     pub(crate) fn read<R: Read>(
         reader: &mut R,
@@ -57,9 +92,13 @@ impl Header {
         let compressed_size = reader.read_u64::<LE>()?;
         let decompressed_size = reader.read_u64::<LE>()?;
 
-        // TODO UE4.22 apparently this can be 1 byte too?
+        // UE 4.22 stores the compression method index as a single byte
+        let compression_method_num = match pak_version {
+            PakVersion::FnameBasedCompressionMethodInitial => reader.read_u8()? as u32,
+            _ => reader.read_u32::<LE>()?,
+        };
         let compression_method =
-            Compression::from_u32(reader.read_u32::<LE>()?, pak_version, compression);
+            Compression::from_u32(compression_method_num, pak_version, compression);
 
         if pak_version <= PakVersion::Initial {
             let _timestamp = reader.read_u64::<LE>()?;
@@ -120,10 +159,8 @@ impl Header {
         reader.read_exact(&mut header_bits)?;
         let header_bits = header_bits.view_bits::<Lsb0>();
 
-        // compression blocks
-        // this is actually irrelevant here because it is also included in the header before the actual data
         let mut block_size = header_bits[0..=5].load_le::<u32>();
-        let _block_count = header_bits[6..=21].load_le::<u32>();
+        let block_count = header_bits[6..=21].load_le::<u32>();
 
         if block_size == 0x3f {
             block_size = reader.read_u32::<LE>()?;
@@ -154,7 +191,31 @@ impl Header {
             read_size(29)?
         };
 
-        // compression blocks could be read here but why waste the time, they won't get used anyways
+        // compression blocks are stored right after the header in front of the data
+        let mut compression_blocks = None;
+        if block_count > 0 {
+            let mut start = Self::calculate_header_len(pak_version, Some(block_count));
+
+            // a single unencrypted block is implicit
+            if block_count == 1 && !is_encrypted {
+                compression_blocks = Some(vec![Block {
+                    start,
+                    size: compressed_size,
+                }]);
+            } else {
+                let mut blocks = Vec::with_capacity(block_count as usize);
+                for _ in 0..block_count {
+                    let size = reader.read_u32::<LE>()? as u64;
+                    blocks.push(Block { start, size });
+                    start += if is_encrypted {
+                        encryption::align(size)
+                    } else {
+                        size
+                    };
+                }
+                compression_blocks = Some(blocks);
+            }
+        }
 
         Ok(Header {
             offset,
@@ -162,12 +223,114 @@ impl Header {
             decompressed_size,
             compression_method,
             hash: [0; 20],
-            compression_blocks: None,
+            compression_blocks,
             compression_block_size: Some(block_size),
-            flags: Some(if is_encrypted { 1 } else { 0 }),
+            flags: Some(if is_encrypted { ENCRYPTED_FLAG } else { 0 }),
         })
     }
 
+    /// Check if this header can be (bit)encoded, if not it has to be stored as a full header in the index
+    pub(crate) fn is_encodable(
+        &self,
+        pak_version: PakVersion,
+        compression: &CompressionMethods,
+    ) -> bool {
+        // the encoded format has no bit for delete records
+        if self.is_deleted() {
+            return false;
+        }
+
+        if !matches!(
+            self.compression_method.as_u32(pak_version, compression),
+            Ok(0..=0x3f)
+        ) {
+            return false;
+        }
+
+        let Some(compression_blocks) = &self.compression_blocks else {
+            return true;
+        };
+        if compression_blocks.len() > 0xffff {
+            return false;
+        }
+
+        // blocks have to directly follow each other after the header
+        let mut start =
+            Self::calculate_header_len(pak_version, Some(compression_blocks.len() as u32));
+        for block in compression_blocks {
+            if block.start != start || block.size > u32::MAX as u64 {
+                return false;
+            }
+            start += if self.is_encrypted() {
+                encryption::align(block.size)
+            } else {
+                block.size
+            };
+        }
+
+        true
+    }
+
+    /// Write (bit)encoded header, only valid if [`Header::is_encodable`] returns true
+    pub(crate) fn write_encoded<W: Write>(
+        &self,
+        writer: &mut W,
+        pak_version: PakVersion,
+        compression: &CompressionMethods,
+    ) -> Result<(), PakError> {
+        let block_count = self
+            .compression_blocks
+            .as_ref()
+            .map(|blocks| blocks.len() as u32)
+            .unwrap_or(0);
+        let block_size = self.compression_block_size.unwrap_or(0);
+        let mut encoded_block_size = (block_size >> 11) & 0x3f;
+        if encoded_block_size << 11 != block_size {
+            encoded_block_size = 0x3f;
+        }
+
+        let is_compressed = !matches!(self.compression_method, Compression::None);
+
+        let mut header_bits = [0u8; 4];
+        let bits = header_bits.view_bits_mut::<Lsb0>();
+        bits[0..=5].store_le(encoded_block_size);
+        bits[6..=21].store_le(block_count);
+        bits.set(22, self.is_encrypted());
+        bits[23..=28].store_le(self.compression_method.as_u32(pak_version, compression)?);
+        bits.set(29, self.compressed_size <= u32::MAX as u64);
+        bits.set(30, self.decompressed_size <= u32::MAX as u64);
+        bits.set(31, self.offset <= u32::MAX as u64);
+        writer.write_all(&header_bits)?;
+
+        if encoded_block_size == 0x3f {
+            writer.write_u32::<LE>(block_size)?;
+        }
+
+        let mut write_size = |size: u64| -> io::Result<()> {
+            if size <= u32::MAX as u64 {
+                writer.write_u32::<LE>(size as u32)
+            } else {
+                writer.write_u64::<LE>(size)
+            }
+        };
+
+        write_size(self.offset)?;
+        write_size(self.decompressed_size)?;
+        if is_compressed {
+            write_size(self.compressed_size)?;
+        }
+
+        if let Some(compression_blocks) = &self.compression_blocks {
+            if compression_blocks.len() != 1 || self.is_encrypted() {
+                for block in compression_blocks {
+                    writer.write_u32::<LE>(block.size as u32)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Write data from a Header into the writer, writer needs to be set where the header is supposed to be written
     pub(crate) fn write<W: Write>(
         writer: &mut W,
@@ -178,7 +341,14 @@ impl Header {
         writer.write_u64::<LE>(header.offset)?;
         writer.write_u64::<LE>(header.compressed_size)?;
         writer.write_u64::<LE>(header.decompressed_size)?;
-        writer.write_u32::<LE>(header.compression_method.as_u32(pak_version, compression)?)?;
+
+        let compression_method_num = header.compression_method.as_u32(pak_version, compression)?;
+        match pak_version {
+            PakVersion::FnameBasedCompressionMethodInitial => {
+                writer.write_u8(compression_method_num as u8)?
+            }
+            _ => writer.write_u32::<LE>(compression_method_num)?,
+        }
 
         writer.write_all(&header.hash)?;
 
@@ -203,8 +373,14 @@ impl Header {
     pub(crate) fn calculate_header_len(pak_version: PakVersion, block_count: Option<u32>) -> u64 {
         let mut len = 0;
 
-        // offset, size, decomp size, comp method
-        len += 28;
+        // offset, size, decomp size
+        len += 24;
+
+        // comp method
+        len += match pak_version {
+            PakVersion::FnameBasedCompressionMethodInitial => 1,
+            _ => 4,
+        };
 
         // timestamp
         if pak_version <= PakVersion::Initial {
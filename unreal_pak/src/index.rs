@@ -1,3 +1,4 @@
+use std::collections::{BTreeMap, HashMap};
 use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 
 use byteorder::{ReadBytesExt, WriteBytesExt, BE, LE};
@@ -5,6 +6,7 @@ use byteorder::{ReadBytesExt, WriteBytesExt, BE, LE};
 use unreal_helpers::{UnrealReadExt, UnrealWriteExt};
 
 use crate::compression::CompressionMethods;
+use crate::encryption::{self, AesKey};
 use crate::error::PakError;
 use crate::header::Header;
 use crate::pakversion::PakVersion;
@@ -19,79 +21,162 @@ pub(crate) struct Index {
 }
 
 impl Index {
-    pub(crate) fn read<R: Read + Seek>(mut reader: &mut R) -> Result<Self, PakError> {
-        let footer = Footer::read(&mut reader)?;
-
-        reader.seek(SeekFrom::Start(footer.index_offset))?;
+    /// Read the index, if `verify` is set the SHA-1 hashes of all parts of the index are verified
+    pub(crate) fn read<R: Read + Seek>(
+        reader: &mut R,
+        key: Option<&AesKey>,
+        verify: bool,
+    ) -> Result<Self, PakError> {
+        let footer = Footer::read(reader)?;
+        let encrypted = footer.index_encrypted.unwrap_or_default();
+
+        let index_data = read_index_part(
+            reader,
+            footer.index_offset,
+            footer.index_size,
+            encrypted,
+            key,
+        )?;
+        if verify && hash(&index_data) != footer.index_hash {
+            return Err(PakError::index_hash_mismatch());
+        }
+        let mut index_reader = Cursor::new(index_data);
 
-        let mount_point = reader.read_fstring()?.unwrap_or_default();
+        let mount_point = index_reader.read_fstring()?.unwrap_or_default();
         let mut path_hash_seed = None;
 
-        let entry_count = reader.read_u32::<LE>()?;
+        let entry_count = index_reader.read_u32::<LE>()?;
         let mut entries = Vec::with_capacity(entry_count as usize);
 
         if footer.pak_version < PakVersion::PathHashIndex {
             for _ in 0..entry_count {
-                let file_name = reader.read_fstring()?.unwrap_or_default();
+                let file_name = index_reader.read_fstring()?.unwrap_or_default();
 
                 entries.push((
                     file_name,
-                    Header::read(reader, footer.pak_version, &footer.compression_methods)?,
+                    Header::read(
+                        &mut index_reader,
+                        footer.pak_version,
+                        &footer.compression_methods,
+                    )?,
                 ));
             }
         } else {
-            path_hash_seed = Some(reader.read_u64::<LE>()?);
+            path_hash_seed = Some(index_reader.read_u64::<LE>()?);
 
             // path hash index
-            if reader.read_u32::<LE>()? != 0 {
-                let _path_hash_index_offset = reader.read_u64::<LE>()?;
-                let _path_hash_index_size = reader.read_u64::<LE>()?;
-                // skip hash
-                reader.seek(SeekFrom::Current(20))?;
-            }
+            let mut path_hashes = None;
+            if index_reader.read_u32::<LE>()? != 0 {
+                let path_hash_index_offset = index_reader.read_u64::<LE>()?;
+                let path_hash_index_size = index_reader.read_u64::<LE>()?;
+                let mut path_hash_index_hash = [0u8; 20];
+                index_reader.read_exact(&mut path_hash_index_hash)?;
+
+                // the path hash index is not needed for reading, it is only read to verify it
+                if verify {
+                    let path_hash_index = read_index_part(
+                        reader,
+                        path_hash_index_offset,
+                        path_hash_index_size,
+                        encrypted,
+                        key,
+                    )?;
+                    if hash(&path_hash_index) != path_hash_index_hash {
+                        return Err(PakError::index_hash_mismatch());
+                    }
 
-            let full_directory_index = if reader.read_u32::<LE>()? != 0 {
-                let full_directory_index_offset = reader.read_u64::<LE>()?;
-                let _full_directory_index_size = reader.read_u64::<LE>()?;
-                // skip hash
-                reader.seek(SeekFrom::Current(20))?;
+                    let mut path_hash_reader = Cursor::new(path_hash_index);
+                    let path_hash_count = path_hash_reader.read_u32::<LE>()?;
+                    if path_hash_count != entry_count {
+                        return Err(PakError::pak_invalid());
+                    }
+                    let mut hashes = HashMap::with_capacity(path_hash_count as usize);
+                    for _ in 0..path_hash_count {
+                        let path_hash = path_hash_reader.read_u64::<LE>()?;
+                        hashes.insert(path_hash, path_hash_reader.read_i32::<LE>()?);
+                    }
+                    path_hashes = Some(hashes);
+                }
+            }
 
-                let previous_pos = reader.stream_position()?;
-                reader.seek(SeekFrom::Start(full_directory_index_offset))?;
+            let full_directory_index = if index_reader.read_u32::<LE>()? != 0 {
+                let full_directory_index_offset = index_reader.read_u64::<LE>()?;
+                let full_directory_index_size = index_reader.read_u64::<LE>()?;
+                let mut full_directory_index_hash = [0u8; 20];
+                index_reader.read_exact(&mut full_directory_index_hash)?;
+
+                let full_directory_index = read_index_part(
+                    reader,
+                    full_directory_index_offset,
+                    full_directory_index_size,
+                    encrypted,
+                    key,
+                )?;
+                if verify && hash(&full_directory_index) != full_directory_index_hash {
+                    return Err(PakError::index_hash_mismatch());
+                }
+                let mut directory_reader = Cursor::new(full_directory_index);
 
-                let directory_count = reader.read_u32::<LE>()? as usize;
+                let directory_count = directory_reader.read_u32::<LE>()? as usize;
                 let mut directories = Vec::new();
                 for _ in 0..directory_count {
-                    let directory_name = reader.read_fstring()?.unwrap_or_default();
-                    let file_count = reader.read_u32::<LE>()? as usize;
+                    let directory_name = directory_reader.read_fstring()?.unwrap_or_default();
+                    let file_count = directory_reader.read_u32::<LE>()? as usize;
                     let mut files = Vec::new();
                     for _ in 0..file_count {
-                        let file_name = reader.read_fstring()?.unwrap_or_default();
-                        files.push((file_name, reader.read_u32::<LE>()?));
+                        let file_name = directory_reader.read_fstring()?.unwrap_or_default();
+                        files.push((file_name, directory_reader.read_i32::<LE>()?));
                     }
                     directories.push((directory_name, files));
                 }
 
-                reader.seek(SeekFrom::Start(previous_pos))?;
                 directories
             } else {
                 return Err(PakError::pak_invalid());
             };
 
-            let _encoded_size = reader.read_u32::<LE>()? as usize;
-            let position = reader.stream_position()?;
+            let encoded_size = index_reader.read_u32::<LE>()? as u64;
+            let position = index_reader.stream_position()?;
+
+            // entries which could not be encoded are stored after the encoded ones
+            index_reader.seek(SeekFrom::Start(position + encoded_size))?;
+            let non_encoded_count = index_reader.read_u32::<LE>()? as usize;
+            let mut non_encoded_entries = Vec::with_capacity(non_encoded_count);
+            for _ in 0..non_encoded_count {
+                non_encoded_entries.push(Some(Header::read(
+                    &mut index_reader,
+                    footer.pak_version,
+                    &footer.compression_methods,
+                )?));
+            }
 
             for (dir_name, dir) in &full_directory_index {
-                for (file_name, encoded_offset) in dir {
+                for (file_name, location) in dir {
                     let mut path = dir_name.strip_prefix('/').unwrap_or(dir_name).to_owned();
                     path.push_str(file_name);
 
-                    reader.seek(SeekFrom::Start(position + *encoded_offset as u64))?;
-                    let entry = Header::read_encoded(
-                        &mut reader,
-                        footer.pak_version,
-                        &footer.compression_methods,
-                    )?;
+                    if let (Some(path_hashes), Some(path_hash_seed)) =
+                        (&path_hashes, path_hash_seed)
+                    {
+                        let path_hash = fnv64_path(&path, path_hash_seed, footer.pak_version);
+                        if path_hashes.get(&path_hash) != Some(location) {
+                            return Err(PakError::path_hash_mismatch(path));
+                        }
+                    }
+
+                    let entry = if *location >= 0 {
+                        index_reader.seek(SeekFrom::Start(position + *location as u64))?;
+                        Header::read_encoded(
+                            &mut index_reader,
+                            footer.pak_version,
+                            &footer.compression_methods,
+                        )?
+                    } else {
+                        non_encoded_entries
+                            .get_mut((-(*location as i64) - 1) as usize)
+                            .and_then(Option::take)
+                            .ok_or_else(PakError::pak_invalid)?
+                    };
 
                     entries.push((path, entry));
                 }
@@ -106,8 +191,16 @@ impl Index {
         })
     }
 
-    pub(crate) fn write<W: Write + Seek>(writer: &mut W, mut index: Self) -> Result<(), PakError> {
+    pub(crate) fn write<W: Write + Seek>(
+        writer: &mut W,
+        mut index: Self,
+        key: Option<&AesKey>,
+    ) -> Result<(), PakError> {
         let index_offset = writer.stream_position()?;
+        let encrypted = index.footer.index_encrypted.unwrap_or_default();
+        if encrypted && index.footer.pak_version < PakVersion::IndexEncryption {
+            return Err(PakError::configuration_invalid());
+        }
 
         let mut index_writer = Cursor::new(Vec::new());
 
@@ -115,6 +208,9 @@ impl Index {
 
         index_writer.write_u32::<LE>(index.entries.len() as u32)?;
 
+        // path hash index and full directory index which come after the main index
+        let mut secondary_indices = Vec::new();
+
         if index.footer.pak_version < PakVersion::PathHashIndex {
             for (name, header) in index.entries {
                 index_writer.write_fstring(Some(name.as_str()))?;
@@ -126,17 +222,112 @@ impl Index {
                 )?;
             }
         } else {
-            dbg!(index.path_hash_seed);
-            return Err(PakError::pak_version_unsupported(index.footer.pak_version));
+            let path_hash_seed = index.path_hash_seed.unwrap_or_else(random_path_hash_seed);
+            index_writer.write_u64::<LE>(path_hash_seed)?;
+
+            let mut encoded_entries = Cursor::new(Vec::new());
+            let mut non_encoded_entries = Cursor::new(Vec::new());
+            let mut non_encoded_count = 0;
+            let mut locations = Vec::with_capacity(index.entries.len());
+
+            for (_, header) in &index.entries {
+                if header.is_encodable(index.footer.pak_version, &index.footer.compression_methods)
+                {
+                    locations.push(encoded_entries.position() as i32);
+                    header.write_encoded(
+                        &mut encoded_entries,
+                        index.footer.pak_version,
+                        &index.footer.compression_methods,
+                    )?;
+                } else {
+                    non_encoded_count += 1;
+                    locations.push(-non_encoded_count);
+                    Header::write(
+                        &mut non_encoded_entries,
+                        index.footer.pak_version,
+                        &index.footer.compression_methods,
+                        header,
+                    )?;
+                }
+            }
+            let encoded_entries = encoded_entries.into_inner();
+            let non_encoded_entries = non_encoded_entries.into_inner();
+
+            let directories = build_directory_index(&index.entries, &locations);
+
+            let mut path_hash_index = Cursor::new(Vec::new());
+            path_hash_index.write_u32::<LE>(index.entries.len() as u32)?;
+            for ((name, _), location) in index.entries.iter().zip(&locations) {
+                path_hash_index.write_u64::<LE>(fnv64_path(
+                    name,
+                    path_hash_seed,
+                    index.footer.pak_version,
+                ))?;
+                path_hash_index.write_i32::<LE>(*location)?;
+            }
+            // pruned directory index, only keeps directories
+            path_hash_index.write_u32::<LE>(directories.len() as u32)?;
+            for directory_name in directories.keys() {
+                path_hash_index.write_fstring(Some(directory_name))?;
+                path_hash_index.write_u32::<LE>(0)?;
+            }
+
+            let mut full_directory_index = Cursor::new(Vec::new());
+            full_directory_index.write_u32::<LE>(directories.len() as u32)?;
+            for (directory_name, files) in &directories {
+                full_directory_index.write_fstring(Some(directory_name))?;
+                full_directory_index.write_u32::<LE>(files.len() as u32)?;
+                for (file_name, location) in files {
+                    full_directory_index.write_fstring(Some(file_name))?;
+                    full_directory_index.write_i32::<LE>(*location)?;
+                }
+            }
+
+            // the size of the main index does not depend on the offsets of the secondary indices
+            let main_index_size = index_writer.position()
+                + 2 * (4 + 8 + 8 + 20)
+                + 4
+                + encoded_entries.len() as u64
+                + 4
+                + non_encoded_entries.len() as u64;
+            let mut offset = index_offset
+                + if encrypted {
+                    encryption::align(main_index_size)
+                } else {
+                    main_index_size
+                };
+
+            for data in [
+                path_hash_index.into_inner(),
+                full_directory_index.into_inner(),
+            ] {
+                let (data, hash) = seal_index_part(data, encrypted, key)?;
+
+                index_writer.write_u32::<LE>(1)?;
+                index_writer.write_u64::<LE>(offset)?;
+                index_writer.write_u64::<LE>(data.len() as u64)?;
+                index_writer.write_all(&hash)?;
+
+                offset += data.len() as u64;
+                secondary_indices.push(data);
+            }
+
+            index_writer.write_u32::<LE>(encoded_entries.len() as u32)?;
+            index_writer.write_all(&encoded_entries)?;
+            index_writer.write_u32::<LE>(non_encoded_count as u32)?;
+            index_writer.write_all(&non_encoded_entries)?;
         }
 
-        let index_data = index_writer.into_inner();
+        let (index_data, index_hash) = seal_index_part(index_writer.into_inner(), encrypted, key)?;
+
         index.footer.index_offset = index_offset;
         index.footer.index_size = index_data.len() as u64;
-
-        index.footer.index_hash = hash(&index_data);
+        index.footer.index_hash = index_hash;
 
         writer.write_all(&index_data)?;
+        for data in secondary_indices {
+            writer.write_all(&data)?;
+        }
 
         Footer::write(writer, index.footer)?;
 
@@ -144,6 +335,103 @@ impl Index {
     }
 }
 
+/// Hash and optionally encrypt a part of the index.
+/// The hash is of the padded but not yet encrypted data.
+fn seal_index_part(
+    mut data: Vec<u8>,
+    encrypted: bool,
+    key: Option<&AesKey>,
+) -> Result<(Vec<u8>, [u8; 20]), PakError> {
+    if !encrypted {
+        let hash = hash(&data);
+        return Ok((data, hash));
+    }
+
+    let key = key.ok_or_else(PakError::encryption_key_missing)?;
+
+    data.resize(encryption::align(data.len() as u64) as usize, 0);
+    let hash = hash(&data);
+    key.encrypt_padded(&mut data);
+
+    Ok((data, hash))
+}
+
+/// Group entries by directory, every parent directory is also included.
+/// Directory names end with a `/`, the root directory is `/`.
+fn build_directory_index(
+    entries: &[(String, Header)],
+    locations: &[i32],
+) -> BTreeMap<String, BTreeMap<String, i32>> {
+    let mut directories = BTreeMap::new();
+    directories.insert("/".to_owned(), BTreeMap::new());
+
+    for ((name, _), location) in entries.iter().zip(locations) {
+        let (directory_name, file_name) = match name.rfind('/') {
+            Some(split) => (&name[..=split], &name[split + 1..]),
+            None => ("/", name.as_str()),
+        };
+
+        directories
+            .entry(directory_name.to_owned())
+            .or_insert_with(BTreeMap::new)
+            .insert(file_name.to_owned(), *location);
+
+        for (i, _) in directory_name.match_indices('/') {
+            directories
+                .entry(directory_name[..=i].to_owned())
+                .or_insert_with(BTreeMap::new);
+        }
+    }
+
+    directories
+}
+
+/// Hash of a path relative to the mount point as used in the path hash index.
+pub(crate) fn fnv64_path(path: &str, seed: u64, pak_version: PakVersion) -> u64 {
+    let data = path
+        .to_lowercase()
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect::<Vec<_>>();
+
+    // before the bug fix the length in characters instead of bytes of the UTF-16 string was hashed
+    let len = if pak_version >= PakVersion::Fnv64BugFix {
+        data.len()
+    } else {
+        data.len() / 2
+    };
+
+    const OFFSET: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x00000100000001b3;
+
+    let mut hash = OFFSET.wrapping_add(seed);
+    for byte in &data[..len] {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(PRIME);
+    }
+    hash
+}
+
+/// Read a part of the index into memory, decrypting it if needed
+fn read_index_part<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+    size: u64,
+    encrypted: bool,
+    key: Option<&AesKey>,
+) -> Result<Vec<u8>, PakError> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut data = vec![0u8; size as usize];
+    reader.read_exact(&mut data)?;
+
+    if encrypted {
+        key.ok_or_else(PakError::encryption_key_missing)?
+            .decrypt(&mut data)?;
+    }
+
+    Ok(data)
+}
+
 #[derive(Debug)]
 pub(crate) struct Footer {
     pub pak_version: PakVersion,
@@ -188,7 +476,7 @@ impl Footer {
             reader.seek(SeekFrom::Current(1))?;
         }
 
-        let compression_methods = if pak_version >= PakVersion::FnameBasedCompressionMethod {
+        let compression_methods = if pak_version >= PakVersion::FnameBasedCompressionMethodInitial {
             CompressionMethods::from_reader(reader)?
         } else {
             CompressionMethods::default()
@@ -251,8 +539,13 @@ impl Footer {
         }
 
         // compression methods
-        if footer.pak_version >= PakVersion::FnameBasedCompressionMethod {
-            writer.write_all(footer.compression_methods.as_bytes().as_slice())?;
+        if footer.pak_version >= PakVersion::FnameBasedCompressionMethodInitial {
+            writer.write_all(
+                footer
+                    .compression_methods
+                    .as_bytes(footer.pak_version)
+                    .as_slice(),
+            )?;
         }
 
         Ok(())
@@ -264,3 +557,32 @@ pub(crate) fn random_path_hash_seed() -> u64 {
     use rand::Rng;
     rand::thread_rng().gen::<u32>() as u64
 }
+
+#[cfg(test)]
+mod tests {
+    use super::fnv64_path;
+    use crate::pakversion::PakVersion;
+
+    // expected hashes are computed like FPakFile::HashPath
+    #[test]
+    fn fnv64_path_bug_fix() {
+        let hash = |path, seed| fnv64_path(path, seed, PakVersion::Fnv64BugFix);
+        assert_eq!(hash("root.txt", 0), 0xea9c7f6fea06f4fd);
+        assert_eq!(hash("Game/Content/Maps/Map.umap", 0), 0x906a453f214eb8d9);
+        assert_eq!(
+            hash("Engine/Config/BaseEngine.ini", 0x123456789abcdef0),
+            0xda1c301a178ded72
+        );
+    }
+
+    #[test]
+    fn fnv64_path_before_bug_fix() {
+        let hash = |path, seed| fnv64_path(path, seed, PakVersion::PathHashIndex);
+        assert_eq!(hash("root.txt", 0), 0x6dfa057e8014c52b);
+        assert_eq!(hash("Game/Content/Maps/Map.umap", 0), 0xfb45f65ae147f0f2);
+        assert_eq!(
+            hash("Engine/Config/BaseEngine.ini", 0x123456789abcdef0),
+            0x26b2c5e97166b3e9
+        );
+    }
+}
@@ -16,23 +16,37 @@ File parts:
 //!
 //! Utility crate for working with Unreal Engine .pak files.
 //! Supports both reading and writing and aims to support all pak versions.
-//! Encrytion is currently unsupported
+//! Encrypted pak files can be read when the AES key is provided, see [`AesKey`].
+//!
+//! With the `parallel` feature compression blocks are compressed on the rayon thread pool
+//! and entries can be read in parallel with `PakReader::par_iter`.
+//! The written pak files are the same with and without the feature.
 
 pub mod compression;
+pub mod encryption;
 mod entry;
+pub mod entryinfo;
+pub mod entryreader;
 pub mod error;
 mod header;
 mod index;
+pub mod pakdiff;
 pub mod pakmemory;
 pub mod pakreader;
 pub mod pakversion;
+pub mod pakvfs;
 pub mod pakwriter;
 
+pub use pakdiff::PakDiff;
 pub use pakmemory::PakMemory;
 pub use pakreader::PakReader;
+pub use pakvfs::PakVfs;
 pub use pakwriter::PakWriter;
 
 pub use compression::Compression;
+pub use encryption::AesKey;
+pub use entryinfo::EntryInfo;
+pub use entryreader::PakEntryReader;
 pub use error::PakError;
 
 pub(crate) const PAK_MAGIC: u32 = u32::from_be_bytes([0xE1, 0x12, 0x6F, 0x5A]);
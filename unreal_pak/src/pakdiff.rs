@@ -0,0 +1,167 @@
+//! Comparing the entries of two pak files
+
+use std::io::{Read, Seek, Write};
+
+use sha1::{Digest, Sha1};
+
+use crate::error::PakError;
+use crate::pakreader::PakReader;
+use crate::pakwriter::PakWriter;
+
+/// Differences between the entries of two pak files.
+///
+/// Entries are compared by their name relative to the mount point and by their content,
+/// entries with the same content but a different compression method are not modified.
+/// All lists are sorted by name.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PakDiff {
+    /// Entries which only exist in the new pak file
+    pub added: Vec<String>,
+    /// Entries which only exist in the old pak file
+    pub removed: Vec<String>,
+    /// Entries which exist in both pak files but have a different content
+    pub modified: Vec<String>,
+}
+
+impl PakDiff {
+    /// Compare the entries of two pak files, the indices of both readers have to be loaded already.
+    pub fn diff<A, B>(old: &mut PakReader<A>, new: &mut PakReader<B>) -> Result<Self, PakError>
+    where
+        A: Read + Seek,
+        B: Read + Seek,
+    {
+        let mut diff = PakDiff::default();
+
+        let old_names = old
+            .get_entry_names()
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        let new_names = new
+            .get_entry_names()
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for name in old_names {
+            match new.contains_entry(&name) {
+                true => {
+                    if !entries_equal(old, new, &name)? {
+                        diff.modified.push(name);
+                    }
+                }
+                false => diff.removed.push(name),
+            }
+        }
+        diff.added = new_names
+            .into_iter()
+            .filter(|e| !old.contains_entry(e))
+            .collect();
+
+        diff.added.sort();
+        diff.removed.sort();
+        diff.modified.sort();
+
+        Ok(diff)
+    }
+
+    /// Checks if there are no differences
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+
+    /// Returns the names of all added and modified entries, sorted by name
+    pub fn get_changed_entry_names(&self) -> Vec<&String> {
+        let mut names = self.added.iter().chain(&self.modified).collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// Write a patch pak file which contains all added and modified entries of the new pak file.
+    ///
+    /// The mount point of the writer is not changed and the writer still has to be finished after this.
+    ///
+    /// # Arguments
+    ///
+    /// * `new` - the new pak file this diff was created with
+    /// * `writer` - writer of the patch pak file
+    /// * `compress` - compress the written entries
+    /// * `delete_removed` - write delete records for removed entries, so that they are also
+    ///   removed when the patch pak file is loaded on top of the old one.
+    ///   Requires at least [`PakVersion::DeleteRecords`](crate::pakversion::PakVersion::DeleteRecords)
+    pub fn write_patch<R, W>(
+        &self,
+        new: &mut PakReader<R>,
+        writer: &mut PakWriter<W>,
+        compress: bool,
+        delete_removed: bool,
+    ) -> Result<(), PakError>
+    where
+        R: Read + Seek,
+        W: Write + Seek,
+    {
+        for name in self.get_changed_entry_names() {
+            let mut entry = new.open_entry(name)?;
+            let size = entry.size();
+            writer.write_entry_from_reader(name, &mut entry, Some(size), compress)?;
+        }
+
+        if delete_removed {
+            for name in &self.removed {
+                writer.write_delete_record(name)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Check if an entry has the same content in both pak files
+fn entries_equal<A, B>(
+    old: &mut PakReader<A>,
+    new: &mut PakReader<B>,
+    name: &String,
+) -> Result<bool, PakError>
+where
+    A: Read + Seek,
+    B: Read + Seek,
+{
+    let (Some(old_info), Some(new_info)) = (old.get_entry_info(name), new.get_entry_info(name))
+    else {
+        return Ok(false);
+    };
+
+    if old_info.decompressed_size != new_info.decompressed_size {
+        return Ok(false);
+    }
+
+    // the stored hash is of the compressed and encrypted data, so it can only be used
+    // to detect unchanged unencrypted entries with the same compression method
+    if !old_info.encrypted && !new_info.encrypted && old_info.compression == new_info.compression {
+        let old_hash = old.read_entry_hash(name)?;
+        let new_hash = new.read_entry_hash(name)?;
+        // some tools don't write hashes, those entries have to be compared by content
+        if old_hash != [0u8; 20] && new_hash != [0u8; 20] && old_hash == new_hash {
+            return Ok(true);
+        }
+    }
+
+    Ok(hash_entry(old, name)? == hash_entry(new, name)?)
+}
+
+/// Hash the decompressed content of an entry
+fn hash_entry<R: Read + Seek>(pak: &mut PakReader<R>, name: &String) -> Result<[u8; 20], PakError> {
+    let mut entry = pak.open_entry(name)?;
+
+    let mut hasher = Sha1::new();
+    let mut buf = vec![0u8; 0x10000];
+    loop {
+        let len = entry.read(&mut buf)?;
+        if len == 0 {
+            break;
+        }
+        hasher.update(&buf[..len]);
+    }
+
+    Ok(hasher.finalize().into())
+}
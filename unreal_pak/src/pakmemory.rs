@@ -1,12 +1,14 @@
 //! PakMemory data structure for more flexible pak files
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::io::{Read, Seek, Write};
 
-use crate::compression::CompressionMethods;
+use crate::compression::{Compression, CompressionMethods};
+use crate::encryption::AesKey;
 use crate::entry::{read_entry, write_entry};
 use crate::error::PakError;
-use crate::index::{random_path_hash_seed, Footer, Index};
+use crate::header::Header;
+use crate::index::{Footer, Index};
 use crate::pakversion::PakVersion;
 
 /// A Unreal Pak file which keeps all of it's data in memory.
@@ -21,7 +23,19 @@ pub struct PakMemory {
     compression: CompressionMethods,
     /// the compression block size
     pub block_size: u32,
+    /// AES key used to decrypt the pak file when loading and to encrypt it when writing
+    pub encryption_key: Option<AesKey>,
+    /// Encrypt the data of all entries when writing. Requires an encryption key.
+    pub encrypt_data: bool,
+    /// Encrypt the index when writing. Requires an encryption key.
+    pub encrypt_index: bool,
+    /// GUID of the used encryption key, all zeroes for the default key of a game
+    pub encryption_key_guid: [u8; 0x10],
+    /// Seed of the path hash index, a random seed is used when unset.
+    /// Set when loading a pak file, UnrealPak uses the CRC32 of the lower case pak file name.
+    pub path_hash_seed: Option<u64>,
     entries: BTreeMap<String, Vec<u8>>,
+    deleted_entries: BTreeSet<String>,
 }
 
 impl PakMemory {
@@ -32,26 +46,45 @@ impl PakMemory {
             mount_point: "../../../".to_owned(),
             compression: CompressionMethods::default(),
             block_size: 0x010000,
+            encryption_key: None,
+            encrypt_data: false,
+            encrypt_index: false,
+            encryption_key_guid: [0u8; 0x10],
+            path_hash_seed: None,
             entries: BTreeMap::new(),
+            deleted_entries: BTreeSet::new(),
         }
     }
 
     /// Loads the data contained in the pak file in the reader into this PakMemory
     pub fn load<R: Read + Seek>(&mut self, mut reader: &mut R) -> Result<(), PakError> {
-        let index = Index::read(reader)?;
+        let index = Index::read(reader, self.encryption_key.as_ref(), false)?;
 
         self.pak_version = index.footer.pak_version;
         self.mount_point = index.mount_point.clone();
         self.compression = index.footer.compression_methods;
+        self.encrypt_index = index.footer.index_encrypted.unwrap_or_default();
+        self.encryption_key_guid = index.footer.encryption_key_guid.unwrap_or_default();
+        self.path_hash_seed = index.path_hash_seed;
 
         for (name, header) in index.entries {
+            if header.is_deleted() {
+                self.entries.remove(&name);
+                self.deleted_entries.insert(name);
+                continue;
+            }
+
+            self.encrypt_data |= header.is_encrypted();
+            self.deleted_entries.remove(&name);
             self.entries.insert(
                 name,
                 read_entry(
                     &mut reader,
                     self.pak_version,
                     &self.compression,
+                    self.encryption_key.as_ref(),
                     header.offset,
+                    None,
                 )?,
             );
         }
@@ -66,6 +99,24 @@ impl PakMemory {
         Ok(pak_memory)
     }
 
+    /// Create a new PakMemory based on the data of the reader, decrypting it with the given key.
+    pub fn load_from_encrypted<R: Read + Seek>(
+        reader: &mut R,
+        key: AesKey,
+    ) -> Result<Self, PakError> {
+        let mut pak_memory = Self::new(PakVersion::Invalid);
+        pak_memory.encryption_key = Some(key);
+        pak_memory.load(reader)?;
+        Ok(pak_memory)
+    }
+
+    /// Set the compression method used for compressed entries.
+    /// For pak versions before [`PakVersion::FnameBasedCompressionMethodInitial`] only Zlib, Gzip and
+    /// Oodle are supported.
+    pub fn set_compression(&mut self, compression: Compression) {
+        self.compression = CompressionMethods::single(compression);
+    }
+
     /// Returns the names of all entries stored in this PakMemory.
     pub fn get_entry_names(&self) -> Vec<&String> {
         self.entries.keys().collect()
@@ -81,14 +132,47 @@ impl PakMemory {
         self.entries.get(name)
     }
 
-    /// Set the data for an entry
+    /// Set the data for an entry, this replaces a delete record with the same name
     pub fn set_entry(&mut self, name: String, data: Vec<u8>) {
+        self.deleted_entries.remove(&name);
         self.entries.insert(name, data);
     }
 
+    /// Returns the names of all delete records stored in this PakMemory.
+    pub fn get_deleted_entry_names(&self) -> Vec<&String> {
+        self.deleted_entries.iter().collect()
+    }
+
+    /// Checks if the pak file contains a delete record with the given name
+    pub fn is_deleted(&self, name: &String) -> bool {
+        self.deleted_entries.contains(name)
+    }
+
+    /// Add a delete record which removes the entry with the given name from lower priority
+    /// pak files, this replaces an entry with the same name.
+    /// Delete records require at least [`PakVersion::DeleteRecords`] when writing.
+    pub fn add_delete_record(&mut self, name: String) {
+        self.entries.remove(&name);
+        self.deleted_entries.insert(name);
+    }
+
+    /// Remove a delete record, returns whether it existed
+    pub fn remove_delete_record(&mut self, name: &String) -> bool {
+        self.deleted_entries.remove(name)
+    }
+
     /// Write all the data as a finished pak file into the provided writer.
     pub fn write<W: Write + Seek>(&self, writer: &mut W) -> Result<(), PakError> {
-        let mut written_entries = Vec::new();
+        let mut written_entries = BTreeMap::new();
+
+        let key = match self.encrypt_data {
+            true => Some(
+                self.encryption_key
+                    .as_ref()
+                    .ok_or_else(PakError::encryption_key_missing)?,
+            ),
+            false => None,
+        };
 
         for (name, data) in self.entries.iter() {
             let header = write_entry(
@@ -98,8 +182,13 @@ impl PakMemory {
                 true,
                 &self.compression,
                 self.block_size,
+                key,
             )?;
-            written_entries.push((name.clone(), header));
+            written_entries.insert(name.clone(), header);
+        }
+
+        for name in &self.deleted_entries {
+            written_entries.insert(name.clone(), Header::delete_record(self.pak_version)?);
         }
 
         let footer = Footer {
@@ -109,18 +198,18 @@ impl PakMemory {
             index_size: 0,
             index_hash: [0u8; 20],
             compression_methods: self.compression,
-            index_encrypted: Some(false),
-            encryption_key_guid: Some([0u8; 0x10]),
+            index_encrypted: Some(self.encrypt_index),
+            encryption_key_guid: Some(self.encryption_key_guid),
         };
 
         let index = Index {
             mount_point: self.mount_point.clone(),
-            path_hash_seed: Some(random_path_hash_seed()),
-            entries: written_entries,
+            path_hash_seed: self.path_hash_seed,
+            entries: written_entries.into_iter().collect(),
             footer,
         };
 
-        Index::write(writer, index)
+        Index::write(writer, index, self.encryption_key.as_ref())
     }
 
     /// Iterate over the entries in the PakMemory
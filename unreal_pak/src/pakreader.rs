@@ -1,10 +1,18 @@
 //! PakFile data structure for reading large pak files
 
 use std::collections::BTreeMap;
-use std::io::{Read, Seek};
+#[cfg(feature = "parallel")]
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
 
-use crate::compression::CompressionMethods;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::compression::{Compression, CompressionMethods};
+use crate::encryption::AesKey;
 use crate::entry::read_entry;
+use crate::entryinfo::EntryInfo;
+use crate::entryreader::PakEntryReader;
 use crate::error::PakError;
 use crate::header::Header;
 use crate::index::Index;
@@ -20,7 +28,12 @@ where
     pak_version: PakVersion,
     /// mount point (Unreal stuff)
     pub mount_point: String,
+    /// verify the SHA-1 hashes of the index when loading it and of entries when reading them
+    pub verify: bool,
     compression: CompressionMethods,
+    index_encrypted: bool,
+    encryption_key_guid: Option<[u8; 0x10]>,
+    key: Option<AesKey>,
     entries: BTreeMap<String, Header>,
     reader: R,
 }
@@ -36,19 +49,34 @@ where
         Self {
             pak_version: PakVersion::Invalid,
             mount_point: "".to_owned(),
+            verify: false,
             compression: Default::default(),
+            index_encrypted: false,
+            encryption_key_guid: None,
+            key: None,
             entries: BTreeMap::new(),
             reader,
         }
     }
 
+    /// Creates a new `PakReader` that reads from the provided reader and decrypts
+    /// the index and encrypted entries with the given key.
+    pub fn new_encrypted(reader: R, key: AesKey) -> Self {
+        Self {
+            key: Some(key),
+            ..Self::new(reader)
+        }
+    }
+
     /// Load the entry info contained in the footer into memory to start reading individual entries.
     pub fn load_index(&mut self) -> Result<(), PakError> {
-        let index = Index::read(&mut self.reader)?;
+        let index = Index::read(&mut self.reader, self.key.as_ref(), self.verify)?;
 
         self.pak_version = index.footer.pak_version;
         self.mount_point = index.mount_point.clone();
         self.compression = index.footer.compression_methods;
+        self.index_encrypted = index.footer.index_encrypted.unwrap_or_default();
+        self.encryption_key_guid = index.footer.encryption_key_guid;
 
         for (name, header) in index.entries {
             self.entries.insert(name, header);
@@ -57,44 +85,235 @@ where
         Ok(())
     }
 
-    /// Returns the names of all entries which have been found.
+    /// Returns the version of the pak file, [`PakVersion::Invalid`] before the index is loaded.
+    pub fn get_pak_version(&self) -> PakVersion {
+        self.pak_version
+    }
+
+    /// Returns the compression methods listed in the footer of the pak file. Empty for pak versions
+    /// before [`PakVersion::FnameBasedCompressionMethodInitial`] which identify compression methods by number.
+    pub fn get_compression_methods(&self) -> Vec<Compression> {
+        self.compression
+            .0
+            .into_iter()
+            .filter(|e| *e != Compression::None)
+            .collect()
+    }
+
+    /// Checks if the index of the pak file is encrypted.
+    pub fn is_index_encrypted(&self) -> bool {
+        self.index_encrypted
+    }
+
+    /// Returns the GUID of the encryption key used by the pak file, all zeroes for the
+    /// default key of a game. `None` for pak versions before [`PakVersion::EncryptionKeyGuid`].
+    pub fn get_encryption_key_guid(&self) -> Option<[u8; 0x10]> {
+        self.encryption_key_guid
+    }
+
+    /// Returns the names of all entries which have been found, excluding delete records.
     pub fn get_entry_names(&self) -> Vec<&String> {
-        self.entries.keys().collect()
+        self.entries
+            .iter()
+            .filter(|(_, header)| !header.is_deleted())
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// Returns the names of all entries which are marked as deleted.
+    /// These entries remove the entry with the same name from lower priority pak files.
+    pub fn get_deleted_entry_names(&self) -> Vec<&String> {
+        self.entries
+            .iter()
+            .filter(|(_, header)| header.is_deleted())
+            .map(|(name, _)| name)
+            .collect()
     }
 
-    /// Checks if the pak file contains an entry with the given name
+    /// Returns the metadata of all entries, excluding delete records.
+    pub fn entries(&self) -> Vec<EntryInfo> {
+        self.entries
+            .iter()
+            .filter(|(_, header)| !header.is_deleted())
+            .map(|(name, header)| EntryInfo::new(name.clone(), header))
+            .collect()
+    }
+
+    /// Returns the metadata of an entry, `None` if there is no such entry or it is a delete record.
+    pub fn get_entry_info(&self, name: &String) -> Option<EntryInfo> {
+        self.entries
+            .get(name)
+            .filter(|header| !header.is_deleted())
+            .map(|header| EntryInfo::new(name.clone(), header))
+    }
+
+    /// Reads the SHA-1 hash of an entry from the header in front of its data.
+    /// Unlike [`EntryInfo::hash`] this is available for all pak versions.
+    pub fn read_entry_hash(&mut self, name: &String) -> Result<[u8; 20], PakError> {
+        let offset = self.get_entry_offset(name)?;
+
+        self.reader.seek(SeekFrom::Start(offset))?;
+        let header = Header::read(&mut self.reader, self.pak_version, &self.compression)?;
+        Ok(header.hash)
+    }
+
+    /// Checks if the pak file contains an entry with the given name which is not a delete record
     pub fn contains_entry(&self, name: &String) -> bool {
-        self.entries.contains_key(name)
+        self.entries
+            .get(name)
+            .is_some_and(|header| !header.is_deleted())
     }
 
-    /// Reads an entry from the pak on disk into memory and returns it's data.
-    pub fn read_entry(&mut self, name: &String) -> Result<Vec<u8>, PakError> {
-        let header = self
-            .entries
+    /// Checks if the pak file contains a delete record with the given name
+    pub fn is_deleted(&self, name: &String) -> bool {
+        self.entries
             .get(name)
-            .ok_or_else(|| PakError::entry_not_found(name.clone()))?;
-        self.read_entry_at_offset(header.offset)
+            .is_some_and(|header| header.is_deleted())
     }
 
-    fn read_entry_at_offset(&mut self, offset: u64) -> Result<Vec<u8>, PakError> {
+    /// Reads an entry from the pak on disk into memory and returns it's data.
+    pub fn read_entry(&mut self, name: &String) -> Result<Vec<u8>, PakError> {
+        let offset = self.get_entry_offset(name)?;
+        let verify = self.verify.then_some(name.as_str());
         read_entry(
             &mut self.reader,
             self.pak_version,
             &self.compression,
+            self.key.as_ref(),
             offset,
+            verify,
         )
     }
 
-    /// Iterate over the entries in the PakReader
+    /// Opens an entry for streaming, the data is decompressed lazily while reading from it.
+    /// See [`PakEntryReader`] for more information.
+    pub fn open_entry(&mut self, name: &String) -> Result<PakEntryReader<&mut R>, PakError> {
+        let offset = self.get_entry_offset(name)?;
+        PakEntryReader::new(
+            &mut self.reader,
+            self.pak_version,
+            &self.compression,
+            self.key.as_ref(),
+            offset,
+        )
+    }
+
+    /// Opens an entry for streaming using another reader of the same pak file,
+    /// e.g. a second handle of the same `File`.
+    ///
+    /// Unlike [`PakReader::open_entry`] this allows multiple entries to be open at the same time,
+    /// like the `.uasset` and `.uexp` files of an asset.
+    pub fn open_entry_with<T: Read + Seek>(
+        &self,
+        name: &String,
+        reader: T,
+    ) -> Result<PakEntryReader<T>, PakError> {
+        let offset = self.get_entry_offset(name)?;
+        PakEntryReader::new(
+            reader,
+            self.pak_version,
+            &self.compression,
+            self.key.as_ref(),
+            offset,
+        )
+    }
+
+    /// Get the offset of the header of an entry which is not a delete record
+    fn get_entry_offset(&self, name: &String) -> Result<u64, PakError> {
+        let header = self
+            .entries
+            .get(name)
+            .ok_or_else(|| PakError::entry_not_found(name.clone()))?;
+        if header.is_deleted() {
+            return Err(PakError::entry_deleted(name.clone()));
+        }
+        Ok(header.offset)
+    }
+
+    /// Verifies the SHA-1 hash of an entry regardless of [`PakReader::verify`].
+    pub fn verify_entry(&mut self, name: &String) -> Result<(), PakError> {
+        let verify = std::mem::replace(&mut self.verify, true);
+        let result = self.read_entry(name).map(|_| ());
+        self.verify = verify;
+        result
+    }
+
+    /// Verifies the SHA-1 hashes of all entries, delete records are skipped.
+    ///
+    /// Returns the names of all corrupted entries together with the error that occurred
+    /// while verifying them, an empty list means that all entries are intact.
+    /// To also verify the index set [`PakReader::verify`] before loading the index.
+    pub fn verify_entries(&mut self) -> Vec<(String, PakError)> {
+        let names = self
+            .get_entry_names()
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        names
+            .into_iter()
+            .filter_map(|name| match self.verify_entry(&name) {
+                Ok(()) => None,
+                Err(err) => Some((name, err)),
+            })
+            .collect()
+    }
+
+    /// Iterate over the entries in the PakReader, delete records are skipped
     pub fn iter(&mut self) -> PakReaderIter<R> {
         PakReaderIter {
             reader: &mut self.reader,
             pak_version: self.pak_version,
             compression: self.compression,
+            key: self.key.as_ref(),
+            verify: self.verify,
             iter: self.entries.iter(),
         }
     }
 
+    /// Read all entries in parallel on the rayon thread pool, delete records are skipped.
+    ///
+    /// Every thread reads from its own reader of the pak file which is created by `open`,
+    /// e.g. by opening the file again or by cloning an in-memory reader.
+    /// The entries are in the same order as with [`PakReader::iter`].
+    #[cfg(feature = "parallel")]
+    pub fn par_iter<'a, T, F>(
+        &'a self,
+        open: F,
+    ) -> impl IndexedParallelIterator<Item = (&'a String, Result<Vec<u8>, PakError>)> + 'a
+    where
+        T: Read + Seek,
+        F: Fn() -> io::Result<T> + Send + Sync + 'a,
+    {
+        let pak_version = self.pak_version;
+        let compression = self.compression;
+        let key = self.key.as_ref();
+        let verify = self.verify;
+
+        let entries = self
+            .entries
+            .iter()
+            .filter(|(_, header)| !header.is_deleted())
+            .collect::<Vec<_>>();
+
+        entries
+            .into_par_iter()
+            .map_init(open, move |reader, (name, header)| {
+                let data = match reader {
+                    Ok(reader) => read_entry(
+                        reader,
+                        pak_version,
+                        &compression,
+                        key,
+                        header.offset,
+                        verify.then_some(name.as_str()),
+                    ),
+                    Err(err) => Err(io::Error::new(err.kind(), err.to_string()).into()),
+                };
+                (name, data)
+            })
+    }
+
     /// Consumes the `PakReader`, returning the wrapped reader.
     /// There are no guarantees for what state the reader might be in.
     pub fn into_inner(self) -> R {
@@ -110,6 +329,8 @@ where
     reader: &'a mut R,
     pak_version: PakVersion,
     compression: CompressionMethods,
+    key: Option<&'a AesKey>,
+    verify: bool,
     iter: std::collections::btree_map::Iter<'a, String, Header>,
 }
 
@@ -120,17 +341,21 @@ where
     type Item = (&'a String, Result<Vec<u8>, PakError>);
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next().map(|(name, header)| {
-            (
-                name,
-                read_entry(
-                    &mut self.reader,
-                    self.pak_version,
-                    &self.compression,
-                    header.offset,
-                ),
-            )
-        })
+        self.iter
+            .find(|(_, header)| !header.is_deleted())
+            .map(|(name, header)| {
+                (
+                    name,
+                    read_entry(
+                        &mut self.reader,
+                        self.pak_version,
+                        &self.compression,
+                        self.key,
+                        header.offset,
+                        self.verify.then_some(name.as_str()),
+                    ),
+                )
+            })
     }
 }
 
@@ -0,0 +1,265 @@
+//! PakVfs data structure for resolving files across multiple pak files
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Seek};
+
+use crate::error::PakError;
+use crate::pakreader::PakReader;
+
+/// Get the priority of a pak file the same way the engine does.
+///
+/// Patch pak files ending in `_P.pak` get a priority of `100 * (version + 1)` on top of
+/// `order`, where the version is the number before the `_P` suffix, e.g. `pakchunk0_2_P.pak`.
+/// Patch pak files without a version get a priority of `100` on top of `order`.
+/// Like in the engine the suffix is matched ignoring case, so `_p.pak` is a patch pak file too.
+///
+/// # Arguments
+///
+/// * `name` - file name of the pak file
+/// * `order` - base order of the pak file, the engine uses the directory the pak file is
+///   located in for this
+pub fn get_pak_priority(name: &str, order: u32) -> u32 {
+    let name = name.to_lowercase();
+    let Some(stem) = name.strip_suffix("_p.pak") else {
+        return order;
+    };
+
+    let chunk_version = stem
+        .rsplit_once('_')
+        .and_then(|(_, version)| version.parse::<u32>().ok())
+        .filter(|version| *version >= 1)
+        .map(|version| version + 1)
+        .unwrap_or(1);
+
+    order + 100 * chunk_version
+}
+
+/// Normalize a path, leading `../` components are removed so that paths with and
+/// without the typical `../../../` mount point resolve to the same file
+fn normalize_path(path: &str) -> String {
+    path.split(['/', '\\'])
+        .filter(|e| !e.is_empty() && *e != "." && *e != "..")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// A file in a [`MountedPak`]
+#[derive(Debug)]
+struct MountedFile {
+    /// normalized path including the mount point
+    path: String,
+    /// entry name relative to the mount point
+    entry_name: String,
+    /// is this file a delete record
+    deleted: bool,
+}
+
+/// A pak file mounted in a [`PakVfs`]
+#[derive(Debug)]
+pub struct MountedPak<R>
+where
+    R: Read + Seek,
+{
+    /// File name of the pak file
+    pub name: String,
+    /// Priority of the pak file, see [`get_pak_priority`]
+    pub priority: u32,
+    /// Reader of the pak file
+    pub reader: PakReader<R>,
+    /// files by their lowercase normalized path
+    files: HashMap<String, MountedFile>,
+}
+
+/// A virtual filesystem which overlays multiple pak files.
+///
+/// Files are resolved like the engine does it, pak files with a higher priority override
+/// pak files with a lower priority and pak files with the same priority are searched in
+/// descending alphabetical order of their names. Delete records hide files from pak files
+/// with a lower priority. Paths are case insensitive and include the mount point of the pak
+/// file without leading `../` components, e.g. `Game/Content/Maps/Map.umap`.
+#[derive(Debug)]
+pub struct PakVfs<R>
+where
+    R: Read + Seek,
+{
+    /// mounted paks ordered from the highest to the lowest priority
+    paks: Vec<MountedPak<R>>,
+}
+
+impl<R> Default for PakVfs<R>
+where
+    R: Read + Seek,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R> PakVfs<R>
+where
+    R: Read + Seek,
+{
+    /// Creates a new empty `PakVfs`.
+    pub fn new() -> Self {
+        Self { paks: Vec::new() }
+    }
+
+    /// Mount a pak file with a base order of 0, see [`PakVfs::mount_with_order`].
+    pub fn mount(&mut self, name: String, reader: PakReader<R>) {
+        self.mount_with_order(name, 0, reader);
+    }
+
+    /// Mount a pak file, the index of the reader has to be loaded already.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - file name of the pak file, e.g. `pakchunk0-WindowsNoEditor.pak`
+    /// * `order` - base order of the pak file, see [`get_pak_priority`]
+    /// * `reader` - reader of the pak file
+    pub fn mount_with_order(&mut self, name: String, order: u32, reader: PakReader<R>) {
+        let priority = get_pak_priority(&name, order);
+
+        let mut files = HashMap::new();
+        let entries = reader
+            .get_entry_names()
+            .into_iter()
+            .map(|e| (e, false))
+            .chain(
+                reader
+                    .get_deleted_entry_names()
+                    .into_iter()
+                    .map(|e| (e, true)),
+            );
+        for (entry_name, deleted) in entries {
+            let path = normalize_path(&format!("{}/{}", reader.mount_point, entry_name));
+            files.insert(
+                path.to_ascii_lowercase(),
+                MountedFile {
+                    path,
+                    entry_name: entry_name.clone(),
+                    deleted,
+                },
+            );
+        }
+
+        let index = self
+            .paks
+            .partition_point(|e| (e.priority, &e.name) > (priority, &name));
+        self.paks.insert(
+            index,
+            MountedPak {
+                name,
+                priority,
+                reader,
+                files,
+            },
+        );
+    }
+
+    /// Unmount a pak file, returns the pak file if it was mounted.
+    pub fn unmount(&mut self, name: &str) -> Option<MountedPak<R>> {
+        let index = self.paks.iter().position(|e| e.name == name)?;
+        Some(self.paks.remove(index))
+    }
+
+    /// Consumes the `PakVfs`, returning the readers of the mounted pak files ordered from the
+    /// highest to the lowest priority.
+    pub fn into_readers(self) -> Vec<PakReader<R>> {
+        self.paks.into_iter().map(|e| e.reader).collect()
+    }
+
+    /// Returns all mounted pak files ordered from the highest to the lowest priority.
+    pub fn get_paks(&self) -> &[MountedPak<R>] {
+        &self.paks
+    }
+
+    /// Get a mounted pak file by its name.
+    pub fn get_pak_mut(&mut self, name: &str) -> Option<&mut MountedPak<R>> {
+        self.paks.iter_mut().find(|e| e.name == name)
+    }
+
+    /// Resolve a path to the index of the pak file it is read from and the file in that pak
+    fn resolve(&self, path: &str) -> Option<(usize, &MountedFile)> {
+        let key = normalize_path(path).to_ascii_lowercase();
+
+        let mut deleted_priority = None;
+        for (index, pak) in self.paks.iter().enumerate() {
+            // a delete record only hides files from lower priority paks
+            if deleted_priority.is_some_and(|priority| priority > pak.priority) {
+                return None;
+            }
+
+            match pak.files.get(&key) {
+                Some(file) if file.deleted => deleted_priority = Some(pak.priority),
+                Some(file) => return Some((index, file)),
+                None => {}
+            }
+        }
+
+        None
+    }
+
+    /// Returns the pak file which the file at the given path is read from.
+    pub fn find(&self, path: &str) -> Option<&MountedPak<R>> {
+        self.resolve(path).map(|(index, _)| &self.paks[index])
+    }
+
+    /// Checks if a file exists at the given path.
+    pub fn contains(&self, path: &str) -> bool {
+        self.resolve(path).is_some()
+    }
+
+    /// Reads the file at the given path from the pak file with the highest priority.
+    pub fn read(&mut self, path: &str) -> Result<Vec<u8>, PakError> {
+        let (index, file) = self
+            .resolve(path)
+            .ok_or_else(|| PakError::entry_not_found(path.to_string()))?;
+        let entry_name = file.entry_name.clone();
+
+        self.paks[index].reader.read_entry(&entry_name)
+    }
+
+    /// Returns the paths of all files in the virtual filesystem, sorted case insensitively.
+    pub fn get_file_names(&self) -> Vec<&str> {
+        let mut files = BTreeMap::new();
+        for pak in &self.paks {
+            for key in pak.files.keys() {
+                if files.contains_key(key.as_str()) {
+                    continue;
+                }
+                files.insert(
+                    key.as_str(),
+                    self.resolve(key).map(|(_, e)| e.path.as_str()),
+                );
+            }
+        }
+
+        files.into_values().flatten().collect()
+    }
+
+    /// Lists the contents of a directory.
+    ///
+    /// Returns the names of all files and directories inside of the directory,
+    /// directory names end with a `/`.
+    pub fn list_directory(&self, path: &str) -> Vec<String> {
+        let mut prefix = normalize_path(path).to_ascii_lowercase();
+        if !prefix.is_empty() {
+            prefix.push('/');
+        }
+
+        let mut children = BTreeMap::new();
+        for file in self.get_file_names() {
+            if !file.to_ascii_lowercase().starts_with(&prefix) {
+                continue;
+            }
+
+            let child = match file[prefix.len()..].split_once('/') {
+                Some((directory, _)) => format!("{directory}/"),
+                None => file[prefix.len()..].to_string(),
+            };
+            children.entry(child.to_ascii_lowercase()).or_insert(child);
+        }
+
+        children.into_values().collect()
+    }
+}
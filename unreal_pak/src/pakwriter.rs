@@ -1,13 +1,14 @@
 //! PakFile data structure for writing large pak files
 
 use std::collections::BTreeMap;
-use std::io::{Seek, Write};
+use std::io::{Read, Seek, Write};
 
-use crate::compression::CompressionMethods;
-use crate::entry::write_entry;
+use crate::compression::{Compression, CompressionMethods};
+use crate::encryption::AesKey;
+use crate::entry::write_entry_from_reader;
 use crate::error::PakError;
 use crate::header::Header;
-use crate::index::{random_path_hash_seed, Footer, Index};
+use crate::index::{Footer, Index};
 use crate::pakversion::PakVersion;
 
 /// An Unreal pak file writer which allows incrementally writing data.
@@ -28,6 +29,16 @@ where
     compression: CompressionMethods,
     /// Compression block size
     pub block_size: u32,
+    /// Encrypt the data of entries written from now on. Requires an encryption key.
+    pub encrypt_data: bool,
+    /// Encrypt the index. Requires an encryption key.
+    pub encrypt_index: bool,
+    /// GUID of the used encryption key, all zeroes for the default key of a game
+    pub encryption_key_guid: [u8; 0x10],
+    /// Seed of the path hash index, a random seed is used when unset.
+    /// UnrealPak uses the CRC32 of the lower case pak file name.
+    pub path_hash_seed: Option<u64>,
+    key: Option<AesKey>,
     entries: BTreeMap<String, Header>,
     writer: W,
 }
@@ -45,11 +56,40 @@ where
             mount_point: "../../../".to_owned(),
             compression: CompressionMethods::zlib(),
             block_size: 0x010000,
+            encrypt_data: false,
+            encrypt_index: false,
+            encryption_key_guid: [0u8; 0x10],
+            path_hash_seed: None,
+            key: None,
             entries: BTreeMap::new(),
             writer,
         }
     }
 
+    /// Creates a new `PakWriter` which encrypts both the entry data and the index with the given key.
+    /// What gets encrypted can be changed using `encrypt_data` and `encrypt_index`.
+    pub fn new_encrypted(
+        writer: W,
+        pak_version: PakVersion,
+        key: AesKey,
+        encryption_key_guid: [u8; 0x10],
+    ) -> Self {
+        Self {
+            encrypt_data: true,
+            encrypt_index: true,
+            encryption_key_guid,
+            key: Some(key),
+            ..Self::new(writer, pak_version)
+        }
+    }
+
+    /// Set the compression method used for compressed entries.
+    /// For pak versions before [`PakVersion::FnameBasedCompressionMethodInitial`] only Zlib, Gzip and
+    /// Oodle are supported.
+    pub fn set_compression(&mut self, compression: Compression) {
+        self.compression = CompressionMethods::single(compression);
+    }
+
     /// Returns the names of all entries which have been found.
     pub fn get_entry_names(&self) -> Vec<&String> {
         self.entries.keys().collect()
@@ -63,24 +103,74 @@ where
         name: &String,
         data: &Vec<u8>,
         compress: bool,
+    ) -> Result<(), PakError> {
+        self.write_entry_from_reader(
+            name,
+            &mut data.as_slice(),
+            Some(data.len() as u64),
+            compress,
+        )
+    }
+
+    /// Writes an entry into the pak file on disk, streaming its data from a reader.
+    /// The data is compressed, encrypted and hashed block by block, so only a single block is kept
+    /// in memory. When compressing data with an unknown `size` the compressed blocks are written
+    /// to a temporary file first, as the entry header contains them.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - name of the entry
+    /// * `reader` - reader of the data
+    /// * `size` - size of the data if known, exactly this many bytes are read from the reader
+    /// * `compress` - compress the data, entries under 32 bytes are never compressed
+    pub fn write_entry_from_reader<R: Read>(
+        &mut self,
+        name: &String,
+        reader: &mut R,
+        size: Option<u64>,
+        compress: bool,
     ) -> Result<(), PakError> {
         if self.entries.contains_key(name) {
             return Err(PakError::double_write(name.clone()));
         }
 
-        let header = write_entry(
+        let key = match self.encrypt_data {
+            true => Some(
+                self.key
+                    .as_ref()
+                    .ok_or_else(PakError::encryption_key_missing)?,
+            ),
+            false => None,
+        };
+
+        let header = write_entry_from_reader(
             &mut self.writer,
             self.pak_version,
-            data,
+            reader,
+            size,
             compress,
             &self.compression,
             self.block_size,
+            key,
         )?;
         self.entries.insert(name.clone(), header);
 
         Ok(())
     }
 
+    /// Writes a delete record which removes the entry with the given name from lower
+    /// priority pak files. Requires at least [`PakVersion::DeleteRecords`].
+    pub fn write_delete_record(&mut self, name: &String) -> Result<(), PakError> {
+        if self.entries.contains_key(name) {
+            return Err(PakError::double_write(name.clone()));
+        }
+
+        self.entries
+            .insert(name.clone(), Header::delete_record(self.pak_version)?);
+
+        Ok(())
+    }
+
     /// Finish writing the pak file by writing index and footer
     pub fn finish_write(mut self) -> Result<(), PakError> {
         let footer = Footer {
@@ -90,17 +180,17 @@ where
             index_size: 0,
             index_hash: [0u8; 20],
             compression_methods: self.compression,
-            index_encrypted: Some(false),
-            encryption_key_guid: Some([0u8; 0x10]),
+            index_encrypted: Some(self.encrypt_index),
+            encryption_key_guid: Some(self.encryption_key_guid),
         };
 
         let index = Index {
             mount_point: self.mount_point,
-            path_hash_seed: Some(random_path_hash_seed()),
+            path_hash_seed: self.path_hash_seed,
             entries: self.entries.into_iter().collect::<Vec<_>>(),
             footer,
         };
 
-        Index::write(&mut self.writer, index)
+        Index::write(&mut self.writer, index, self.key.as_ref())
     }
 }
@@ -0,0 +1,114 @@
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, Write};
+use std::str::FromStr;
+
+use unreal_pak::{pakversion::PakVersion, AesKey, Compression, PakReader, PakWriter};
+
+#[allow(dead_code)]
+pub(crate) const KEY_HEX: &str =
+    "0x7E0A2A6E8C1E6F4C3B11A0B9D5F0E2C4A8F6D3B1E9C7A5B3D1F0E8C6A4B2D0F1";
+
+/// Pak v8 files written by UnrealPak, the cus ones have their index in a different order than the data
+#[allow(dead_code)]
+pub(crate) const TESTFILES: [&str; 4] = [
+    "testfiles/000-TestPak-cus-C_P.pak",
+    "testfiles/000-TestPak-cus-NoC_P.pak",
+    "testfiles/000-TestPak-off-C_P.pak",
+    "testfiles/000-TestPak-off-NoC_P.pak",
+];
+
+#[allow(dead_code)]
+pub(crate) fn test_key() -> AesKey {
+    AesKey::from_str(KEY_HEX).unwrap()
+}
+
+#[allow(dead_code)]
+pub(crate) fn test_entries() -> Vec<(String, Vec<u8>)> {
+    // sorted by name, like the entries of a pak
+    vec![
+        (
+            "Game/Content/Sub/random.bin".to_owned(),
+            (0..70_001u32)
+                .map(|e| (e.wrapping_mul(2654435761) >> 13) as u8)
+                .collect(),
+        ),
+        (
+            "Game/Content/compressible.bin".to_owned(),
+            b"compress me ".repeat(25_000),
+        ),
+        // exactly two compression blocks
+        ("Game/blocks.bin".to_owned(), vec![0x5A; 0x20000]),
+        ("Game/small.txt".to_owned(), b"small".to_vec()),
+        ("Game/stored.bin".to_owned(), b"0123456789abcdef".repeat(8)),
+        ("empty.txt".to_owned(), Vec::new()),
+    ]
+}
+
+/// Create a writer which encrypts the data and index if a key is given.
+/// The path hash seed is fixed, so the output is the same for every run.
+#[allow(dead_code)]
+pub(crate) fn pak_writer<W: Write + Seek>(
+    writer: W,
+    pak_version: PakVersion,
+    key: Option<AesKey>,
+) -> PakWriter<W> {
+    let mut pak = match key {
+        Some(key) => PakWriter::new_encrypted(writer, pak_version, key, [0u8; 0x10]),
+        None => PakWriter::new(writer, pak_version),
+    };
+    pak.path_hash_seed = Some(0x1234_5678);
+    pak
+}
+
+/// Write [`test_entries`], `Game/stored.bin` is written uncompressed
+#[allow(dead_code)]
+pub(crate) fn write_test_entries<W: Write + Seek>(pak: &mut PakWriter<W>) {
+    for (name, data) in test_entries() {
+        pak.write_entry(&name, &data, name != "Game/stored.bin")
+            .unwrap();
+    }
+}
+
+#[allow(dead_code)]
+pub(crate) fn write_pak(pak_version: PakVersion, key: Option<AesKey>) -> Vec<u8> {
+    let mut output = Cursor::new(Vec::new());
+
+    let mut pak = pak_writer(&mut output, pak_version, key);
+    write_test_entries(&mut pak);
+    pak.finish_write().unwrap();
+
+    output.into_inner()
+}
+
+#[allow(dead_code)]
+pub(crate) fn open_pak<R: Read + Seek>(reader: R, key: Option<AesKey>) -> PakReader<R> {
+    let mut pak = match key {
+        Some(key) => PakReader::new_encrypted(reader, key),
+        None => PakReader::new(reader),
+    };
+    pak.load_index().unwrap();
+    pak
+}
+
+#[allow(dead_code)]
+pub(crate) fn open_testfile(path: &str) -> PakReader<File> {
+    open_pak(File::open(path).unwrap(), None)
+}
+
+/// Read the entries of a testfile, sorted by name
+#[allow(dead_code)]
+pub(crate) fn testfile_entries(path: &str) -> Vec<(String, Vec<u8>)> {
+    open_testfile(path)
+        .iter()
+        .map(|(name, data)| (name.clone(), data.unwrap()))
+        .collect()
+}
+
+/// The compression used by a testfile, [`Compression::None`] if it has no compression methods
+#[allow(dead_code)]
+pub(crate) fn testfile_compression(pak: &PakReader<File>) -> Compression {
+    pak.get_compression_methods()
+        .first()
+        .copied()
+        .unwrap_or(Compression::None)
+}
@@ -0,0 +1,114 @@
+use std::io::{self, Cursor};
+
+use unreal_pak::compression::{register_backend, CompressionBackend, OodleBackend};
+use unreal_pak::{pakversion::PakVersion, Compression, PakWriter};
+
+mod common;
+use common::{open_pak, pak_writer, test_entries, testfile_entries, TESTFILES};
+
+/// The test entries and the entries of an UnrealPak testfile, which are compressed again
+fn entries() -> Vec<(String, Vec<u8>)> {
+    let mut entries = test_entries();
+    entries.extend(testfile_entries(TESTFILES[2]));
+    entries
+}
+
+fn round_trip(pak_version: PakVersion, compression: Compression) {
+    let mut output = Cursor::new(Vec::new());
+
+    let mut pak = pak_writer(&mut output, pak_version, None);
+    pak.set_compression(compression);
+    for (name, data) in entries() {
+        pak.write_entry(&name, &data, true).unwrap();
+    }
+    pak.finish_write().unwrap();
+
+    let mut pak = open_pak(Cursor::new(output.get_ref()), None);
+    for (name, data) in entries() {
+        assert_eq!(
+            pak.read_entry(&name).unwrap(),
+            data,
+            "{compression:?} {name}"
+        );
+    }
+}
+
+#[test]
+fn builtin_backends() {
+    for compression in [
+        Compression::zlib(),
+        Compression::gzip(),
+        Compression::lz4(),
+        Compression::zstd(),
+    ] {
+        assert!(compression.is_supported());
+        round_trip(PakVersion::FnameBasedCompressionMethod, compression);
+        round_trip(PakVersion::Fnv64BugFix, compression);
+    }
+
+    // older versions identify compression methods by number
+    round_trip(PakVersion::RelativeChunkOffsets, Compression::gzip());
+}
+
+#[test]
+fn unsupported_backend() {
+    let mut pak = PakWriter::new(Cursor::new(Vec::new()), PakVersion::Fnv64BugFix);
+    pak.set_compression(Compression::from_name("Unsupported"));
+    assert!(pak
+        .write_entry(&"a".to_owned(), &vec![0u8; 1000], true)
+        .is_err());
+
+    // zstd can't be represented in older pak versions
+    let mut pak = PakWriter::new(Cursor::new(Vec::new()), PakVersion::RelativeChunkOffsets);
+    pak.set_compression(Compression::zstd());
+    assert!(pak
+        .write_entry(&"a".to_owned(), &vec![0u8; 1000], true)
+        .is_err());
+}
+
+// no Oodle library is loaded in the tests
+#[test]
+fn oodle_not_loaded() {
+    let compression = Compression::oodle();
+    assert_eq!(Compression::from_name("Oodle"), compression);
+    assert!(!compression.is_supported());
+
+    let mut pak = PakWriter::new(Cursor::new(Vec::new()), PakVersion::Fnv64BugFix);
+    pak.set_compression(compression);
+    assert!(pak
+        .write_entry(&"a".to_owned(), &vec![0u8; 1000], true)
+        .is_err());
+
+    assert!(unsafe { OodleBackend::load("/nonexistent/liboo2corelinux64.so.9") }.is_err());
+    assert!(!compression.is_supported());
+}
+
+#[derive(Debug)]
+struct XorBackend;
+
+impl CompressionBackend for XorBackend {
+    fn name(&self) -> &'static str {
+        "Xor"
+    }
+
+    fn compress(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        Ok(data.iter().map(|e| e ^ 0x5a).collect())
+    }
+
+    fn decompress(&self, buf: &mut Vec<u8>, data: &[u8], _: usize) -> io::Result<()> {
+        buf.extend(data.iter().map(|e| e ^ 0x5a));
+        Ok(())
+    }
+}
+
+#[test]
+fn custom_backend() {
+    assert!(!Compression::from_name("Xor").is_supported());
+
+    register_backend(XorBackend);
+    let compression = Compression::from_name("Xor");
+    assert_eq!(compression, Compression::Known("Xor"));
+    assert!(compression.is_supported());
+
+    round_trip(PakVersion::Fnv64BugFix, compression);
+}
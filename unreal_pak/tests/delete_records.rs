@@ -0,0 +1,111 @@
+use std::fs::File;
+use std::io::Cursor;
+
+use unreal_pak::{error::PakErrorKind, pakversion::PakVersion, PakMemory, PakWriter};
+
+mod common;
+use common::{open_pak, pak_writer, test_entries, testfile_entries, TESTFILES};
+
+const PAK_VERSIONS: [PakVersion; 5] = [
+    PakVersion::DeleteRecords,
+    PakVersion::EncryptionKeyGuid,
+    PakVersion::FnameBasedCompressionMethod,
+    PakVersion::PathHashIndex,
+    PakVersion::Fnv64BugFix,
+];
+
+fn deleted_entry() -> String {
+    "Game/Content/Removed.uasset".to_owned()
+}
+
+#[test]
+fn delete_record_round_trip() {
+    for pak_version in PAK_VERSIONS {
+        let mut output = Cursor::new(Vec::new());
+
+        let mut pak = pak_writer(&mut output, pak_version, None);
+        for (name, data) in test_entries() {
+            pak.write_entry(&name, &data, true).unwrap();
+        }
+        pak.write_delete_record(&deleted_entry()).unwrap();
+        assert!(pak.write_delete_record(&deleted_entry()).is_err());
+        pak.finish_write().unwrap();
+
+        let mut pak = open_pak(Cursor::new(output.get_ref()), None);
+
+        let names = test_entries()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        assert_eq!(pak.get_entry_names(), names.iter().collect::<Vec<_>>());
+        assert_eq!(pak.get_deleted_entry_names(), vec![&deleted_entry()]);
+        assert!(pak.is_deleted(&deleted_entry()));
+        assert!(!pak.contains_entry(&deleted_entry()));
+
+        let error = pak.read_entry(&deleted_entry()).unwrap_err();
+        assert!(matches!(error.kind, PakErrorKind::EntryDeleted(_)));
+
+        for (name, data) in test_entries() {
+            assert_eq!(pak.read_entry(&name).unwrap(), data, "{name}");
+        }
+        assert_eq!(pak.iter().count(), test_entries().len());
+    }
+}
+
+#[test]
+fn delete_record_pak_memory() {
+    for pak_version in PAK_VERSIONS {
+        let mut pak = PakMemory::new(pak_version);
+        for (name, data) in test_entries() {
+            pak.set_entry(name, data);
+        }
+        pak.set_entry(deleted_entry(), b"replaced".to_vec());
+        pak.add_delete_record(deleted_entry());
+        assert_eq!(pak.get_entry(&deleted_entry()), None);
+
+        let mut output = Cursor::new(Vec::new());
+        pak.write(&mut output).unwrap();
+
+        output.set_position(0);
+        let mut pak = PakMemory::load_from(&mut output).unwrap();
+        assert_eq!(pak.get_deleted_entry_names(), vec![&deleted_entry()]);
+        assert_eq!(pak.get_entry_names().len(), test_entries().len());
+        for (name, data) in test_entries() {
+            assert_eq!(pak.get_entry(&name), Some(&data), "{name}");
+        }
+
+        pak.set_entry(deleted_entry(), b"restored".to_vec());
+        assert!(!pak.is_deleted(&deleted_entry()));
+    }
+}
+
+#[test]
+fn delete_record_unsupported_version() {
+    let mut output = Cursor::new(Vec::new());
+    let mut pak = PakWriter::new(&mut output, PakVersion::RelativeChunkOffsets);
+    assert!(pak.write_delete_record(&deleted_entry()).is_err());
+
+    let mut pak = PakMemory::new(PakVersion::RelativeChunkOffsets);
+    pak.add_delete_record(deleted_entry());
+    assert!(pak.write(&mut Cursor::new(Vec::new())).is_err());
+}
+
+#[test]
+fn delete_record_testfiles() {
+    for path in TESTFILES {
+        let mut pak = PakMemory::load_from(&mut File::open(path).unwrap()).unwrap();
+        let entries = testfile_entries(path);
+        let (deleted, _) = &entries[0];
+        pak.add_delete_record(deleted.clone());
+
+        let mut output = Cursor::new(Vec::new());
+        pak.write(&mut output).unwrap();
+
+        let mut pak = open_pak(Cursor::new(output.get_ref()), None);
+        assert_eq!(pak.get_deleted_entry_names(), vec![deleted]);
+        assert_eq!(pak.get_entry_names().len(), entries.len() - 1);
+        for (name, data) in &entries[1..] {
+            assert_eq!(&pak.read_entry(name).unwrap(), data, "{path} {name}");
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::io::Cursor;
+use std::str::FromStr;
+
+use unreal_pak::{pakversion::PakVersion, AesKey, PakMemory, PakReader, PakWriter};
+
+mod common;
+use common::{
+    open_pak, open_testfile, pak_writer, test_entries, test_key, testfile_compression,
+    testfile_entries, TESTFILES,
+};
+
+const KEY_BASE64: &str = "fgoqboweb0w7EaC51fDixKj207Hpx6Wz0fDoxqSy0PE=";
+
+fn write_encrypted(pak_version: PakVersion, compress: bool) -> Vec<u8> {
+    let key = test_key();
+    let mut output = Cursor::new(Vec::new());
+
+    let mut pak = PakWriter::new_encrypted(&mut output, pak_version, key, [0x11; 0x10]);
+    for (name, data) in test_entries() {
+        pak.write_entry(&name, &data, compress).unwrap();
+    }
+    pak.finish_write().unwrap();
+
+    output.into_inner()
+}
+
+fn check_entries<R: std::io::Read + std::io::Seek>(pak: &mut PakReader<R>) {
+    pak.load_index().unwrap();
+    for (name, data) in test_entries() {
+        assert_eq!(pak.read_entry(&name).unwrap(), data, "{name}");
+    }
+}
+
+#[test]
+fn encrypted_round_trip() {
+    for pak_version in [
+        PakVersion::EncryptionKeyGuid,
+        PakVersion::FnameBasedCompressionMethod,
+        PakVersion::FrozenIndex,
+        PakVersion::PathHashIndex,
+        PakVersion::Fnv64BugFix,
+    ] {
+        for compress in [false, true] {
+            let data = write_encrypted(pak_version, compress);
+
+            let key = AesKey::from_str(KEY_BASE64).unwrap();
+            let mut pak = PakReader::new_encrypted(Cursor::new(&data), key);
+            check_entries(&mut pak);
+        }
+    }
+}
+
+#[test]
+fn encrypted_without_key() {
+    let data = write_encrypted(PakVersion::FnameBasedCompressionMethod, true);
+
+    let mut pak = PakReader::new(Cursor::new(&data));
+    assert!(pak.load_index().is_err());
+}
+
+#[test]
+fn encrypted_data_only() {
+    let key = test_key();
+    let mut output = Cursor::new(Vec::new());
+
+    let mut pak = PakWriter::new_encrypted(
+        &mut output,
+        PakVersion::FnameBasedCompressionMethod,
+        key,
+        [0; 0x10],
+    );
+    pak.encrypt_index = false;
+    for (name, data) in test_entries() {
+        pak.write_entry(&name, &data, true).unwrap();
+    }
+    pak.finish_write().unwrap();
+
+    // the index can be read without a key, the data can not
+    let mut pak = PakReader::new(Cursor::new(output.get_ref()));
+    pak.load_index().unwrap();
+    assert!(pak.read_entry(&test_entries()[0].0).is_err());
+
+    check_entries(&mut PakReader::new_encrypted(
+        Cursor::new(output.get_ref()),
+        test_key(),
+    ));
+}
+
+#[test]
+fn encrypted_pak_memory() {
+    let data = write_encrypted(PakVersion::FnameBasedCompressionMethod, true);
+
+    let pak_memory = PakMemory::load_from_encrypted(&mut Cursor::new(&data), test_key()).unwrap();
+    assert!(pak_memory.encrypt_data);
+    assert!(pak_memory.encrypt_index);
+    assert_eq!(pak_memory.encryption_key_guid, [0x11; 0x10]);
+
+    let mut output = Cursor::new(Vec::new());
+    pak_memory.write(&mut output).unwrap();
+
+    check_entries(&mut PakReader::new_encrypted(
+        Cursor::new(output.get_ref()),
+        test_key(),
+    ));
+}
+
+#[test]
+fn encrypted_testfiles() {
+    for path in TESTFILES {
+        let testfile = open_testfile(path);
+        let entries = testfile_entries(path);
+
+        let mut output = Cursor::new(Vec::new());
+        let mut pak = pak_writer(&mut output, testfile.get_pak_version(), Some(test_key()));
+        pak.set_compression(testfile_compression(&testfile));
+        for (name, data) in &entries {
+            pak.write_entry(name, data, true).unwrap();
+        }
+        pak.finish_write().unwrap();
+
+        let mut pak = open_pak(Cursor::new(output.get_ref()), Some(test_key()));
+        assert!(pak.is_index_encrypted());
+        for (name, data) in entries {
+            assert_eq!(pak.read_entry(&name).unwrap(), data, "{path} {name}");
+        }
+    }
+}
+
+#[test]
+fn invalid_key() {
+    assert!(AesKey::from_str("0x1234").is_err());
+    assert!(AesKey::from_str("not a key").is_err());
+    assert!(AesKey::try_from([0u8; 31].as_slice()).is_err());
+}
@@ -0,0 +1,135 @@
+use std::io::Cursor;
+
+use unreal_pak::{pakversion::PakVersion, AesKey, Compression};
+
+mod common;
+use common::{open_pak, open_testfile, pak_writer, test_key, TESTFILES};
+
+fn write_pak(pak_version: PakVersion, key: Option<AesKey>) -> Vec<u8> {
+    let mut output = Cursor::new(Vec::new());
+
+    let mut pak = pak_writer(&mut output, pak_version, key);
+    pak.block_size = 0x1000;
+    let large = b"compress me ".repeat(1_000);
+    pak.write_entry(&"Game/a.bin".to_owned(), &large, true)
+        .unwrap();
+    pak.write_entry(&"Game/b.bin".to_owned(), &large, true)
+        .unwrap();
+    pak.write_entry(&"Game/stored.txt".to_owned(), &b"stored".to_vec(), false)
+        .unwrap();
+    pak.write_delete_record(&"Game/deleted.txt".to_owned())
+        .unwrap();
+    pak.finish_write().unwrap();
+
+    output.into_inner()
+}
+
+#[test]
+fn entry_info() {
+    for pak_version in [
+        PakVersion::DeleteRecords,
+        PakVersion::FnameBasedCompressionMethod,
+        PakVersion::Fnv64BugFix,
+    ] {
+        for key in [None, Some(test_key())] {
+            let encrypted = key.is_some();
+            let data = write_pak(pak_version, key.clone());
+            let mut pak = open_pak(Cursor::new(data), key);
+
+            assert_eq!(pak.get_pak_version(), pak_version);
+            match pak_version < PakVersion::FnameBasedCompressionMethod {
+                true => assert!(pak.get_compression_methods().is_empty()),
+                false => assert_eq!(pak.get_compression_methods(), [Compression::zlib()]),
+            }
+            assert_eq!(pak.is_index_encrypted(), encrypted);
+            assert_eq!(
+                pak.get_encryption_key_guid(),
+                (pak_version >= PakVersion::EncryptionKeyGuid).then_some([0u8; 0x10])
+            );
+
+            let entries = pak.entries();
+            assert_eq!(
+                entries.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(),
+                ["Game/a.bin", "Game/b.bin", "Game/stored.txt"]
+            );
+            assert!(pak.get_entry_info(&"Game/deleted.txt".to_owned()).is_none());
+
+            let compressed = &entries[0];
+            assert_eq!(compressed.compression, Compression::zlib());
+            assert_eq!(compressed.decompressed_size, 12_000);
+            assert_eq!(compressed.compression_block_count, 3);
+            assert_eq!(compressed.compression_block_size, 0x1000);
+            assert_eq!(compressed.encrypted, encrypted);
+            assert!(compressed.compression_ratio() < 0.5);
+
+            let stored = pak.get_entry_info(&"Game/stored.txt".to_owned()).unwrap();
+            assert_eq!(stored, entries[2]);
+            assert_eq!(stored.compression, Compression::None);
+            assert_eq!(stored.compressed_size, 6);
+            assert_eq!(stored.decompressed_size, 6);
+            assert_eq!(stored.compression_block_count, 0);
+            assert!(stored.offset > compressed.offset);
+
+            // identical content has an identical hash
+            let hashes = entries
+                .iter()
+                .map(|e| pak.read_entry_hash(&e.name).unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(hashes[0], hashes[1]);
+            assert_ne!(hashes[0], hashes[2]);
+
+            for (entry, hash) in entries.iter().zip(hashes) {
+                match pak_version < PakVersion::PathHashIndex {
+                    true => assert_eq!(entry.hash, Some(hash)),
+                    false => assert_eq!(entry.hash, None),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn entry_info_testfiles() {
+    let large = "Astro/Content/Guy/TestPak/LargeFile.bin".to_owned();
+    let small = "Astro/smoll.txt".to_owned();
+
+    for path in TESTFILES {
+        let mut pak = open_testfile(path);
+        assert_eq!(
+            pak.get_pak_version(),
+            PakVersion::FnameBasedCompressionMethod
+        );
+        assert_eq!(pak.entries().len(), 5);
+
+        let compressed = path.ends_with("-C_P.pak");
+        let info = pak.get_entry_info(&large).unwrap();
+        assert_eq!(info.offset, 0);
+        assert_eq!(info.decompressed_size, 200_000);
+        assert!(!info.encrypted);
+        assert_eq!(info.hash, Some(pak.read_entry_hash(&large).unwrap()));
+        match compressed {
+            true => {
+                assert_eq!(info.compression, Compression::zlib());
+                assert_eq!(info.compressed_size, 2065);
+                assert_eq!(info.compression_block_count, 4);
+                assert_eq!(info.compression_block_size, 0x10000);
+                assert_eq!(
+                    info.hash.map(hex::encode).as_deref(),
+                    Some("354ecf1e0eb186ce74a1903867a619fb3e91648d")
+                );
+            }
+            false => {
+                assert_eq!(info.compression, Compression::None);
+                assert_eq!(info.compressed_size, 200_000);
+                assert_eq!(info.compression_block_count, 0);
+                assert_eq!(info.compression_block_size, 0);
+            }
+        }
+
+        // entries under 32 bytes are stored uncompressed by UnrealPak as well
+        let info = pak.get_entry_info(&small).unwrap();
+        assert_eq!(info.compression, Compression::None);
+        assert_eq!(info.compressed_size, 6);
+        assert_eq!(info.compression_block_size, 0);
+    }
+}
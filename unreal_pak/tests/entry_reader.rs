@@ -0,0 +1,123 @@
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+use unreal_pak::{pakversion::PakVersion, AesKey};
+
+mod common;
+use common::{open_pak, open_testfile, pak_writer, test_key, testfile_entries, TESTFILES};
+
+fn test_data() -> Vec<u8> {
+    // large enough for multiple decryption chunks of uncompressed encrypted entries
+    (0..150_000u32)
+        .map(|e| ((e / 7) as u8).wrapping_mul(31) ^ (e >> 11) as u8)
+        .collect()
+}
+
+fn write_pak(pak_version: PakVersion, key: Option<AesKey>) -> Vec<u8> {
+    let mut output = Cursor::new(Vec::new());
+
+    let mut pak = pak_writer(&mut output, pak_version, key);
+    pak.block_size = 0x1000;
+    pak.write_entry(&"compressed.bin".to_owned(), &test_data(), true)
+        .unwrap();
+    pak.write_entry(&"stored.bin".to_owned(), &test_data(), false)
+        .unwrap();
+    pak.write_entry(&"empty.bin".to_owned(), &Vec::new(), false)
+        .unwrap();
+    pak.finish_write().unwrap();
+
+    output.into_inner()
+}
+
+#[test]
+fn entry_reader() {
+    let expected = test_data();
+
+    for pak_version in [
+        PakVersion::RelativeChunkOffsets,
+        PakVersion::FnameBasedCompressionMethod,
+        PakVersion::Fnv64BugFix,
+    ] {
+        for key in [None, Some(test_key())] {
+            let data = write_pak(pak_version, key.clone());
+            let mut pak = open_pak(Cursor::new(&data), key);
+
+            for name in ["compressed.bin", "stored.bin"] {
+                let mut entry = pak.open_entry(&name.to_owned()).unwrap();
+                assert_eq!(entry.size(), expected.len() as u64);
+
+                let mut buf = Vec::new();
+                entry.read_to_end(&mut buf).unwrap();
+                assert!(buf == expected, "{pak_version:?} {name}");
+
+                // random access across block boundaries
+                for position in [0x0fff, 0x10_000 - 3, 100_000, 5] {
+                    let mut buf = [0u8; 0x1800];
+                    entry.seek(SeekFrom::Start(position)).unwrap();
+                    entry.read_exact(&mut buf).unwrap();
+                    let position = position as usize;
+                    assert_eq!(buf, expected[position..position + buf.len()]);
+                }
+
+                let mut buf = [0u8; 16];
+                entry.seek(SeekFrom::End(-16)).unwrap();
+                entry.read_exact(&mut buf).unwrap();
+                assert_eq!(buf, expected[expected.len() - 16..]);
+                assert_eq!(entry.read(&mut buf).unwrap(), 0);
+                assert!(entry.seek(SeekFrom::Current(-200_000)).is_err());
+            }
+
+            let mut buf = Vec::new();
+            let mut entry = pak.open_entry(&"empty.bin".to_owned()).unwrap();
+            entry.read_to_end(&mut buf).unwrap();
+            assert!(buf.is_empty());
+        }
+    }
+}
+
+#[test]
+fn entry_reader_multiple() {
+    let data = write_pak(PakVersion::Fnv64BugFix, None);
+    let pak = open_pak(Cursor::new(&data), None);
+
+    let mut compressed = pak
+        .open_entry_with(&"compressed.bin".to_owned(), Cursor::new(&data))
+        .unwrap();
+    let mut stored = pak
+        .open_entry_with(&"stored.bin".to_owned(), Cursor::new(&data))
+        .unwrap();
+
+    let mut compressed_buf = [0u8; 0x100];
+    let mut stored_buf = [0u8; 0x100];
+    for _ in 0..16 {
+        compressed.read_exact(&mut compressed_buf).unwrap();
+        stored.read_exact(&mut stored_buf).unwrap();
+        assert_eq!(compressed_buf, stored_buf);
+    }
+
+    assert!(pak
+        .open_entry_with(&"missing.bin".to_owned(), Cursor::new(&data))
+        .is_err());
+}
+
+#[test]
+fn entry_reader_testfiles() {
+    for path in TESTFILES {
+        let mut pak = open_testfile(path);
+        for (name, expected) in testfile_entries(path) {
+            let mut entry = pak.open_entry(&name).unwrap();
+            assert_eq!(entry.size(), expected.len() as u64);
+
+            let mut buf = Vec::new();
+            entry.read_to_end(&mut buf).unwrap();
+            assert!(buf == expected, "{path} {name}");
+
+            // the large entry has multiple compression blocks in the compressed testfiles
+            if expected.len() > 0x10_000 {
+                let mut buf = [0u8; 0x100];
+                entry.seek(SeekFrom::Start(0x10_000 - 0x80)).unwrap();
+                entry.read_exact(&mut buf).unwrap();
+                assert_eq!(buf, expected[0x10_000 - 0x80..0x10_000 + 0x80]);
+            }
+        }
+    }
+}
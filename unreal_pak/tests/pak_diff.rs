@@ -0,0 +1,131 @@
+use std::io::Cursor;
+
+use unreal_pak::{pakversion::PakVersion, PakDiff, PakReader, PakVfs, PakWriter};
+
+fn build_pak(entries: &[(&str, &[u8], bool)]) -> PakReader<Cursor<Vec<u8>>> {
+    let mut output = Cursor::new(Vec::new());
+
+    let mut pak = PakWriter::new(&mut output, PakVersion::Fnv64BugFix);
+    for (name, data, compress) in entries {
+        pak.write_entry(&name.to_string(), &data.to_vec(), *compress)
+            .unwrap();
+    }
+    pak.finish_write().unwrap();
+
+    let mut pak = PakReader::new(Cursor::new(output.into_inner()));
+    pak.load_index().unwrap();
+    pak
+}
+
+/// Zero the stored hashes of all entries, like tools which don't write hashes do
+fn clear_hashes(mut pak: PakReader<Cursor<Vec<u8>>>) -> PakReader<Cursor<Vec<u8>>> {
+    let names = pak
+        .get_entry_names()
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    let hashes = names
+        .iter()
+        .map(|name| pak.read_entry_hash(name).unwrap())
+        .collect::<Vec<_>>();
+
+    let mut data = pak.into_inner().into_inner();
+    for hash in hashes {
+        while let Some(position) = data.windows(20).position(|e| e == hash) {
+            data[position..position + 20].fill(0);
+        }
+    }
+
+    let mut pak = PakReader::new(Cursor::new(data));
+    pak.load_index().unwrap();
+    pak
+}
+
+#[test]
+fn pak_diff() {
+    let unchanged = b"unchanged ".repeat(100);
+    let old_content = b"old content ".repeat(100);
+    let new_content = b"new content ".repeat(100);
+
+    let mut old = build_pak(&[
+        ("Game/unchanged.bin", &unchanged, true),
+        ("Game/recompressed.bin", &unchanged, true),
+        ("Game/modified.bin", &old_content, true),
+        ("Game/same_size.txt", b"aaaa", false),
+        ("Game/removed.txt", b"removed", false),
+    ]);
+    let mut new = build_pak(&[
+        ("Game/added.txt", b"added", false),
+        ("Game/unchanged.bin", &unchanged, true),
+        ("Game/recompressed.bin", &unchanged, false),
+        ("Game/modified.bin", &new_content, true),
+        ("Game/same_size.txt", b"aaab", false),
+    ]);
+
+    let diff = PakDiff::diff(&mut old, &mut new).unwrap();
+    assert_eq!(diff.added, vec!["Game/added.txt"]);
+    assert_eq!(diff.removed, vec!["Game/removed.txt"]);
+    assert_eq!(
+        diff.modified,
+        vec!["Game/modified.bin", "Game/same_size.txt"]
+    );
+    assert_eq!(
+        diff.get_changed_entry_names(),
+        vec!["Game/added.txt", "Game/modified.bin", "Game/same_size.txt"]
+    );
+
+    // loading the patch on top of the old pak file gives the contents of the new pak file
+    let mut output = Cursor::new(Vec::new());
+    let mut writer = PakWriter::new(&mut output, PakVersion::Fnv64BugFix);
+    diff.write_patch(&mut new, &mut writer, true, true).unwrap();
+    writer.finish_write().unwrap();
+
+    let mut patch = PakReader::new(Cursor::new(output.into_inner()));
+    patch.load_index().unwrap();
+    let mut names = patch.get_entry_names();
+    names.sort();
+    assert_eq!(
+        names,
+        vec!["Game/added.txt", "Game/modified.bin", "Game/same_size.txt"]
+    );
+    assert_eq!(patch.get_deleted_entry_names(), vec!["Game/removed.txt"]);
+
+    let mut vfs = PakVfs::new();
+    vfs.mount("pakchunk0-Windows.pak".to_owned(), old);
+    vfs.mount("pakchunk0-Windows_P.pak".to_owned(), patch);
+
+    let mut expected = new.get_entry_names();
+    expected.sort();
+    assert_eq!(vfs.get_file_names(), expected);
+    for name in expected.into_iter().cloned().collect::<Vec<_>>() {
+        assert_eq!(vfs.read(&name).unwrap(), new.read_entry(&name).unwrap());
+    }
+
+    let mut stored = build_pak(&[("Game/unchanged.bin", &unchanged, false)]);
+    let mut compressed = build_pak(&[("Game/unchanged.bin", &unchanged, true)]);
+    assert!(PakDiff::diff(&mut stored, &mut compressed)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn pak_diff_zero_hashes() {
+    let mut old = clear_hashes(build_pak(&[
+        ("Game/unchanged.txt", b"unchanged", false),
+        ("Game/same_size.txt", b"aaaa", false),
+    ]));
+    let mut new = clear_hashes(build_pak(&[
+        ("Game/unchanged.txt", b"unchanged", false),
+        ("Game/same_size.txt", b"aaab", false),
+    ]));
+    assert_eq!(
+        old.read_entry_hash(&"Game/same_size.txt".to_owned())
+            .unwrap(),
+        [0u8; 20]
+    );
+
+    let diff = PakDiff::diff(&mut old, &mut new).unwrap();
+    assert!(diff.added.is_empty());
+    assert!(diff.removed.is_empty());
+    assert_eq!(diff.modified, vec!["Game/same_size.txt"]);
+}
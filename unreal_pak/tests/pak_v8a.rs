@@ -0,0 +1,160 @@
+use std::fs;
+use std::io::Cursor;
+
+use unreal_pak::{pakversion::PakVersion, AesKey, Compression, PakMemory, PakReader};
+
+mod common;
+use common::{
+    open_pak, open_testfile, pak_writer, test_entries, test_key, testfile_compression,
+    testfile_entries,
+};
+
+const PAK_MAGIC: [u8; 4] = [0xE1, 0x12, 0x6F, 0x5A];
+
+fn write_pak(compression: Compression, key: Option<AesKey>) -> Vec<u8> {
+    let mut output = Cursor::new(Vec::new());
+
+    let mut pak = pak_writer(
+        &mut output,
+        PakVersion::FnameBasedCompressionMethodInitial,
+        key,
+    );
+    pak.set_compression(compression);
+    for (name, data) in test_entries() {
+        pak.write_entry(&name, &data, true).unwrap();
+    }
+    pak.finish_write().unwrap();
+
+    output.into_inner()
+}
+
+#[test]
+fn pak_v8a_round_trip() {
+    for compression in [Compression::zlib(), Compression::zstd()] {
+        for key in [None, Some(test_key())] {
+            let data = write_pak(compression, key.clone());
+
+            // the footer only has 4 compression method slots
+            let magic_offset = data.len() - 0xAC;
+            assert_eq!(data[magic_offset..magic_offset + 4], PAK_MAGIC);
+            assert_eq!(data[magic_offset + 4..magic_offset + 8], 8u32.to_le_bytes());
+
+            // the first entry header has a 1 byte compression method index after the sizes
+            assert_eq!(data[24], 1);
+
+            let mut pak = match key {
+                Some(key) => PakReader::new_encrypted(Cursor::new(&data), key),
+                None => PakReader::new(Cursor::new(&data)),
+            };
+            pak.verify = true;
+            pak.load_index().unwrap();
+            assert_eq!(
+                pak.get_pak_version(),
+                PakVersion::FnameBasedCompressionMethodInitial
+            );
+            assert_eq!(pak.get_compression_methods(), vec![compression]);
+
+            for (name, data) in test_entries() {
+                assert_eq!(
+                    pak.read_entry(&name).unwrap(),
+                    data,
+                    "{compression:?} {name}"
+                );
+            }
+            assert!(pak.verify_entries().is_empty());
+
+            let info = pak.get_entry_info(&test_entries()[0].0).unwrap();
+            assert_eq!(info.compression, compression);
+        }
+    }
+}
+
+#[test]
+fn pak_v8a_memory() {
+    let data = write_pak(Compression::zlib(), None);
+    let pak = PakMemory::load_from(&mut Cursor::new(&data)).unwrap();
+
+    let mut output = Cursor::new(Vec::new());
+    pak.write(&mut output).unwrap();
+
+    let mut pak = PakReader::new(Cursor::new(output.get_ref()));
+    pak.load_index().unwrap();
+    assert_eq!(
+        pak.get_pak_version(),
+        PakVersion::FnameBasedCompressionMethodInitial
+    );
+    for (name, data) in test_entries() {
+        assert_eq!(pak.read_entry(&name).unwrap(), data, "{name}");
+    }
+}
+
+/// Convert the raw entry header of a pak v8 entry into the one of pak v8A,
+/// which stores the compression method index in 1 instead of 4 bytes
+fn to_v8a_header(header: &[u8], block_count: u32) -> Vec<u8> {
+    // offset, sizes, compression method index and hash
+    let mut converted = header[..25].to_vec();
+    converted.extend_from_slice(&header[28..48]);
+
+    let mut rest = &header[48..];
+    if header[24] != 0 {
+        converted.extend_from_slice(&rest[..4]);
+        // block starts and ends are relative to the header start, so the header is 3 bytes shorter
+        for i in 0..block_count as usize * 2 {
+            let value = u64::from_le_bytes(rest[4 + i * 8..12 + i * 8].try_into().unwrap());
+            converted.extend_from_slice(&(value - 3).to_le_bytes());
+        }
+        rest = &rest[4 + block_count as usize * 16..];
+    }
+
+    // flags and compression block size
+    converted.extend_from_slice(&rest[..5]);
+    converted
+}
+
+#[test]
+fn pak_v8a_entry_headers() {
+    // there is no UE 4.22 pak in the testfiles, so the headers are compared
+    // with the ones UnrealPak wrote for the same entries in pak v8
+    for path in [
+        "testfiles/000-TestPak-off-C_P.pak",
+        "testfiles/000-TestPak-off-NoC_P.pak",
+    ] {
+        let original_data = fs::read(path).unwrap();
+        let original = open_testfile(path);
+
+        let mut output = Cursor::new(Vec::new());
+        let mut pak = pak_writer(
+            &mut output,
+            PakVersion::FnameBasedCompressionMethodInitial,
+            None,
+        );
+        pak.set_compression(testfile_compression(&original));
+        for (name, data) in testfile_entries(path) {
+            pak.write_entry(&name, &data, true).unwrap();
+        }
+        pak.finish_write().unwrap();
+        let data = output.into_inner();
+
+        let pak = open_pak(Cursor::new(&data), None);
+        for name in original.get_entry_names() {
+            let original_info = original.get_entry_info(name).unwrap();
+            let info = pak.get_entry_info(name).unwrap();
+
+            let block_count = original_info.compression_block_count;
+            // the block count and blocks are only stored for compressed entries
+            let header_len = match block_count {
+                0 => 53,
+                _ => 57 + block_count as usize * 16,
+            };
+            let original_offset = original_info.offset as usize;
+            let original_header = &original_data[original_offset..original_offset + header_len];
+            let offset = info.offset as usize;
+            let expected = to_v8a_header(original_header, block_count);
+            assert_eq!(
+                data[offset..offset + expected.len()],
+                expected,
+                "{path} {name}"
+            );
+        }
+    }
+}
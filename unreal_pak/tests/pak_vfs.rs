@@ -0,0 +1,138 @@
+use std::io::Cursor;
+
+use unreal_pak::{
+    pakversion::PakVersion,
+    pakvfs::{get_pak_priority, PakVfs},
+    PakReader, PakWriter,
+};
+
+fn build_pak(
+    mount_point: &str,
+    entries: &[(&str, &[u8])],
+    delete_records: &[&str],
+) -> PakReader<Cursor<Vec<u8>>> {
+    let mut output = Cursor::new(Vec::new());
+
+    let mut pak = PakWriter::new(&mut output, PakVersion::Fnv64BugFix);
+    pak.mount_point = mount_point.to_owned();
+    for (name, data) in entries {
+        pak.write_entry(&name.to_string(), &data.to_vec(), false)
+            .unwrap();
+    }
+    for name in delete_records {
+        pak.write_delete_record(&name.to_string()).unwrap();
+    }
+    pak.finish_write().unwrap();
+
+    let mut pak = PakReader::new(Cursor::new(output.into_inner()));
+    pak.load_index().unwrap();
+    pak
+}
+
+fn build_vfs() -> PakVfs<Cursor<Vec<u8>>> {
+    let mut vfs = PakVfs::new();
+    vfs.mount(
+        "Game-WindowsNoEditor.pak".to_owned(),
+        build_pak(
+            "../../../",
+            &[
+                ("Game/Content/A.uasset", b"base a"),
+                ("Game/Content/B.uasset", b"base b"),
+                ("Game/Content/Sub/C.uasset", b"base c"),
+            ],
+            &[],
+        ),
+    );
+    vfs.mount(
+        "000-First_P.pak".to_owned(),
+        build_pak(
+            "../../../Game/Content/",
+            &[("A.uasset", b"first a"), ("D.uasset", b"first d")],
+            &[],
+        ),
+    );
+    vfs.mount(
+        "001-Second_P.pak".to_owned(),
+        build_pak("../../../", &[("Game/Content/D.uasset", b"second d")], &[]),
+    );
+    vfs.mount(
+        "Game-WindowsNoEditor_1_P.pak".to_owned(),
+        build_pak("../../../", &[], &["Game/Content/B.uasset"]),
+    );
+    vfs
+}
+
+#[test]
+fn pak_priority() {
+    assert_eq!(get_pak_priority("Game-WindowsNoEditor.pak", 4), 4);
+    assert_eq!(get_pak_priority("000-Mod-0.1.0_P.pak", 4), 104);
+    assert_eq!(get_pak_priority("Game-WindowsNoEditor_P.pak", 0), 100);
+    assert_eq!(
+        get_pak_priority("pakchunk0-WindowsNoEditor_0_P.pak", 0),
+        100
+    );
+    assert_eq!(
+        get_pak_priority("pakchunk0-WindowsNoEditor_2_P.pak", 0),
+        300
+    );
+
+    // the suffix is matched ignoring case
+    assert_eq!(get_pak_priority("000-mod-0.1.0_p.pak", 4), 104);
+    assert_eq!(get_pak_priority("pakchunk0_2_p.PAK", 0), 300);
+}
+
+#[test]
+fn pak_vfs_resolution() {
+    let mut vfs = build_vfs();
+
+    let names = vfs.get_paks().iter().map(|e| &e.name).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec![
+            "Game-WindowsNoEditor_1_P.pak",
+            "001-Second_P.pak",
+            "000-First_P.pak",
+            "Game-WindowsNoEditor.pak",
+        ]
+    );
+
+    // patch paks override the base pak, with equal priority the last name wins
+    assert_eq!(
+        vfs.find("Game/Content/A.uasset").unwrap().name,
+        "000-First_P.pak"
+    );
+    assert_eq!(
+        vfs.find("../../../Game/Content/D.uasset").unwrap().name,
+        "001-Second_P.pak"
+    );
+    assert_eq!(vfs.read("game/content/a.uasset").unwrap(), b"first a");
+    assert_eq!(vfs.read("Game/Content/D.uasset").unwrap(), b"second d");
+    assert_eq!(vfs.read("Game/Content/Sub/C.uasset").unwrap(), b"base c");
+
+    // delete records hide files of lower priority paks
+    assert!(!vfs.contains("Game/Content/B.uasset"));
+    assert!(vfs.read("Game/Content/B.uasset").is_err());
+
+    assert_eq!(
+        vfs.get_file_names(),
+        vec![
+            "Game/Content/A.uasset",
+            "Game/Content/D.uasset",
+            "Game/Content/Sub/C.uasset",
+        ]
+    );
+    assert_eq!(vfs.list_directory(""), vec!["Game/"]);
+    assert_eq!(
+        vfs.list_directory("Game/Content"),
+        vec!["A.uasset", "D.uasset", "Sub/"]
+    );
+
+    vfs.unmount("Game-WindowsNoEditor_1_P.pak").unwrap();
+    assert_eq!(vfs.read("Game/Content/B.uasset").unwrap(), b"base b");
+
+    // readers are returned from the highest to the lowest priority
+    let readers = vfs.into_readers();
+    assert_eq!(readers.len(), 3);
+    assert!(readers[0].contains_entry(&"Game/Content/D.uasset".to_owned()));
+    assert!(readers[2].contains_entry(&"Game/Content/B.uasset".to_owned()));
+}
@@ -0,0 +1,100 @@
+#![cfg(feature = "parallel")]
+
+use std::fs::{self, File};
+use std::io::{self, Cursor};
+
+use rayon::prelude::*;
+use unreal_pak::{pakversion::PakVersion, PakReader};
+
+mod common;
+use common::{
+    open_testfile, pak_writer, test_entries, testfile_compression, testfile_entries,
+    write_test_entries,
+};
+
+fn write_pak(pak_version: PakVersion) -> Vec<u8> {
+    let mut output = Cursor::new(Vec::new());
+
+    // small blocks, so that every entry has enough blocks to be compressed in parallel
+    let mut pak = pak_writer(&mut output, pak_version, None);
+    pak.block_size = 0x1000;
+    write_test_entries(&mut pak);
+    pak.finish_write().unwrap();
+
+    output.into_inner()
+}
+
+#[test]
+fn parallel_write_deterministic() {
+    for pak_version in [
+        PakVersion::FnameBasedCompressionMethod,
+        PakVersion::Fnv64BugFix,
+    ] {
+        let expected = write_pak(pak_version);
+
+        for threads in [1, 2, 7] {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            let data = pool.install(|| write_pak(pak_version));
+            assert!(data == expected, "{pak_version:?} {threads}");
+        }
+    }
+}
+
+#[test]
+fn parallel_read() {
+    let data = write_pak(PakVersion::Fnv64BugFix);
+
+    let mut pak = PakReader::new(Cursor::new(&data));
+    pak.verify = true;
+    pak.load_index().unwrap();
+
+    let entries = pak
+        .par_iter(|| Ok(Cursor::new(&data)))
+        .map(|(name, data)| (name.clone(), data.unwrap()))
+        .collect::<Vec<_>>();
+    assert!(entries == test_entries());
+
+    // errors opening the reader are reported for every entry
+    let errors = pak
+        .par_iter(|| Err::<Cursor<&[u8]>, _>(io::Error::other("no reader")))
+        .filter(|(_, data)| data.is_err())
+        .count();
+    assert_eq!(errors, test_entries().len());
+}
+
+#[test]
+fn parallel_testfiles() {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(7)
+        .build()
+        .unwrap();
+
+    for path in [
+        "testfiles/000-TestPak-off-C_P.pak",
+        "testfiles/000-TestPak-off-NoC_P.pak",
+    ] {
+        let mut testfile = open_testfile(path);
+        testfile.verify = true;
+        let entries = pool.install(|| {
+            testfile
+                .par_iter(|| File::open(path))
+                .map(|(name, data)| (name.clone(), data.unwrap()))
+                .collect::<Vec<_>>()
+        });
+        assert!(entries == testfile_entries(path), "{path}");
+
+        let mut output = Cursor::new(Vec::new());
+        let mut pak = pak_writer(&mut output, testfile.get_pak_version(), None);
+        pak.set_compression(testfile_compression(&testfile));
+        pool.install(|| {
+            for (name, data) in &entries {
+                pak.write_entry(name, data, true).unwrap();
+            }
+        });
+        pak.finish_write().unwrap();
+        assert!(output.into_inner() == fs::read(path).unwrap(), "{path}");
+    }
+}
@@ -0,0 +1,85 @@
+use std::fs::File;
+use std::io::Cursor;
+
+use unreal_pak::{pakversion::PakVersion, PakMemory, PakReader, PakWriter};
+
+mod common;
+use common::{pak_writer, test_entries, TESTFILES};
+
+#[test]
+fn path_hash_index_round_trip() {
+    for pak_version in [PakVersion::PathHashIndex, PakVersion::Fnv64BugFix] {
+        for compress in [false, true] {
+            let mut output = Cursor::new(Vec::new());
+
+            let mut pak = pak_writer(&mut output, pak_version, None);
+            for (name, data) in test_entries() {
+                pak.write_entry(&name, &data, compress).unwrap();
+            }
+            pak.finish_write().unwrap();
+
+            let mut pak = PakReader::new(Cursor::new(output.get_ref()));
+            pak.load_index().unwrap();
+
+            let mut names = test_entries()
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>();
+            names.sort();
+            assert_eq!(pak.get_entry_names(), names.iter().collect::<Vec<_>>());
+
+            for (name, data) in test_entries() {
+                assert_eq!(pak.read_entry(&name).unwrap(), data, "{name}");
+            }
+        }
+    }
+}
+
+#[test]
+fn convert_to_fnv64_bug_fix() {
+    for path in TESTFILES {
+        let mut file = File::open(path).unwrap();
+        let mut pak_memory = PakMemory::load_from(&mut file).unwrap();
+        pak_memory.pak_version = PakVersion::Fnv64BugFix;
+
+        let mut output = Cursor::new(Vec::new());
+        pak_memory.write(&mut output).unwrap();
+
+        output.set_position(0);
+        let converted = PakMemory::load_from(&mut output).unwrap();
+        assert_eq!(converted.pak_version, PakVersion::Fnv64BugFix);
+        assert_eq!(converted.mount_point, pak_memory.mount_point);
+        assert!(converted.iter().eq(pak_memory.iter()), "{path}");
+    }
+}
+
+#[test]
+fn path_hash_seed() {
+    let write_pak = |path_hash_seed| {
+        let mut output = Cursor::new(Vec::new());
+        let mut pak = PakWriter::new(&mut output, PakVersion::Fnv64BugFix);
+        pak.path_hash_seed = path_hash_seed;
+        for (name, data) in test_entries() {
+            pak.write_entry(&name, &data, true).unwrap();
+        }
+        pak.finish_write().unwrap();
+        output.into_inner()
+    };
+
+    // a fixed seed makes the output reproducible
+    let data = write_pak(Some(0x1234_5678));
+    assert_eq!(write_pak(Some(0x1234_5678)), data);
+
+    let pak_memory = PakMemory::load_from(&mut Cursor::new(&data)).unwrap();
+    assert_eq!(pak_memory.path_hash_seed, Some(0x1234_5678));
+
+    // the loaded seed is kept when writing
+    let mut output = Cursor::new(Vec::new());
+    pak_memory.write(&mut output).unwrap();
+    let rewritten = PakMemory::load_from(&mut Cursor::new(output.get_ref())).unwrap();
+    assert_eq!(rewritten.path_hash_seed, Some(0x1234_5678));
+
+    // without a seed a random one is used
+    let pak_memory = PakMemory::load_from(&mut Cursor::new(write_pak(None))).unwrap();
+    assert!(pak_memory.path_hash_seed.is_some());
+}
@@ -0,0 +1,125 @@
+use std::fs;
+use std::io::{self, Cursor, Read};
+
+use unreal_pak::{error::PakErrorKind, pakversion::PakVersion, AesKey, PakReader, PakWriter};
+
+mod common;
+use common::{
+    open_testfile, pak_writer, test_entries, test_key, testfile_compression, testfile_entries,
+};
+
+/// A reader which only returns a few bytes per read
+struct SlowReader<'a>(&'a [u8]);
+
+impl Read for SlowReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.0.len()).min(1000);
+        buf[..len].copy_from_slice(&self.0[..len]);
+        self.0 = &self.0[len..];
+        Ok(len)
+    }
+}
+
+fn write_pak(pak_version: PakVersion, key: Option<AesKey>, streamed: Option<bool>) -> Vec<u8> {
+    let mut output = Cursor::new(Vec::new());
+
+    let mut pak = pak_writer(&mut output, pak_version, key);
+    for (name, data) in test_entries() {
+        for compress in [false, true] {
+            let name = format!("{name}.{compress}");
+            match streamed {
+                Some(known_size) => pak
+                    .write_entry_from_reader(
+                        &name,
+                        &mut SlowReader(&data),
+                        known_size.then_some(data.len() as u64),
+                        compress,
+                    )
+                    .unwrap(),
+                None => pak.write_entry(&name, &data, compress).unwrap(),
+            }
+        }
+    }
+    pak.finish_write().unwrap();
+
+    output.into_inner()
+}
+
+#[test]
+fn stream_write() {
+    for pak_version in [
+        PakVersion::RelativeChunkOffsets,
+        PakVersion::FnameBasedCompressionMethod,
+        PakVersion::Fnv64BugFix,
+    ] {
+        for key in [None, Some(test_key())] {
+            let expected = write_pak(pak_version, key.clone(), None);
+
+            for known_size in [true, false] {
+                let data = write_pak(pak_version, key.clone(), Some(known_size));
+                assert!(data == expected, "{pak_version:?} {known_size}");
+
+                let mut pak = match key.clone() {
+                    Some(key) => PakReader::new_encrypted(Cursor::new(data), key),
+                    None => PakReader::new(Cursor::new(data)),
+                };
+                pak.verify = true;
+                pak.load_index().unwrap();
+                for (name, data) in test_entries() {
+                    for compress in [false, true] {
+                        let name = format!("{name}.{compress}");
+                        assert!(pak.read_entry(&name).unwrap() == data, "{name}");
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn stream_write_testfiles() {
+    for path in [
+        "testfiles/000-TestPak-off-C_P.pak",
+        "testfiles/000-TestPak-off-NoC_P.pak",
+    ] {
+        let testfile = open_testfile(path);
+        let expected = fs::read(path).unwrap();
+
+        for known_size in [true, false] {
+            let mut output = Cursor::new(Vec::new());
+            let mut pak = pak_writer(&mut output, testfile.get_pak_version(), None);
+            pak.set_compression(testfile_compression(&testfile));
+            for (name, data) in testfile_entries(path) {
+                pak.write_entry_from_reader(
+                    &name,
+                    &mut SlowReader(&data),
+                    known_size.then_some(data.len() as u64),
+                    true,
+                )
+                .unwrap();
+            }
+            pak.finish_write().unwrap();
+
+            // streaming the entries reproduces the pak written by UnrealPak
+            assert!(output.into_inner() == expected, "{path} {known_size}");
+        }
+    }
+}
+
+#[test]
+fn stream_write_size_mismatch() {
+    let data = b"too short".repeat(10);
+
+    for compress in [false, true] {
+        let mut pak = PakWriter::new(Cursor::new(Vec::new()), PakVersion::Fnv64BugFix);
+        let err = pak
+            .write_entry_from_reader(
+                &"Game/short.bin".to_owned(),
+                &mut data.as_slice(),
+                Some(data.len() as u64 + 1),
+                compress,
+            )
+            .unwrap_err();
+        assert!(matches!(err.kind, PakErrorKind::IoError(_)));
+    }
+}
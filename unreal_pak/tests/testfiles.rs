@@ -0,0 +1,31 @@
+use std::fs;
+use std::io::Cursor;
+
+use unreal_pak::PakWriter;
+
+mod common;
+use common::{open_testfile, testfile_compression, testfile_entries};
+
+#[test]
+fn rewrite_testfiles() {
+    // the index of the cus testfiles is in a different order than the data,
+    // which can't be reproduced as the index is written in the order the entries were written
+    for path in [
+        "testfiles/000-TestPak-off-C_P.pak",
+        "testfiles/000-TestPak-off-NoC_P.pak",
+    ] {
+        let pak = open_testfile(path);
+
+        let mut output = Cursor::new(Vec::new());
+        let mut writer = PakWriter::new(&mut output, pak.get_pak_version());
+        writer.mount_point = pak.mount_point.clone();
+        writer.set_compression(testfile_compression(&pak));
+        for (name, data) in testfile_entries(path) {
+            writer.write_entry(&name, &data, true).unwrap();
+        }
+        writer.finish_write().unwrap();
+
+        // the testfiles were written by UnrealPak, so this compares the output with UnrealPak
+        assert!(output.into_inner() == fs::read(path).unwrap(), "{path}");
+    }
+}
@@ -0,0 +1,123 @@
+use std::fs::File;
+use std::io::Cursor;
+
+use unreal_pak::{error::PakErrorKind, pakversion::PakVersion, PakReader};
+
+mod common;
+use common::{test_entries, test_key, write_pak, TESTFILES};
+
+const PAK_VERSIONS: [PakVersion; 4] = [
+    PakVersion::RelativeChunkOffsets,
+    PakVersion::FnameBasedCompressionMethod,
+    PakVersion::PathHashIndex,
+    PakVersion::Fnv64BugFix,
+];
+
+fn find(data: &[u8], needle: &[u8]) -> usize {
+    data.windows(needle.len())
+        .rposition(|e| e == needle)
+        .unwrap()
+}
+
+#[test]
+fn verify_intact() {
+    for pak_version in PAK_VERSIONS {
+        for key in [None, Some(test_key())] {
+            if key.is_some() && pak_version < PakVersion::IndexEncryption {
+                continue;
+            }
+
+            let mut pak = PakReader::new(Cursor::new(write_pak(pak_version, key.clone())));
+            if let Some(key) = key {
+                pak = PakReader::new_encrypted(pak.into_inner(), key);
+            }
+            pak.verify = true;
+            pak.load_index().unwrap();
+
+            assert!(pak.verify_entries().is_empty(), "{pak_version:?}");
+            for (name, data) in test_entries() {
+                assert_eq!(pak.read_entry(&name).unwrap(), data, "{pak_version:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn verify_testfiles() {
+    for path in TESTFILES {
+        let mut pak = PakReader::new(File::open(path).unwrap());
+        pak.verify = true;
+        pak.load_index().unwrap();
+        assert!(pak.verify_entries().is_empty(), "{path}");
+    }
+}
+
+#[test]
+fn verify_corrupted_entry() {
+    for pak_version in PAK_VERSIONS {
+        let mut data = write_pak(pak_version, None);
+        let position = find(&data, b"0123456789abcdef");
+        data[position] ^= 0xff;
+
+        let mut pak = PakReader::new(Cursor::new(data));
+        pak.load_index().unwrap();
+
+        // without verification the corruption goes unnoticed
+        assert!(pak.read_entry(&"Game/stored.bin".to_owned()).is_ok());
+
+        let corrupted = pak.verify_entries();
+        assert_eq!(corrupted.len(), 1, "{pak_version:?}");
+        assert_eq!(corrupted[0].0, "Game/stored.bin");
+        assert!(matches!(
+            corrupted[0].1.kind,
+            PakErrorKind::EntryHashMismatch(ref name) if name == "Game/stored.bin"
+        ));
+
+        pak.verify = true;
+        assert!(pak.read_entry(&"Game/stored.bin".to_owned()).is_err());
+        assert!(pak.read_entry(&"Game/small.txt".to_owned()).is_ok());
+        assert!(pak
+            .iter()
+            .all(|(name, data)| data.is_ok() == (name != "Game/stored.bin")));
+    }
+}
+
+#[test]
+fn verify_corrupted_index() {
+    for pak_version in PAK_VERSIONS {
+        let mut data = write_pak(pak_version, None);
+        // the last occurrence of an entry name is in the index,
+        // for newer versions it is in the full directory index
+        let position = find(&data, b"small.txt");
+        data[position + 2] = b'e';
+
+        let mut pak = PakReader::new(Cursor::new(data.clone()));
+        pak.load_index().unwrap();
+        assert!(pak.contains_entry(&"Game/smell.txt".to_owned()));
+
+        let mut pak = PakReader::new(Cursor::new(data));
+        pak.verify = true;
+        let err = pak.load_index().unwrap_err();
+        assert!(
+            matches!(err.kind, PakErrorKind::IndexHashMismatch),
+            "{pak_version:?}"
+        );
+    }
+}
+
+#[test]
+fn verify_path_hash_index() {
+    // path hashes written before the FNV-64 bug fix don't match the fixed hash
+    let mut data = write_pak(PakVersion::PathHashIndex, None);
+    let position = find(&data, &0x5A6F12E1u32.to_le_bytes()) + 4;
+    data[position..position + 4].copy_from_slice(&(PakVersion::Fnv64BugFix as u32).to_le_bytes());
+
+    let mut pak = PakReader::new(Cursor::new(data.clone()));
+    pak.load_index().unwrap();
+    assert_eq!(pak.get_pak_version(), PakVersion::Fnv64BugFix);
+
+    let mut pak = PakReader::new(Cursor::new(data));
+    pak.verify = true;
+    let err = pak.load_index().unwrap_err();
+    assert!(matches!(err.kind, PakErrorKind::PathHashMismatch(_)));
+}
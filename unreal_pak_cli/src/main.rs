@@ -1,12 +1,20 @@
+use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
-use std::io::{BufReader, BufWriter, Write};
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
 use std::path::{Path, PathBuf};
 use std::process::exit;
+use std::str::FromStr;
 use std::time::SystemTime;
 
 use clap::{Parser, Subcommand};
+use glob::{MatchOptions, Pattern};
 use path_absolutize::Absolutize;
-use unreal_pak::{pakversion::PakVersion, PakReader, PakWriter};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use unreal_pak::{
+    compression::OodleBackend, pakversion::PakVersion, AesKey, Compression, PakDiff,
+    PakEntryReader, PakError, PakReader, PakWriter,
+};
 use walkdir::WalkDir;
 
 /// Command line tool for working with Unreal Engine .pak files.
@@ -18,6 +26,10 @@ struct Args {
     /// What to do
     #[clap(subcommand)]
     commands: Commands,
+    /// Oodle library shipped with the game, e.g. oo2core_9_win64.dll,
+    /// required for reading and writing Oodle compressed .pak files
+    #[clap(long, global = true, value_name = "LIBRARY")]
+    oodle: Option<String>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -26,12 +38,51 @@ enum Commands {
     Check {
         /// The .pak file to check
         pakfile: String,
+        /// AES key used to decrypt the .pak file, as hex or base64
+        #[clap(short, long)]
+        aes_key: Option<String>,
     },
 
     /// Only check the header of a .pak file if it is valid.
     CheckHeader {
         /// The .pak file to check
         pakfile: String,
+        /// AES key used to decrypt the .pak file, as hex or base64
+        #[clap(short, long)]
+        aes_key: Option<String>,
+    },
+
+    /// Verify the SHA-1 hashes of the index and all entries of a .pak file.
+    Verify {
+        /// The .pak file to verify
+        pakfile: String,
+        /// AES key used to decrypt the .pak file, as hex or base64
+        #[clap(short, long)]
+        aes_key: Option<String>,
+    },
+
+    /// Print information about a .pak file like its version, mount point and compression methods.
+    Info {
+        /// The .pak file to inspect
+        pakfile: String,
+        /// AES key used to decrypt the .pak file, as hex or base64
+        #[clap(short, long)]
+        aes_key: Option<String>,
+        /// Print the information as JSON
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// List all entries of a .pak file with their sizes, compression method and hash.
+    List {
+        /// The .pak file to list
+        pakfile: String,
+        /// AES key used to decrypt the .pak file, as hex or base64
+        #[clap(short, long)]
+        aes_key: Option<String>,
+        /// Print the entries as JSON
+        #[clap(long)]
+        json: bool,
     },
 
     /// Extract a .pak file to a directory.
@@ -40,6 +91,41 @@ enum Commands {
         pakfile: String,
         /// The directory to extract to, if not specified the .pak file name will be used
         outdir: Option<String>,
+        /// AES key used to decrypt the .pak file, as hex or base64
+        #[clap(short, long)]
+        aes_key: Option<String>,
+        /// Only extract records matching this glob pattern, e.g. "*.json", can be used multiple times
+        #[clap(short, long)]
+        include: Vec<String>,
+        /// Do not extract records matching this glob pattern, can be used multiple times
+        #[clap(short, long)]
+        exclude: Vec<String>,
+        /// Extract all records directly into the output directory without their directories
+        #[clap(short, long)]
+        flatten: bool,
+        /// Write a single record to stdout instead of extracting to a directory
+        #[clap(long, value_name = "RECORD", conflicts_with_all = ["outdir", "include", "exclude", "flatten"])]
+        stdout: Option<String>,
+    },
+
+    /// Compare two .pak files and list the added, removed and modified records.
+    Diff {
+        /// The old .pak file
+        old_pakfile: String,
+        /// The new .pak file
+        new_pakfile: String,
+        /// AES key used to decrypt both .pak files, as hex or base64
+        #[clap(short, long)]
+        aes_key: Option<String>,
+        /// Write a patch .pak file with all added and modified records of the new .pak file
+        #[clap(short, long, value_name = "PATCH")]
+        patch: Option<String>,
+        /// Do not use compression when writing the patch .pak file
+        #[clap(short, long, requires = "patch")]
+        no_compression: bool,
+        /// Print the differences as JSON
+        #[clap(long)]
+        json: bool,
     },
 
     /// create a new .pak file from the files from a directory, optionally disabling compression.
@@ -51,6 +137,26 @@ enum Commands {
         /// Do not use compression when writing the file
         #[clap(short, long)]
         no_compression: bool,
+        /// Version of the .pak file format, from 1 to 11, 8a for UE 4.22 and 8b for UE 4.23-4.24
+        #[clap(short = 'v', long, default_value = "8", value_parser = parse_pak_version)]
+        pak_version: PakVersion,
+        /// Mount point of the .pak file
+        #[clap(short, long, default_value = "../../../")]
+        mount_point: String,
+        /// Compression method used for compressed files, e.g. Zlib, Gzip, LZ4, Zstd or Oodle (requires --oodle)
+        #[clap(short, long, default_value = "Zlib")]
+        compression: String,
+        /// Size of the compression blocks in bytes
+        #[clap(short, long, default_value_t = 0x10000)]
+        block_size: u32,
+        /// Never compress files with this extension, e.g. "ushaderbytecode", can be used multiple times
+        #[clap(long, value_name = "EXTENSION")]
+        no_compress_ext: Vec<String>,
+        /// Only write the files listed in this response file, in the listed order.
+        /// Each line is `"<source path>" "<pak path>" [-compress | -nocompress]`,
+        /// source paths are relative to the input directory.
+        #[clap(short, long, value_name = "FILE")]
+        response_file: Option<String>,
     },
 }
 
@@ -58,16 +164,77 @@ fn main() {
     let args = Args::parse();
 
     let start = SystemTime::now();
+    // the output of these commands is meant to be parsed
+    let print_time = !matches!(
+        args.commands,
+        Commands::Info { .. }
+            | Commands::List { .. }
+            | Commands::Diff { json: true, .. }
+            | Commands::Extract {
+                stdout: Some(_),
+                ..
+            }
+    );
+
+    if let Some(oodle) = args.oodle {
+        // the library is only used through the exported Oodle functions
+        if let Err(err) = unsafe { OodleBackend::load(&oodle) } {
+            eprintln!("Error loading Oodle library {oodle:?}! Error: {err}");
+            exit(1);
+        }
+    }
 
     match args.commands {
-        Commands::CheckHeader { pakfile } => {
-            let file = open_file(Path::new(&pakfile));
-            let mut pak = PakReader::new(file);
+        Commands::Info {
+            pakfile,
+            aes_key,
+            json,
+        } => {
+            let mut pak = open_pak(Path::new(&pakfile), aes_key);
+            load_index(&mut pak);
+            print_info(&pak, json);
+        }
+        Commands::List {
+            pakfile,
+            aes_key,
+            json,
+        } => {
+            let mut pak = open_pak(Path::new(&pakfile), aes_key);
+            load_index(&mut pak);
+            print_list(&mut pak, json);
+        }
+        Commands::Diff {
+            old_pakfile,
+            new_pakfile,
+            aes_key,
+            patch,
+            no_compression,
+            json,
+        } => {
+            let mut old = open_pak(Path::new(&old_pakfile), aes_key.clone());
+            load_index(&mut old);
+            let mut new = open_pak(Path::new(&new_pakfile), aes_key);
+            load_index(&mut new);
+
+            let diff = match PakDiff::diff(&mut old, &mut new) {
+                Ok(diff) => diff,
+                Err(err) => {
+                    eprintln!("Error comparing pak files! Error: {err}");
+                    exit(1);
+                }
+            };
+            print_diff(&diff, json);
+
+            if let Some(patch) = patch {
+                write_patch(&diff, &mut new, Path::new(&patch), !no_compression);
+            }
+        }
+        Commands::CheckHeader { pakfile, aes_key } => {
+            let mut pak = open_pak(Path::new(&pakfile), aes_key);
             check_header(&mut pak);
         }
-        Commands::Check { pakfile } => {
-            let file = open_file(Path::new(&pakfile));
-            let mut pak = PakReader::new(file);
+        Commands::Check { pakfile, aes_key } => {
+            let mut pak = open_pak(Path::new(&pakfile), aes_key);
             check_header(&mut pak);
 
             for (i, (file_name, data)) in pak.iter().enumerate() {
@@ -82,10 +249,54 @@ fn main() {
                 }
             }
         }
-        Commands::Extract { pakfile, outdir } => {
+        Commands::Verify { pakfile, aes_key } => {
+            let mut pak = open_pak(Path::new(&pakfile), aes_key);
+            pak.verify = true;
+            check_header(&mut pak);
+
+            let corrupted = pak.verify_entries();
+            for (file_name, err) in &corrupted {
+                eprintln!("Corrupted record {file_name:?}! Error: {err}");
+            }
+
+            if !corrupted.is_empty() {
+                eprintln!("Found {} corrupted records", corrupted.len());
+                exit(1);
+            }
+            println!("All records are ok");
+        }
+        Commands::Extract {
+            stdout: Some(file_name),
+            pakfile,
+            aes_key,
+            ..
+        } => {
+            let mut pak = open_pak(Path::new(&pakfile), aes_key);
+            load_index(&mut pak);
+
+            let data = match pak.read_entry(&file_name) {
+                Ok(data) => data,
+                Err(err) => {
+                    eprintln!("Error reading record {file_name:?}! Error: {err}");
+                    exit(1);
+                }
+            };
+            if let Err(err) = io::stdout().lock().write_all(&data) {
+                eprintln!("Error writing to stdout! Error: {err}");
+                exit(1);
+            }
+        }
+        Commands::Extract {
+            pakfile,
+            outdir,
+            aes_key,
+            include,
+            exclude,
+            flatten,
+            stdout: None,
+        } => {
             let path = Path::new(&pakfile);
-            let file = open_file(path);
-            let mut pak = PakReader::new(file);
+            let mut pak = open_pak(path, aes_key);
             check_header(&mut pak);
 
             // temp values required to extend lifetimes outside of match scope
@@ -94,63 +305,74 @@ fn main() {
                 None => path.parent().unwrap().join(path.file_stem().unwrap()),
             };
 
-            println!("Extracting to {output_folder:?}");
+            let include = parse_patterns(&include);
+            let exclude = parse_patterns(&exclude);
 
-            for (i, (file_name, data)) in pak.iter().enumerate() {
-                match data {
-                    Ok(data) => {
-                        let path = output_folder.join(file_name);
-                        let dir_path = match path.parent() {
-                            Some(dir) => dir,
-                            None => {
-                                eprintln!("No parent directories found! {i}: {file_name:?}");
-                                exit(1);
-                            }
-                        };
-
-                        // Create the parent directories, then files.
-                        match std::fs::create_dir_all(dir_path) {
-                            Ok(_) => {
-                                // Create the file
-                                let mut file = match File::create(&path) {
-                                    Ok(file) => file,
-                                    Err(err) => {
-                                        eprintln!(
-                                            "Error creating file {i}: {path:?}! Error: {err}"
-                                        );
-                                        exit(1);
-                                    }
-                                };
-                                // Write the file
-                                match file.write_all(data.as_slice()) {
-                                    Ok(_) => {
-                                        println!("Record {i}: {file_name}");
-                                    }
-                                    Err(err) => {
-                                        eprintln!(
-                                            "Error writing to file {i}: {path:?}! Error: {err}"
-                                        );
-                                        exit(1);
-                                    }
-                                }
-                            }
-                            Err(err) => {
-                                eprintln!("Error creating directories {dir_path:?}! Error: {err}");
-                                exit(1);
-                            }
-                        };
-                    }
-                    Err(err) => {
-                        eprintln!("Error reading record {i}: {file_name:?}! Error: {err}");
-                        exit(1);
-                    }
+            // pak file paths are case insensitive
+            let match_options = MatchOptions {
+                case_sensitive: false,
+                ..Default::default()
+            };
+            let matches = |file_name: &str, patterns: &[Pattern]| {
+                patterns
+                    .iter()
+                    .any(|e| e.matches_with(file_name, match_options))
+            };
+
+            let mut output_paths = HashMap::new();
+            let mut file_names = Vec::new();
+            for file_name in pak.get_entry_names() {
+                if (!include.is_empty() && !matches(file_name, &include))
+                    || matches(file_name, &exclude)
+                {
+                    continue;
                 }
+
+                let output_path = match flatten {
+                    true => output_folder.join(file_name.rsplit('/').next().unwrap_or(file_name)),
+                    false => output_folder.join(file_name),
+                };
+                if let Some(other) = output_paths.insert(output_path.clone(), file_name) {
+                    eprintln!(
+                        "Records {other:?} and {file_name:?} would both be extracted to {output_path:?}!"
+                    );
+                    exit(1);
+                }
+
+                file_names.push((file_name.clone(), output_path));
+            }
+
+            println!(
+                "Extracting {} records to {output_folder:?}",
+                file_names.len()
+            );
+
+            // entries are streamed to disk to keep memory usage low for large files
+            #[cfg(feature = "parallel")]
+            file_names
+                .par_iter()
+                .enumerate()
+                .for_each(|(i, (file_name, output_path))| {
+                    let entry = pak.open_entry_with(file_name, open_file(path));
+                    extract_entry(i, file_name, entry, output_path);
+                });
+
+            #[cfg(not(feature = "parallel"))]
+            for (i, (file_name, output_path)) in file_names.iter().enumerate() {
+                let entry = pak.open_entry(file_name);
+                extract_entry(i, file_name, entry, output_path);
             }
         }
         Commands::Create {
             indir,
             pakfile,
             no_compression,
+            pak_version,
+            mount_point,
+            compression,
+            block_size,
+            no_compress_ext,
+            response_file,
         } => {
             let pakfile = match pakfile {
                 Some(pakfile) => Path::new(&pakfile).absolutize().unwrap().to_path_buf(),
@@ -165,57 +387,72 @@ fn main() {
                 }
             };
             let indir = Path::new(&indir).absolutize().unwrap().to_path_buf();
-            let indir_len = indir.components().count();
+
+            let compression_method = Compression::from_name(&compression);
+            if !compression_method.is_supported() {
+                match compression_method == Compression::oodle() {
+                    true => eprintln!("Oodle compression requires the Oodle library, use --oodle!"),
+                    false => eprintln!("Compression method {compression:?} is not supported!"),
+                }
+                exit(1);
+            }
+            if block_size == 0 {
+                eprintln!("Block size must not be 0!");
+                exit(1);
+            }
+
+            let files = match response_file {
+                Some(response_file) => {
+                    read_response_file(Path::new(&response_file), &indir, &mount_point)
+                }
+                None => walk_dir(&indir),
+            };
 
             println!("Creating {pakfile:?}");
 
-            // clear file
-            OpenOptions::new()
+            // the file can't be opened in append mode as entry headers are written again after their data
+            let file = OpenOptions::new()
                 .create(true)
                 .write(true)
                 .truncate(true)
                 .open(&pakfile)
                 .unwrap();
 
-            let file = OpenOptions::new().append(true).open(&pakfile).unwrap();
-
-            let mut pak = PakWriter::new(
-                BufWriter::new(file),
-                PakVersion::FnameBasedCompressionMethod,
-            );
+            let mut pak = PakWriter::new(BufWriter::new(file), pak_version);
+            pak.mount_point = mount_point;
+            pak.block_size = block_size;
+            pak.set_compression(compression_method);
 
-            // Get all files and write them to the .pak file
-            let files = WalkDir::new(&indir)
-                .into_iter()
-                .filter_map(|entry| entry.ok())
-                .filter(|entry| entry.file_type().is_file())
+            let no_compress_ext = no_compress_ext
+                .iter()
+                .map(|e| e.trim_start_matches('.').to_lowercase())
                 .collect::<Vec<_>>();
-            //files.sort_unstable_by_key(|entry| entry.file_name().to_owned());
 
             println!("Writing {} files", files.len());
 
-            for (i, entry) in files.iter().enumerate() {
-                // file_path is the OS absolute path, file_name is the folders and file name written to the pak
-                let file_path = entry.path();
-                let mut components = file_path.components();
-                for _ in 0..indir_len {
-                    components.next();
-                }
+            for (i, file) in files.iter().enumerate() {
+                let file_path = &file.source;
+                let file_name = &file.name;
 
-                let mut file_name = components.as_path().to_string_lossy().replace('\\', "/");
-                if file_name.starts_with('/') {
-                    file_name = file_name[1..].to_owned();
-                }
+                let extension = file_name
+                    .rsplit_once('.')
+                    .map(|(_, e)| e.to_lowercase())
+                    .unwrap_or_default();
+                let compress = file.compress.unwrap_or(!no_compression)
+                    && !no_compress_ext.contains(&extension);
 
-                let file_data = match std::fs::read(file_path) {
-                    Ok(file_data) => file_data,
+                // files are streamed into the pak to keep memory usage low for large files
+                let (mut file, size) = match File::open(file_path)
+                    .and_then(|file| Ok((file.metadata()?.len(), file)))
+                {
+                    Ok((size, file)) => (BufReader::new(file), size),
                     Err(err) => {
                         eprintln!("Error reading file {file_path:?}! Error: {err}");
                         exit(1);
                     }
                 };
 
-                match pak.write_entry(&file_name, &file_data, !no_compression) {
+                match pak.write_entry_from_reader(file_name, &mut file, Some(size), compress) {
                     Ok(_) => println!("Wrote file {i}: {file_name}"),
                     Err(err) => {
                         eprintln!("Error writing file in pak {file_name:?}! Error: {err}");
@@ -233,10 +470,146 @@ fn main() {
             }
         }
     }
-    println!(
-        "unreal_pak_cli took {:?} seconds...",
-        start.elapsed().unwrap().as_secs_f32()
-    )
+
+    if print_time {
+        println!(
+            "unreal_pak_cli took {:?} seconds...",
+            start.elapsed().unwrap().as_secs_f32()
+        )
+    }
+}
+
+/// A file which will be written to a .pak file
+struct PakFile {
+    /// OS path of the file
+    source: PathBuf,
+    /// Name of the entry in the .pak file
+    name: String,
+    /// Overrides if the file is compressed
+    compress: Option<bool>,
+}
+
+fn parse_pak_version(version: &str) -> Result<PakVersion, String> {
+    match version.to_lowercase().as_str() {
+        "8a" => return Ok(PakVersion::FnameBasedCompressionMethodInitial),
+        "8b" => return Ok(PakVersion::FnameBasedCompressionMethod),
+        _ => (),
+    }
+
+    match version.parse::<u32>().map(PakVersion::from_num) {
+        Ok(PakVersion::Invalid) | Err(_) => Err(format!("invalid pak version {version:?}")),
+        Ok(pak_version) => Ok(pak_version),
+    }
+}
+
+/// Get all files in a directory and their names relative to it
+fn walk_dir(indir: &Path) -> Vec<PakFile> {
+    let indir_len = indir.components().count();
+
+    WalkDir::new(indir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| {
+            // file_path is the OS absolute path, file_name is the folders and file name written to the pak
+            let file_path = entry.path();
+            let mut components = file_path.components();
+            for _ in 0..indir_len {
+                components.next();
+            }
+
+            let mut file_name = components.as_path().to_string_lossy().replace('\\', "/");
+            if file_name.starts_with('/') {
+                file_name = file_name[1..].to_owned();
+            }
+
+            PakFile {
+                source: file_path.to_path_buf(),
+                name: file_name,
+                compress: None,
+            }
+        })
+        .collect()
+}
+
+/// Read a response file listing the files to write, similar to the filelists of UnrealPak
+fn read_response_file(path: &Path, indir: &Path, mount_point: &str) -> Vec<PakFile> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) => {
+            eprintln!("Error reading response file {path:?}! Error: {err}");
+            exit(1);
+        }
+    };
+
+    let mut files = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut args = split_response_line(line).into_iter();
+        let (Some(source), Some(name)) = (args.next(), args.next()) else {
+            eprintln!(
+                "Expected a source and a pak path in line {} of the response file!",
+                i + 1
+            );
+            exit(1);
+        };
+
+        let compress = match args.next().as_deref() {
+            None => None,
+            Some("-compress") => Some(true),
+            Some("-nocompress") => Some(false),
+            Some(arg) => {
+                eprintln!(
+                    "Unknown option {arg:?} in line {} of the response file!",
+                    i + 1
+                );
+                exit(1);
+            }
+        };
+
+        // UnrealPak filelists usually contain the mount point in the pak path
+        let name = name.replace('\\', "/");
+        let name = name
+            .strip_prefix(mount_point)
+            .unwrap_or(&name)
+            .trim_start_matches('/')
+            .to_owned();
+
+        files.push(PakFile {
+            source: indir.join(source),
+            name,
+            compress,
+        });
+    }
+
+    files
+}
+
+/// Split a line of a response file into its arguments, arguments containing spaces have to be quoted
+fn split_response_line(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut arg = String::new();
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !arg.is_empty() {
+                    args.push(std::mem::take(&mut arg));
+                }
+            }
+            c => arg.push(c),
+        }
+    }
+    if !arg.is_empty() {
+        args.push(arg);
+    }
+
+    args
 }
 
 fn open_file(path: &Path) -> BufReader<File> {
@@ -249,13 +622,276 @@ fn open_file(path: &Path) -> BufReader<File> {
     }
 }
 
-fn check_header(pak: &mut PakReader<BufReader<File>>) {
-    match pak.load_index() {
-        Ok(_) => println!("Header is ok"),
+fn open_pak(path: &Path, aes_key: Option<String>) -> PakReader<BufReader<File>> {
+    let file = open_file(path);
+    match aes_key {
+        Some(aes_key) => match AesKey::from_str(&aes_key) {
+            Ok(key) => PakReader::new_encrypted(file, key),
+            Err(err) => {
+                eprintln!("Could not parse AES key! Error: {err}");
+                exit(1);
+            }
+        },
+        None => PakReader::new(file),
+    }
+}
+
+fn extract_entry<R: Read + Seek>(
+    i: usize,
+    file_name: &String,
+    entry: Result<PakEntryReader<R>, PakError>,
+    path: &Path,
+) {
+    let mut entry = match entry {
+        Ok(entry) => entry,
         Err(err) => {
-            eprintln!("Error reading header! Error: {err}");
+            eprintln!("Error reading record {i}: {file_name:?}! Error: {err}");
+            exit(1);
+        }
+    };
+
+    let dir_path = match path.parent() {
+        Some(dir) => dir,
+        None => {
+            eprintln!("No parent directories found! {i}: {file_name:?}");
             exit(1);
         }
+    };
+
+    // Create the parent directories, then files.
+    if let Err(err) = std::fs::create_dir_all(dir_path) {
+        eprintln!("Error creating directories {dir_path:?}! Error: {err}");
+        exit(1);
+    }
+
+    // Create the file
+    let mut file = match File::create(path) {
+        Ok(file) => file,
+        Err(err) => {
+            eprintln!("Error creating file {i}: {path:?}! Error: {err}");
+            exit(1);
+        }
+    };
+
+    // Write the file
+    match io::copy(&mut entry, &mut file) {
+        Ok(_) => println!("Record {i}: {file_name}"),
+        Err(err) => {
+            eprintln!("Error writing to file {i}: {path:?}! Error: {err}");
+            exit(1);
+        }
+    }
+}
+
+fn parse_patterns(patterns: &[String]) -> Vec<Pattern> {
+    patterns
+        .iter()
+        .map(|pattern| match Pattern::new(pattern) {
+            Ok(pattern) => pattern,
+            Err(err) => {
+                eprintln!("Invalid pattern {pattern:?}! Error: {err}");
+                exit(1);
+            }
+        })
+        .collect()
+}
+
+fn load_index(pak: &mut PakReader<BufReader<File>>) {
+    if let Err(err) = pak.load_index() {
+        eprintln!("Error reading header! Error: {err}");
+        exit(1);
     }
+}
+
+fn check_header(pak: &mut PakReader<BufReader<File>>) {
+    load_index(pak);
+    println!("Header is ok");
     println!("Found {:?} records", pak.get_entry_names().len());
 }
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|e| format!("{e:02x}")).collect()
+}
+
+fn print_info(pak: &PakReader<BufReader<File>>, json: bool) {
+    let pak_version = pak.get_pak_version();
+    let compression_methods = pak
+        .get_compression_methods()
+        .iter()
+        .map(|e| e.name().unwrap_or("Unknown").to_owned())
+        .collect::<Vec<_>>();
+    let encryption_key_guid = pak.get_encryption_key_guid().map(|e| to_hex(&e));
+    let entries = pak.entries();
+
+    let size = entries.iter().map(|e| e.decompressed_size).sum::<u64>();
+    let compressed_size = entries.iter().map(|e| e.compressed_size).sum::<u64>();
+    let encrypted_entries = entries.iter().filter(|e| e.encrypted).count();
+    let deleted_entries = pak.get_deleted_entry_names().len();
+
+    if json {
+        let info = serde_json::json!({
+            "pak_version": pak_version.to_num(),
+            "pak_version_name": format!("{pak_version:?}"),
+            "mount_point": pak.mount_point,
+            "compression_methods": compression_methods,
+            "index_encrypted": pak.is_index_encrypted(),
+            "encryption_key_guid": encryption_key_guid,
+            "entry_count": entries.len(),
+            "encrypted_entry_count": encrypted_entries,
+            "deleted_entry_count": deleted_entries,
+            "size": size,
+            "compressed_size": compressed_size,
+        });
+        println!("{info:#}");
+        return;
+    }
+
+    println!("Pak version: {} ({pak_version:?})", pak_version.to_num());
+    println!("Mount point: {}", pak.mount_point);
+    println!(
+        "Compression methods: {}",
+        match compression_methods.is_empty() {
+            true => "None".to_owned(),
+            false => compression_methods.join(", "),
+        }
+    );
+    println!("Index encrypted: {}", pak.is_index_encrypted());
+    println!(
+        "Encryption key GUID: {}",
+        encryption_key_guid.as_deref().unwrap_or("None")
+    );
+    println!("Entries: {} ({encrypted_entries} encrypted)", entries.len());
+    println!("Delete records: {deleted_entries}");
+    println!("Size: {size} bytes ({compressed_size} bytes compressed)");
+}
+
+fn print_diff(diff: &PakDiff, json: bool) {
+    if json {
+        let diff = serde_json::json!({
+            "added": diff.added,
+            "removed": diff.removed,
+            "modified": diff.modified,
+        });
+        println!("{diff:#}");
+        return;
+    }
+
+    let mut rows = diff
+        .added
+        .iter()
+        .map(|e| ('A', e))
+        .chain(diff.removed.iter().map(|e| ('D', e)))
+        .chain(diff.modified.iter().map(|e| ('M', e)))
+        .collect::<Vec<_>>();
+    rows.sort_by_key(|(_, name)| *name);
+
+    for (status, name) in rows {
+        println!("{status} {name}");
+    }
+    println!(
+        "{} added, {} removed, {} modified",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.modified.len()
+    );
+}
+
+fn write_patch(diff: &PakDiff, new: &mut PakReader<BufReader<File>>, path: &Path, compress: bool) {
+    println!("Creating patch {path:?}");
+
+    let file = match OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(path)
+    {
+        Ok(file) => file,
+        Err(err) => {
+            eprintln!("Error creating file {path:?}! Error: {err}");
+            exit(1);
+        }
+    };
+
+    let pak_version = new.get_pak_version();
+    let mut pak = PakWriter::new(BufWriter::new(file), pak_version);
+    pak.mount_point = new.mount_point.clone();
+
+    // removed records can only be deleted by patches which support delete records
+    let delete_removed = pak_version >= PakVersion::DeleteRecords;
+    if !delete_removed && !diff.removed.is_empty() {
+        println!(
+            "Pak version {pak_version:?} does not support delete records, removed records are kept"
+        );
+    }
+
+    if let Err(err) = diff.write_patch(new, &mut pak, compress, delete_removed) {
+        eprintln!("Error writing patch! Error: {err}");
+        exit(1);
+    }
+
+    match pak.finish_write() {
+        Ok(_) => println!(
+            "Wrote {} records to the patch",
+            diff.get_changed_entry_names().len()
+        ),
+        Err(err) => {
+            eprintln!("Error writing pak index or footer! Error: {err}");
+            exit(1);
+        }
+    }
+}
+
+fn print_list(pak: &mut PakReader<BufReader<File>>, json: bool) {
+    let entries = pak.entries();
+
+    let mut rows = Vec::with_capacity(entries.len());
+    for entry in entries {
+        // newer pak versions don't store the hash in the index
+        let hash = match entry.hash {
+            Some(hash) => hash,
+            None => match pak.read_entry_hash(&entry.name) {
+                Ok(hash) => hash,
+                Err(err) => {
+                    eprintln!("Error reading record {:?}! Error: {err}", entry.name);
+                    exit(1);
+                }
+            },
+        };
+        rows.push((entry, to_hex(&hash)));
+    }
+
+    if json {
+        let entries = rows
+            .iter()
+            .map(|(entry, hash)| {
+                serde_json::json!({
+                    "name": entry.name,
+                    "offset": entry.offset,
+                    "size": entry.decompressed_size,
+                    "compressed_size": entry.compressed_size,
+                    "compression": entry.compression.name().unwrap_or("Unknown"),
+                    "compression_block_count": entry.compression_block_count,
+                    "compression_block_size": entry.compression_block_size,
+                    "encrypted": entry.encrypted,
+                    "hash": hash,
+                })
+            })
+            .collect::<Vec<_>>();
+        println!("{:#}", serde_json::Value::Array(entries));
+        return;
+    }
+
+    println!(
+        "{:>12} {:>12} {:<8} {:<40} Name",
+        "Size", "Compressed", "Method", "SHA-1"
+    );
+    for (entry, hash) in &rows {
+        println!(
+            "{:>12} {:>12} {:<8} {hash:<40} {}",
+            entry.decompressed_size,
+            entry.compressed_size,
+            entry.compression.name().unwrap_or("Unknown"),
+            entry.name
+        );
+    }
+}